@@ -0,0 +1,22 @@
+use tonic_build::manual::{Builder, Method, Service};
+
+// Jito ShredStream 代理的 gRPC 服务，消息类型在 src/source/shredstream.rs 中用 prost 手写，
+// 这里只生成 client/server，不需要 protoc
+// https://github.com/jito-labs/mev-protos/blob/master/shredstream.proto
+fn main() {
+    let shredstream = Service::builder()
+        .name("ShredstreamProxy")
+        .package("shredstream")
+        .method(
+            Method::builder()
+                .name("subscribe_entries")
+                .route_name("SubscribeEntries")
+                .input_type("crate::source::shredstream::SubscribeEntriesRequest")
+                .output_type("crate::source::shredstream::Entry")
+                .codec_path("tonic::codec::ProstCodec")
+                .server_streaming()
+                .build(),
+        )
+        .build();
+    Builder::new().compile(&[shredstream]);
+}
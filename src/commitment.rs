@@ -0,0 +1,272 @@
+use std::collections::{BTreeMap, HashSet};
+
+use solana_sdk::signature::Signature;
+
+/// Status of a slot as reported by the cluster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotStatus {
+    Processed,
+    Confirmed,
+    Finalized,
+    /// The slot failed to replay and will never be confirmed
+    Dead,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotUpdate {
+    pub slot: u64,
+    pub parent: Option<u64>,
+    pub status: SlotStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitmentStatus {
+    Confirmed,
+    Finalized,
+    /// The slot was skipped or died, the transaction never landed there
+    Dropped,
+    /// A later slot was finalized but the ancestry down to this slot was never
+    /// seen, so whether the transaction landed is unknown
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitmentEvent {
+    pub signature: Signature,
+    pub slot: u64,
+    pub status: CommitmentStatus,
+}
+
+/// Follows transactions observed at `Processed` until they are finalized or dropped.
+///
+/// Finalized slots form a single chain. Once a slot is finalized, the pending
+/// slots below it on its ancestry, followed through the parents of earlier slot
+/// updates, were finalized with it and the others were skipped on a fork.
+#[derive(Debug, Default)]
+pub struct CommitmentTracker {
+    pending: BTreeMap<u64, Vec<Signature>>,
+    // slot -> parent，只保留最后一个 finalized slot 之后的
+    parents: BTreeMap<u64, u64>,
+    last_finalized: Option<u64>,
+}
+
+impl CommitmentTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts following `signature`, observed in `slot`.
+    pub fn track(&mut self, signature: Signature, slot: u64) {
+        // 已经 finalized 的 slot 不会再收到状态更新
+        if self
+            .last_finalized
+            .is_some_and(|finalized| slot <= finalized)
+        {
+            return;
+        }
+        let signatures = self.pending.entry(slot).or_default();
+        if !signatures.contains(&signature) {
+            signatures.push(signature);
+        }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.values().map(Vec::len).sum()
+    }
+
+    /// Applies a slot update, returning the status changes of tracked transactions.
+    pub fn on_slot(&mut self, update: SlotUpdate) -> Vec<CommitmentEvent> {
+        let mut events = Vec::new();
+        if let Some(parent) = update.parent
+            && self
+                .last_finalized
+                .is_none_or(|finalized| update.slot > finalized)
+        {
+            self.parents.insert(update.slot, parent);
+        }
+        match update.status {
+            SlotStatus::Processed => {}
+            SlotStatus::Confirmed => {
+                if let Some(signatures) = self.pending.get(&update.slot) {
+                    events.extend(events_for(
+                        update.slot,
+                        signatures,
+                        CommitmentStatus::Confirmed,
+                    ));
+                }
+            }
+            SlotStatus::Finalized => {
+                // 错过了某个 slot 的 Finalized 更新时（重连、root 跳跃、端点落后），
+                // 它如果在新 root 的祖先链上就已经 finalized，否则在分叉上被跳过
+                let lowest_pending = self.pending.keys().next().copied();
+                let mut ancestors = HashSet::new();
+                let mut lowest = update.slot;
+                while lowest_pending.is_some_and(|pending| lowest > pending)
+                    && let Some(parent) = self.parents.get(&lowest)
+                {
+                    ancestors.insert(*parent);
+                    lowest = *parent;
+                }
+                let resolved: Vec<_> = self
+                    .pending
+                    .range(..update.slot)
+                    .map(|(slot, _)| *slot)
+                    .collect();
+                for slot in resolved {
+                    let signatures = self.pending.remove(&slot).unwrap();
+                    // 祖先链在 lowest 处中断，更低的 slot 无法判断
+                    let status = if ancestors.contains(&slot) {
+                        CommitmentStatus::Finalized
+                    } else if slot > lowest {
+                        CommitmentStatus::Dropped
+                    } else {
+                        CommitmentStatus::Unknown
+                    };
+                    events.extend(events_for(slot, &signatures, status));
+                }
+                if let Some(signatures) = self.pending.remove(&update.slot) {
+                    events.extend(events_for(
+                        update.slot,
+                        &signatures,
+                        CommitmentStatus::Finalized,
+                    ));
+                }
+                // 多个端点时落后的端点可能晚到，不能回退
+                self.last_finalized = self.last_finalized.max(Some(update.slot));
+                if let Some(finalized) = self.last_finalized {
+                    self.parents = self.parents.split_off(&finalized);
+                }
+            }
+            SlotStatus::Dead => {
+                if let Some(signatures) = self.pending.remove(&update.slot) {
+                    events.extend(events_for(
+                        update.slot,
+                        &signatures,
+                        CommitmentStatus::Dropped,
+                    ));
+                }
+            }
+        }
+        events
+    }
+}
+
+fn events_for(
+    slot: u64,
+    signatures: &[Signature],
+    status: CommitmentStatus,
+) -> impl Iterator<Item = CommitmentEvent> + '_ {
+    signatures.iter().map(move |signature| CommitmentEvent {
+        signature: *signature,
+        slot,
+        status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(slot: u64, parent: Option<u64>, status: SlotStatus) -> SlotUpdate {
+        SlotUpdate {
+            slot,
+            parent,
+            status,
+        }
+    }
+
+    #[test]
+    fn test_commitment_tracker() {
+        let landed = Signature::from([1; 64]);
+        let forked = Signature::from([2; 64]);
+        let dead = Signature::from([3; 64]);
+        let missed = Signature::from([5; 64]);
+
+        let mut tracker = CommitmentTracker::new();
+        tracker.track(missed, 6);
+        tracker.track(landed, 10);
+        tracker.track(landed, 10);
+        tracker.track(forked, 11);
+        tracker.track(dead, 13);
+        assert_eq!(tracker.pending(), 4);
+
+        assert!(
+            tracker
+                .on_slot(update(10, Some(9), SlotStatus::Processed))
+                .is_empty()
+        );
+        let events = tracker.on_slot(update(10, Some(9), SlotStatus::Confirmed));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].status, CommitmentStatus::Confirmed);
+
+        // 没见过 9 的 parent，6 是否上链无法判断，报告为 Unknown 而不是一直挂起
+        let events = tracker.on_slot(update(10, Some(9), SlotStatus::Finalized));
+        assert_eq!(
+            events,
+            vec![
+                CommitmentEvent {
+                    signature: missed,
+                    slot: 6,
+                    status: CommitmentStatus::Unknown,
+                },
+                CommitmentEvent {
+                    signature: landed,
+                    slot: 10,
+                    status: CommitmentStatus::Finalized,
+                }
+            ]
+        );
+
+        // 12 的 parent 是 10，说明 11 在分叉上被跳过
+        let events = tracker.on_slot(update(12, Some(10), SlotStatus::Finalized));
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            (events[0].signature, events[0].status),
+            (forked, CommitmentStatus::Dropped)
+        );
+
+        let events = tracker.on_slot(update(13, Some(12), SlotStatus::Dead));
+        assert_eq!(
+            (events[0].signature, events[0].status),
+            (dead, CommitmentStatus::Dropped)
+        );
+        assert_eq!(tracker.pending(), 0);
+
+        // finalized 之前的 slot 不再跟踪
+        tracker.track(Signature::from([4; 64]), 12);
+        assert_eq!(tracker.pending(), 0);
+    }
+
+    #[test]
+    fn test_root_jumps_over_ancestor() {
+        let ancestor = Signature::from([1; 64]);
+        let forked = Signature::from([2; 64]);
+
+        let mut tracker = CommitmentTracker::new();
+        tracker.track(ancestor, 20);
+        tracker.track(forked, 21);
+        // 21 在分叉上，22 的 parent 是 20
+        for (slot, parent) in [(20, 19), (21, 20), (22, 20), (23, 22)] {
+            tracker.on_slot(update(slot, Some(parent), SlotStatus::Processed));
+        }
+
+        // 错过了 20 的 Finalized 更新，root 直接跳到 23
+        let events = tracker.on_slot(update(23, Some(22), SlotStatus::Finalized));
+        assert_eq!(
+            events,
+            vec![
+                CommitmentEvent {
+                    signature: ancestor,
+                    slot: 20,
+                    status: CommitmentStatus::Finalized,
+                },
+                CommitmentEvent {
+                    signature: forked,
+                    slot: 21,
+                    status: CommitmentStatus::Dropped,
+                },
+            ]
+        );
+        assert_eq!(tracker.pending(), 0);
+    }
+}
@@ -1,4 +1,5 @@
 use once_cell::sync::Lazy;
+use solana_sdk::pubkey::Pubkey;
 use std::env;
 
 pub static GRPC_ENDPOINT: Lazy<String> = Lazy::new(|| {
@@ -7,12 +8,182 @@ pub static GRPC_ENDPOINT: Lazy<String> = Lazy::new(|| {
     })
 });
 
+// 多个 grpc 端点用逗号分隔，同时订阅并按签名去重；未设置时只用 GRPC_ENDPOINT
+pub static GRPC_ENDPOINTS: Lazy<Vec<String>> = Lazy::new(|| match env::var("GRPC_ENDPOINTS") {
+    Ok(endpoints) => endpoints
+        .split(',')
+        .map(|endpoint| endpoint.trim().to_string())
+        .filter(|endpoint| !endpoint.is_empty())
+        .collect(),
+    Err(_) => vec![GRPC_ENDPOINT.clone()],
+});
+
+// 付费 grpc 服务商要求的 x-token
+pub static GRPC_X_TOKEN: Lazy<Option<String>> = Lazy::new(|| env::var("GRPC_X_TOKEN").ok());
+
+// grpc 客户端调优，时间用 humantime 格式，如 10s、500ms，由 GeyserClientConfig::from_env 解析
+pub static GRPC_CONNECT_TIMEOUT: Lazy<Option<String>> =
+    Lazy::new(|| env::var("GRPC_CONNECT_TIMEOUT").ok());
+
+pub static GRPC_TIMEOUT: Lazy<Option<String>> = Lazy::new(|| env::var("GRPC_TIMEOUT").ok());
+
+pub static GRPC_MAX_DECODING_MESSAGE_SIZE: Lazy<Option<String>> =
+    Lazy::new(|| env::var("GRPC_MAX_DECODING_MESSAGE_SIZE").ok());
+
+pub static GRPC_KEEPALIVE_INTERVAL: Lazy<Option<String>> =
+    Lazy::new(|| env::var("GRPC_KEEPALIVE_INTERVAL").ok());
+
+pub static GRPC_KEEPALIVE_TIMEOUT: Lazy<Option<String>> =
+    Lazy::new(|| env::var("GRPC_KEEPALIVE_TIMEOUT").ok());
+
+// gzip 或 zstd，不设置则不压缩
+pub static GRPC_COMPRESSION: Lazy<Option<String>> = Lazy::new(|| env::var("GRPC_COMPRESSION").ok());
+
 pub static JITO_RPC_ENDPOINT: Lazy<String> = Lazy::new(|| {
     env::var("JITO_RPC_ENDPOINT")
         .unwrap_or_else(|_| "https://ny.testnet.block-engine.jito.wtf/api/v1".to_string())
 });
 
+// 交易来源：grpc（默认）、websocket、shredstream、replay 或 recording
+pub static TRANSACTION_SOURCE: Lazy<String> =
+    Lazy::new(|| env::var("TRANSACTION_SOURCE").unwrap_or_else(|_| "grpc".to_string()));
+
+pub static RPC_ENDPOINT: Lazy<String> = Lazy::new(|| {
+    env::var("RPC_ENDPOINT").unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string())
+});
+
+pub static WS_ENDPOINT: Lazy<String> = Lazy::new(|| {
+    env::var("WS_ENDPOINT").unwrap_or_else(|_| "wss://api.mainnet-beta.solana.com".to_string())
+});
+
+// Jito ShredStream 代理的 grpc 地址
+pub static SHREDSTREAM_ENDPOINT: Lazy<String> = Lazy::new(|| {
+    env::var("SHREDSTREAM_ENDPOINT").unwrap_or_else(|_| "http://127.0.0.1:9999".to_string())
+});
+
+pub static REPLAY_FILE: Lazy<String> =
+    Lazy::new(|| env::var("REPLAY_FILE").unwrap_or_else(|_| "transactions.jsonl".to_string()));
+
+// 设置后把收到的每条 grpc 推送录制到该文件，用 TRANSACTION_SOURCE=recording 回放
+pub static RECORD_FILE: Lazy<Option<String>> = Lazy::new(|| env::var("RECORD_FILE").ok());
+
+// 回放速度倍数，1 为原速，不设置则尽快回放
+pub static REPLAY_SPEED: Lazy<Option<f64>> =
+    Lazy::new(|| env::var("REPLAY_SPEED").ok().and_then(|v| v.parse().ok()));
+
+// 跟踪状态的账户（Raydium AMM、pump.fun bonding curve），逗号分隔；AMM 的 vault 会自动订阅
+pub static STATE_ACCOUNTS: Lazy<Vec<String>> = Lazy::new(|| {
+    env::var("STATE_ACCOUNTS")
+        .map(|accounts| {
+            accounts
+                .split(',')
+                .map(|account| account.trim().to_string())
+                .filter(|account| !account.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+});
+
+// 解码 worker 数量，默认为 CPU 核数
+pub static DECODER_WORKERS: Lazy<usize> = Lazy::new(|| {
+    env::var("DECODER_WORKERS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        })
+});
+
+// 各处理阶段之间队列的容量
+pub static PIPELINE_QUEUE_CAPACITY: Lazy<usize> = Lazy::new(|| {
+    env::var("PIPELINE_QUEUE_CAPACITY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1024)
+});
+
+// Anchor IDL 所在目录，其中每个 *.json 对应一个程序，没有 DexDecoder 的程序用它解码
+pub static IDL_DIR: Lazy<Option<String>> = Lazy::new(|| env::var("IDL_DIR").ok());
+
+// 并行运行的策略，逗号分隔，见 StrategyRegistry::from_names
+pub static STRATEGIES: Lazy<Vec<String>> = Lazy::new(|| {
+    env::var("STRATEGIES")
+        .unwrap_or_else(|_| "raydium_backrun".to_string())
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+});
+
+// 跟单 bundle 的 jito 小费 (lamports)
+pub static BACKRUN_TIP_LAMPORTS: Lazy<u64> = Lazy::new(|| {
+    env::var("BACKRUN_TIP_LAMPORTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(12345)
+});
+
+// 事件总线每个订阅者可积压的事件数，落后更多的订阅者会丢失最旧的事件
+pub static EVENT_BUS_CAPACITY: Lazy<usize> = Lazy::new(|| {
+    env::var("EVENT_BUS_CAPACITY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(4096)
+});
+
+// 解码队列满时的策略：drop 丢弃新交易（默认，不阻塞接收和 ping），block 等待
+pub static PIPELINE_QUEUE_POLICY: Lazy<String> =
+    Lazy::new(|| env::var("PIPELINE_QUEUE_POLICY").unwrap_or_else(|_| "drop".to_string()));
+
 pub static KEYPAIR_FILE: Lazy<String> = Lazy::new(|| env::var("KEYPAIR_FILE").unwrap());
 
-pub static PUMP_FUN_ID: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
-pub static RAYDIUM_AAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+// 是否订阅失败的交易，用于分析失败原因（滑点等），默认订阅
+pub static INCLUDE_FAILED_TRANSACTIONS: Lazy<bool> = Lazy::new(|| {
+    env::var("INCLUDE_FAILED_TRANSACTIONS")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(true)
+});
+
+pub const PUMP_FUN_ID: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+pub const RAYDIUM_AAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+pub const ORCA_WHIRLPOOL_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+pub const METEORA_DLMM_ID: &str = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo";
+pub const METEORA_DAMM_ID: &str = "Eo7WjKq67rjJQSZxS6z3YkapzY3eMj6Xy8X5EQVn5UaB";
+pub const RAYDIUM_LAUNCHLAB_ID: &str = "LanMV9sAd7wArD4vJFi2qDdfnVhFxYSUg6eADduJ3uj";
+pub const JUPITER_V6_ID: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+pub const OPENBOOK_ID: &str = "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX";
+pub const PHOENIX_ID: &str = "PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY";
+pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
+pub const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+pub const COMPUTE_BUDGET_PROGRAM_ID: &str = "ComputeBudget111111111111111111111111111111";
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+
+// 解码时直接比较的 program id，避免每次解析字符串
+pub const PUMP_FUN_PROGRAM: Pubkey = Pubkey::from_str_const(PUMP_FUN_ID);
+pub const RAYDIUM_AAM_PROGRAM: Pubkey = Pubkey::from_str_const(RAYDIUM_AAM_ID);
+pub const ORCA_WHIRLPOOL_PROGRAM: Pubkey = Pubkey::from_str_const(ORCA_WHIRLPOOL_ID);
+pub const METEORA_DLMM_PROGRAM: Pubkey = Pubkey::from_str_const(METEORA_DLMM_ID);
+pub const METEORA_DAMM_PROGRAM: Pubkey = Pubkey::from_str_const(METEORA_DAMM_ID);
+pub const RAYDIUM_LAUNCHLAB_PROGRAM: Pubkey = Pubkey::from_str_const(RAYDIUM_LAUNCHLAB_ID);
+pub const JUPITER_V6_PROGRAM: Pubkey = Pubkey::from_str_const(JUPITER_V6_ID);
+pub const OPENBOOK_PROGRAM: Pubkey = Pubkey::from_str_const(OPENBOOK_ID);
+pub const PHOENIX_PROGRAM: Pubkey = Pubkey::from_str_const(PHOENIX_ID);
+pub const TOKEN_PROGRAM: Pubkey = Pubkey::from_str_const(TOKEN_PROGRAM_ID);
+pub const TOKEN_2022_PROGRAM: Pubkey = Pubkey::from_str_const(TOKEN_2022_PROGRAM_ID);
+pub const COMPUTE_BUDGET_PROGRAM: Pubkey = Pubkey::from_str_const(COMPUTE_BUDGET_PROGRAM_ID);
+pub const ASSOCIATED_TOKEN_PROGRAM: Pubkey = Pubkey::from_str_const(ASSOCIATED_TOKEN_PROGRAM_ID);
+pub const WSOL_MINT_PUBKEY: Pubkey = Pubkey::from_str_const(WSOL_MINT);
+// JitoJsonRpcSDK::get_random_tip_account 从这 8 个账户中随机选择
+pub const JITO_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
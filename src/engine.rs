@@ -1,333 +1,755 @@
-use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use anyhow::{Ok, Result, anyhow};
 use base64::Engine as _;
-use futures_util::SinkExt;
 use futures_util::StreamExt;
 use jito_sdk_rust::JitoJsonRpcSDK;
-use log::{debug, info};
+use log::{debug, info, warn};
 use serde_json::json;
-use solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Signature, signer::Signer};
-use solana_sdk::{
-    message::{
-        MessageHeader, VersionedMessage,
-        v0::{Message as V0Message, MessageAddressTableLookup},
-    },
-    transaction::VersionedTransaction,
-};
-use solana_transaction_status::{UiTransactionEncoding, option_serializer::OptionSerializer};
-use yellowstone_grpc_client::{ClientTlsConfig, GeyserGrpcClient};
-use yellowstone_grpc_proto::convert_from;
-use yellowstone_grpc_proto::geyser::CommitmentLevel;
-use yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof;
-use yellowstone_grpc_proto::geyser::{SubscribeRequest, SubscribeRequestFilterTransactions};
-
-use crate::raydium;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+use tokio::sync::watch;
+
+use crate::commitment::{CommitmentStatus, CommitmentTracker, SlotUpdate};
+use crate::decoder::{DexDecoder, Venue};
+use crate::events::{BundleResult, BundleStatus, Event, EventBus};
+use crate::idl::IdlRegistry;
+use crate::pipeline::{self, QueuePolicy, QueueReceiver, QueueSender, QueueStats};
+use crate::priority_fee::PriorityFeeEstimator;
+use crate::raydium::SwapInstructionBaseIn;
+use crate::source::geyser::{GeyserClientConfig, GeyserSource, WatchedAccounts};
+use crate::source::redundant::RedundantSource;
+use crate::source::{ObservedTransaction, TransactionSource};
+use crate::state::{AccountUpdate, StateStore, state_accounts};
+use crate::strategy::{Action, Context, StrategyRegistry};
+use crate::tip::TipStats;
+use crate::transaction::DecodedTransaction;
 use crate::{constants, utils};
+use crate::{launchlab, meteora, phoenix, pumpfun, raydium, whirlpool};
+
+const STATS_INTERVAL: Duration = Duration::from_secs(60);
+// 跟单交易过时就没有意义，队列不需要很长
+const SUBMIT_QUEUE_CAPACITY: usize = 16;
+// 每次输出小费统计时列出的池子和钱包数
+const TOP_TIPPED: usize = 5;
+// blockhash 约一分钟后过期，刷新间隔要远小于它
+const BLOCKHASH_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+// 解码 worker 交给策略阶段的交易
+struct DecodedItem {
+    observed: ObservedTransaction,
+    decoded: DecodedTransaction,
+}
+
+// 策略阶段交给提交阶段的动作，连同它引用的交易
+struct Submission {
+    strategy: &'static str,
+    action: Action,
+    target: VersionedTransaction,
+}
+
+impl Submission {
+    // 只能跟正在处理的那笔交易，其他签名没有可以放进 bundle 的交易
+    fn new(strategy: &'static str, action: Action, observed: &ObservedTransaction) -> Option<Self> {
+        let target = match &action {
+            Action::Backrun { target, .. } => *target,
+            // swap 不跟在目标交易之后，只记下触发它的交易
+            Action::Buy { .. } | Action::Sell { .. } => observed.signature,
+            Action::Ignore => return None,
+        };
+        if target != observed.signature {
+            warn!(
+                "strategy {}: backrun target {} is not the observed transaction",
+                strategy, target
+            );
+            return None;
+        }
+        Some(Submission {
+            strategy,
+            action,
+            target: observed.transaction.clone(),
+        })
+    }
+
+    fn signature(&self) -> Signature {
+        self.target.signatures.first().copied().unwrap_or_default()
+    }
+}
 
 pub struct Engine {
     pub jito_sdk: JitoJsonRpcSDK,
+    pub decoders: Vec<Box<dyn DexDecoder>>,
+    /// IDLs of programs without a decoder, loaded from `IDL_DIR`
+    pub idls: IdlRegistry,
+    /// Priority fees observed on the stream, for pricing our own transactions
+    pub priority_fees: Mutex<PriorityFeeEstimator>,
+    /// Jito tips seen on the stream per pool and signer, logged every `STATS_INTERVAL`
+    pub tips: Mutex<TipStats>,
+    /// Accounts the Geyser subscriptions follow, changeable while `run` is running
+    pub watched: WatchedAccounts,
+    /// Swaps we may have acted on, followed until they are finalized or dropped
+    pub commitment: Mutex<CommitmentTracker>,
+    /// Latest state of pools, vaults and bonding curves for quoting and building swaps
+    pub state: Mutex<StateStore>,
+    /// Accounts followed into `state`, the vaults of AMMs are added as they are decoded
+    pub state_accounts: WatchedAccounts,
+    /// Decoded transactions, swaps, new pools, bundles and slots, for every consumer
+    pub events: EventBus,
+    /// Strategies deciding what to do with each decoded transaction, see `constants::STRATEGIES`
+    pub strategies: Mutex<StrategyRegistry>,
 }
 
 impl Engine {
-    pub async fn new() -> Self {
-        Engine {
+    pub async fn new() -> Result<Self> {
+        Ok(Engine {
             jito_sdk: JitoJsonRpcSDK::new(&constants::JITO_RPC_ENDPOINT.clone(), None),
-        }
+            // jupiter 只是路由，实际成交由下面各 DEX 的指令体现，路由本身在 DecodedTransaction::jupiter_routes 中解码
+            decoders: vec![
+                Box::new(raydium::RaydiumAmmDecoder),
+                Box::new(pumpfun::PumpfunDecoder),
+                Box::new(whirlpool::WhirlpoolDecoder),
+                Box::new(meteora::DlmmDecoder),
+                Box::new(meteora::DammDecoder),
+                Box::new(launchlab::LaunchlabDecoder),
+                Box::new(phoenix::PhoenixDecoder),
+            ],
+            idls: match constants::IDL_DIR.as_ref() {
+                Some(dir) => IdlRegistry::from_dir(dir)?,
+                None => IdlRegistry::default(),
+            },
+            priority_fees: Mutex::new(PriorityFeeEstimator::default()),
+            tips: Mutex::new(TipStats::default()),
+            watched: WatchedAccounts::new(watched_accounts()),
+            commitment: Mutex::new(CommitmentTracker::new()),
+            state: Mutex::new(StateStore::new()),
+            state_accounts: WatchedAccounts::new(state_accounts()),
+            events: EventBus::new(*constants::EVENT_BUS_CAPACITY),
+            strategies: Mutex::new(StrategyRegistry::from_names(&constants::STRATEGIES)?),
+        })
     }
 
-    pub async fn run(&self) -> Result<()> {
+    /// Runs on the Yellowstone gRPC subscriptions configured by `GRPC_ENDPOINTS`,
+    /// de-duplicated by signature when there is more than one. The client is
+    /// tuned by the other `GRPC_*` settings, see `GeyserClientConfig::from_env`.
+    pub async fn run(self: &Arc<Self>) -> Result<()> {
         // https://solana-testnet-yellowstone-grpc.publicnode.com:443
         // https://solana-yellowstone-grpc.publicnode.com:443
-        let grpc_endpoint = constants::GRPC_ENDPOINT.clone();
-        println!("GRPC_ENDPOINT = {}", grpc_endpoint);
-        let mut client = GeyserGrpcClient::build_from_shared(grpc_endpoint)?
-            .tls_config(ClientTlsConfig::new().with_native_roots())?
-            .connect()
-            .await?;
-
-        let recent_blockhash = Hash::from_str(
-            &client
-                .get_latest_blockhash(Some(CommitmentLevel::Processed))
-                .await?
-                .blockhash,
-        )
-        .unwrap();
+        let client_config = GeyserClientConfig::from_env()?;
+        let mut sources = Vec::new();
+        for endpoint in constants::GRPC_ENDPOINTS.iter() {
+            let mut source = GeyserSource::new(endpoint.clone(), Vec::new())
+                .with_client_config(client_config.clone())
+                .with_watched(self.watched.clone())
+                .with_state_accounts(self.state_accounts.clone());
+            // 多个端点时只录制第一个，避免录制文件中出现重复推送
+            if sources.is_empty()
+                && let Some(path) = constants::RECORD_FILE.as_ref()
+            {
+                source = source.record_to(path)?;
+            }
+            sources.push((endpoint.clone(), source));
+        }
+
+        if sources.len() == 1 {
+            let (_, source) = sources.pop().unwrap();
+            return self.run_with_source(source).await;
+        }
+
+        // 汇合各端点的队列与接收队列采用相同的策略
+        let policy = QueuePolicy::from_name(&constants::PIPELINE_QUEUE_POLICY)?;
+        let source =
+            RedundantSource::new(sources).with_queue(*constants::PIPELINE_QUEUE_CAPACITY, policy);
+        let stats = source.stats();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(STATS_INTERVAL);
+            loop {
+                interval.tick().await;
+                for stats in stats.lock().unwrap().iter() {
+                    info!(
+                        "endpoint {}: received {}, win rate {:.2}, average lag {:?}, max lag {:?}, errors {}, dropped {}",
+                        stats.endpoint,
+                        stats.received,
+                        stats.win_rate(),
+                        stats.average_lag(),
+                        stats.max_lag,
+                        stats.errors,
+                        stats.dropped,
+                    );
+                }
+            }
+        });
+        self.run_with_source(source).await
+    }
+
+    /// Runs the pipeline on `source`: ingestion, a pool of `DECODER_WORKERS`
+    /// decoders, strategy evaluation and bundle submission, connected by
+    /// bounded queues.
+    ///
+    /// The ingestion queue follows `PIPELINE_QUEUE_POLICY`. Decoders wait for
+    /// the strategy stage, the strategy stage never waits for submission and
+    /// drops snipes once the submission queue is full, so a slow Jito call
+    /// can't stall ingestion. Decoding in parallel means transactions may reach the
+    /// strategy stage out of order.
+    pub async fn run_with_source<S: TransactionSource + Send + 'static>(
+        self: &Arc<Self>,
+        mut source: S,
+    ) -> Result<()> {
+        // 回放等离线来源没有 blockhash，此时只解码不跟单
+        let recent_blockhash = source.latest_blockhash().await?;
+        if recent_blockhash.is_none() {
+            info!("no recent blockhash, sniping disabled");
+        }
 
         // 支付钱包
-        let sender = utils::read_keypair_file(None).unwrap();
-
-        // let (_sink, mut stream) = client.subscribe().await?;
-        let account_include = vec![
-            // main-beta
-            constants::RAYDIUM_AAM_ID.to_string(),
-            // raydium devnet
-            // "HWy1jotHpo6UqeQxx49dpYYdQB8wj9Qk9MdxwjLvDHB8".to_string(),
-        ];
-        let account_exclude = Vec::new();
-        let account_required = Vec::new();
-        println!("account_include = {:?}", account_include);
-
-        let mut transactions: HashMap<String, SubscribeRequestFilterTransactions> = HashMap::new();
-        transactions.insert(
-            "client".to_string(),
-            SubscribeRequestFilterTransactions {
-                vote: None,
-                failed: None,
-                signature: None,
-                account_include,
-                account_exclude,
-                account_required,
-            },
-        );
-        let request = SubscribeRequest {
-            transactions,
-            commitment: Some(CommitmentLevel::Processed.into()),
-            ..Default::default()
-        };
-        let (mut sink, mut stream) = client.subscribe_with_request(Some(request)).await?;
-        // let version = client.get_version().await?;
-        // println!("version = {:#?}", version);
-
-        // 处理接收到的更新
-        while let Some(message) = stream.next().await {
-            if let Some(update) = message?.update_oneof {
-                match update {
-                    // tx 类型为 SubscribeUpdateTransaction
-                    UpdateOneof::Transaction(tx) => {
-                        // tx_info 类型为 SubscribeUpdateTransactionInfo
-                        if let Some(tx_info) = tx.transaction {
-                            println!(
-                                "Signature = {:?}",
-                                bs58::encode(&tx_info.signature).into_string()
-                            );
-
-                            let allow_sniper = self.allow_sniper(tx_info.clone()).await;
-                            if allow_sniper.is_ok() {
-                                println!("allow_sniper");
-                                let bundle_result =
-                                    self.send_bundle(&tx_info, &sender, &recent_blockhash).await;
-                                if let Err(err) = bundle_result {
-                                    println!("Error sending bundle: {:?}", err);
-                                }
-                            }
-                        }
-                    }
-                    UpdateOneof::BlockMeta(meta) => {
-                        println!("BlockMeta: {:?}", meta);
-                    }
-                    UpdateOneof::Ping(v) => {
-                        println!("Ping received; {:?}", v);
-                        let _ = sink
-                            .send(yellowstone_grpc_proto::geyser::SubscribeRequest {
-                                ping: Some(yellowstone_grpc_proto::geyser::SubscribeRequestPing {
-                                    id: 1,
-                                }),
-                                ..Default::default()
-                            })
-                            .await;
+        let sender = recent_blockhash
+            .map(|_| utils::read_keypair_file(None))
+            .transpose()?;
+
+        // 不提供 slot 更新的来源无法跟踪交易是否最终上链
+        let mut slots = source
+            .slot_updates()
+            .unwrap_or_else(|| Box::pin(futures::stream::pending()));
+        let mut accounts = source
+            .account_updates()
+            .unwrap_or_else(|| Box::pin(futures::stream::pending()));
+        let mut stream = source.subscribe().await?;
+
+        // blockhash 大约 150 个 slot 后过期，订阅之后由后台任务定期刷新
+        let blockhash = recent_blockhash.map(|recent_blockhash| {
+            let (blockhash_tx, blockhash_rx) = watch::channel(recent_blockhash);
+            let refresh = tokio::spawn(refresh_blockhash(source, blockhash_tx));
+            (blockhash_rx, refresh)
+        });
+
+        let policy = QueuePolicy::from_name(&constants::PIPELINE_QUEUE_POLICY)?;
+        let capacity = *constants::PIPELINE_QUEUE_CAPACITY;
+        let (ingest_tx, ingest_rx) = pipeline::queue("decode", capacity, policy);
+        let (decoded_tx, decoded_rx) = pipeline::queue("strategy", capacity, QueuePolicy::Block);
+        let (submit_tx, submit_rx) =
+            pipeline::queue("submit", SUBMIT_QUEUE_CAPACITY, QueuePolicy::DropNewest);
+        let queues = [ingest_tx.stats(), decoded_tx.stats(), submit_tx.stats()];
+
+        let mut stages = Vec::new();
+        let ingest_rx = Arc::new(tokio::sync::Mutex::new(ingest_rx));
+        for _ in 0..(*constants::DECODER_WORKERS).max(1) {
+            let engine = self.clone();
+            let (ingest_rx, decoded_tx) = (ingest_rx.clone(), decoded_tx.clone());
+            stages.push(tokio::spawn(async move {
+                engine.decode_stage(ingest_rx, decoded_tx).await
+            }));
+        }
+        drop(decoded_tx);
+        let engine = self.clone();
+        let sniping = recent_blockhash.is_some();
+        stages.push(tokio::spawn(async move {
+            engine.strategy_stage(decoded_rx, submit_tx, sniping).await
+        }));
+        if let (Some(sender), Some((blockhash_rx, _))) = (sender, blockhash.as_ref()) {
+            let engine = self.clone();
+            let blockhash_rx = blockhash_rx.clone();
+            stages.push(tokio::spawn(async move {
+                engine.submit_stage(submit_rx, sender, blockhash_rx).await
+            }));
+        }
+        let stats_task = tokio::spawn(self.clone().log_stats(queues));
+
+        // 接收阶段只负责把交易放进队列，slot 和账户更新开销很小，直接处理
+        let ingested = async {
+            loop {
+                let observed = tokio::select! {
+                    observed = stream.next() => observed,
+                    Some(update) = slots.next() => {
+                        self.on_slot(update);
+                        continue;
                     }
-                    o => {
-                        print!("OTHER: {:?}", o);
+                    Some(update) = accounts.next() => {
+                        self.on_account(update);
+                        continue;
                     }
                 };
+                let Some(observed) = observed else {
+                    break;
+                };
+                ingest_tx.push(observed?).await?;
             }
+            Ok(())
         }
+        .await;
 
-        Ok(())
+        // 来源结束后各阶段依次处理完队列中剩余的交易再退出
+        drop(ingest_tx);
+        for stage in stages {
+            stage.await?;
+        }
+        stats_task.abort();
+        if let Some((_, refresh)) = blockhash {
+            refresh.abort();
+        }
+        ingested
     }
 
-    pub async fn send_bundle(
+    async fn decode_stage(
         &self,
-        tx_info: &yellowstone_grpc_proto::geyser::SubscribeUpdateTransactionInfo,
-        sender: &solana_sdk::signature::Keypair,
-        recent_blockhash: &solana_sdk::hash::Hash,
-    ) -> Result<()> {
-        // 1. 将监听到的交易转换成一个普通交易，以便于后续打包到 jito
-        let _serialized_origin_tx = parsed_tx(tx_info);
-
-        // 2. 创建一个转账交易
-        let serialized_transfer_tx = {
-            let to = solana_sdk::pubkey::Pubkey::from_str(
-                "89ab91UYbFj8KBJUv1FYgLNzAwaDXdDpE8D4i8vnRy4J",
-            )?;
-            let tx = utils::create_transfer_tx(
-                sender,
-                &sender.pubkey(),
-                &to,
-                52345,
-                recent_blockhash.clone(),
-            )?;
-            base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&tx).unwrap())
-        };
+        ingest_rx: Arc<tokio::sync::Mutex<QueueReceiver<ObservedTransaction>>>,
+        decoded_tx: QueueSender<DecodedItem>,
+    ) {
+        loop {
+            let Some(observed) = ingest_rx.lock().await.recv().await else {
+                break;
+            };
+            let ctx = observed.context();
+            let decoded = DecodedTransaction::decode(&ctx, &self.decoders, &self.idls);
+            let item = DecodedItem { observed, decoded };
+            if decoded_tx.push(item).await.is_err() {
+                break;
+            }
+        }
+    }
 
-        // 3. 创建一个jito小费交易
-        let serialized_tip_tx = {
-            let tip_account = Pubkey::from_str(&self.jito_sdk.get_random_tip_account().await?)?;
-            // println!("Tips account: {}", tip_account);
+    async fn strategy_stage(
+        &self,
+        mut decoded_rx: QueueReceiver<DecodedItem>,
+        submit_tx: QueueSender<Submission>,
+        sniping: bool,
+    ) {
+        while let Some(item) = decoded_rx.recv().await {
+            let DecodedItem { observed, decoded } = item;
+            debug!("Signature = {:?}", observed.signature.to_string());
+
+            self.priority_fees.lock().unwrap().record(&decoded);
+            self.tips.lock().unwrap().record(&decoded);
+            let decoded = Arc::new(decoded);
+            for swap in &decoded.swaps {
+                info!("swap: {:?}", swap);
+            }
+            debug!("token instructions: {:?}", decoded.token_instructions);
+            debug!("idl instructions: {:?}", decoded.idl_instructions);
+            if let Some(tip) = &decoded.jito_tip {
+                info!("jito tip: {:?}", tip);
+            }
 
-            let tip_tx = utils::create_tip_tx(&sender, &tip_account, 12345, *recent_blockhash)?;
+            // 失败交易的 swap 也发布，带上失败原因，用于分析滑点等
+            let mut events = vec![Event::Transaction(decoded.clone())];
+            events.extend(decoded.swaps.iter().cloned().map(Event::Swap));
+            if let Some(error) = &decoded.error {
+                info!("failed: {:?}", error);
+            } else {
+                for pool in &decoded.pools_created {
+                    info!("pool created: {:?}", pool);
+                    events.push(Event::PoolCreated(pool.clone()));
+                }
+                if !decoded.swaps.is_empty() {
+                    self.commitment
+                        .lock()
+                        .unwrap()
+                        .track(observed.signature, observed.slot);
+                }
+            }
 
-            base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&tip_tx)?)
-        };
+            let actions = self.evaluate(Some(&observed), events);
+            for (strategy, action) in actions {
+                info!("strategy {}: {:?}", strategy, action);
+                if !sniping {
+                    continue;
+                }
+                let Some(submission) = Submission::new(strategy, action, &observed) else {
+                    continue;
+                };
+                if submit_tx.push(submission).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
 
-        // 3. 打包交易
-        let transactions = json!([
-            serialized_tip_tx,
-            serialized_transfer_tx,
-            // serialized_origin_tx
-        ]); // serialized_origin_tx
-        let params = json!([transactions, {"encoding": "base64"}]);
-        println!("bundle params = {}", params);
-        let response = self.jito_sdk.send_bundle(Some(params), None).await?;
-        // // TODO 处理响应
-        println!("{:?}", response);
-        Ok(())
+    // 依次发布事件并交给所有策略，返回各策略的动作
+    fn evaluate(
+        &self,
+        observed: Option<&ObservedTransaction>,
+        events: Vec<Event>,
+    ) -> Vec<(&'static str, Action)> {
+        let state = self.state.lock().unwrap();
+        let mut strategies = self.strategies.lock().unwrap();
+        let ctx = Context {
+            observed,
+            state: &state,
+        };
+        let mut actions = Vec::new();
+        for event in events {
+            actions.extend(strategies.on_event(&ctx, &event));
+            self.events.publish(event);
+        }
+        actions
     }
 
-    pub async fn allow_sniper(
+    async fn submit_stage(
         &self,
-        tx_info: yellowstone_grpc_proto::geyser::SubscribeUpdateTransactionInfo,
-    ) -> Result<()> {
-        let encode_transaction_with_status_meta = convert_from::create_tx_with_meta(tx_info)
-            .unwrap()
-            .encode(UiTransactionEncoding::Base64, Some(u8::MAX), true)
-            .map_err(|e| anyhow!("{}", e));
-
-        if let Some(meta1) = encode_transaction_with_status_meta?.meta {
-            if let OptionSerializer::Some(ixs) = meta1.inner_instructions {
-                let ixs_len = ixs.len();
-                if ixs_len > 0 {
-                    debug!("FOUND instructions {:?}", ixs_len);
-                }
-                let mut idx = 0;
-                for inner_ixs in ixs {
-                    debug!("inner_ixs: {:?}", inner_ixs);
-                    info!("instruaction info == {}", idx);
-                    for ix in inner_ixs.instructions {
-                        // let ins_result = pumpfun::TargetEvent::try_from(ix) {
-                        let ins_result = raydium::AmmInstruction::try_from(ix)?;
-                        match ins_result {
-                            raydium::AmmInstruction::SwapBaseIn(info) => {
-                                // TODO 策略机制，如分析下单详细,考虑滑点，决定是否进行跟单
-                                info!("SwapBaseIn: {:?}", info);
-                                return Ok(());
-                            }
-                            raydium::AmmInstruction::SwapBaseOut(info) => {
-                                // TODO
-                                info!("SwapBaseOut: {:?}", info);
-                                return Ok(());
-                            }
-                            raydium::AmmInstruction::SimulateInfo(simulate_instruction) => {
-                                // TODO
-                                info!("SimulateInfo: {:?}", simulate_instruction);
-                                return Ok(());
-                            }
-                            raydium::AmmInstruction::Deposit(deposit_instruction) => {
-                                // TODO
-                                info!("Deposit: {:?}", deposit_instruction);
-                                return Ok(());
-                            }
-                            raydium::AmmInstruction::Withdraw(withdraw_instruction) => {
-                                // TODO
-                                info!("Withdraw: {:?}", withdraw_instruction);
-                                return Ok(());
-                            }
-                            x => {
-                                debug!("OK: {:?}", x);
-                            }
-                        }
+        mut submit_rx: QueueReceiver<Submission>,
+        sender: Keypair,
+        blockhash_rx: watch::Receiver<Hash>,
+    ) {
+        while let Some(submission) = submit_rx.recv().await {
+            let recent_blockhash = *blockhash_rx.borrow();
+            let bundle_result = self
+                .send_bundle(&submission, &sender, &recent_blockhash)
+                .await;
+            let result = match bundle_result {
+                Result::Ok(bundle_id) => BundleResult::Sent { bundle_id },
+                Err(err) => {
+                    warn!("Error sending bundle of {}: {:?}", submission.strategy, err);
+                    BundleResult::Failed {
+                        error: err.to_string(),
                     }
-                    idx += 1;
-                }
-                if ixs_len > 0 {
-                    println!("\n\n");
                 }
+            };
+            self.events.publish(Event::Bundle(BundleStatus {
+                signature: submission.signature(),
+                result,
+            }));
+        }
+    }
+
+    /// Follows tracked swaps through confirmed and finalized, warning about the
+    /// ones dropped on a fork, and hands the slot and the status changes to the
+    /// strategies so they can unwind decisions based on dropped swaps.
+    pub fn on_slot(&self, update: SlotUpdate) {
+        let mut events = vec![Event::Slot(update)];
+        for event in self.commitment.lock().unwrap().on_slot(update) {
+            match event.status {
+                CommitmentStatus::Dropped => warn!("dropped: {:?}", event),
+                CommitmentStatus::Unknown => warn!("commitment unknown: {:?}", event),
+                _ => debug!("commitment: {:?}", event),
             }
+            events.push(Event::Commitment(event));
+        }
+        // 没有对应的交易可以跟，这里产生的动作无法提交
+        for (strategy, action) in self.evaluate(None, events) {
+            warn!(
+                "strategy {}: {:?} on a slot update, nothing to submit",
+                strategy, action
+            );
+        }
+    }
+
+    /// Stores the decoded account, subscribing to the vaults of newly seen AMMs.
+    pub fn on_account(&self, update: AccountUpdate) {
+        let mut state = self.state.lock().unwrap();
+        if let Err(err) = state.apply(&update) {
+            warn!("failed to decode account {}: {:?}", update.pubkey, err);
         }
+        // 没有变化时不会重新发送订阅
+        self.state_accounts.add(&state.dependent_accounts());
+    }
 
-        Err(anyhow!("Unexpected error"))
+    // 构造执行 submission.action 的 bundle 并发送，返回 bundle id
+    async fn send_bundle(
+        &self,
+        submission: &Submission,
+        sender: &Keypair,
+        recent_blockhash: &Hash,
+    ) -> Result<String> {
+        let transactions = match &submission.action {
+            // 跟在目标交易之后，同一个 bundle 中按顺序执行
+            Action::Backrun { tip, .. } => {
+                vec![
+                    serialize_tx(&submission.target)?,
+                    self.tip_tx(sender, *tip, recent_blockhash).await?,
+                ]
+            }
+            Action::Buy { tip, .. } | Action::Sell { tip, .. } => {
+                let swap_tx = self.swap_tx(sender, &submission.action, recent_blockhash)?;
+                vec![
+                    serialize_tx(&swap_tx)?,
+                    self.tip_tx(sender, *tip, recent_blockhash).await?,
+                ]
+            }
+            Action::Ignore => return Err(anyhow!("nothing to submit for Ignore")),
+        };
+        let params = json!([transactions, {"encoding": "base64"}]);
+        debug!("bundle params = {}", params);
+        let response = self.jito_sdk.send_bundle(Some(params), None).await?;
+        debug!("{:?}", response);
+        let bundle_id = response["result"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Failed to get bundle UUID from response"))?;
+        Ok(bundle_id.to_string())
+    }
+
+    // 定期输出各队列的情况和小费最多的池子、钱包
+    async fn log_stats(self: Arc<Self>, queues: [Arc<QueueStats>; 3]) {
+        let mut interval = tokio::time::interval(STATS_INTERVAL);
+        loop {
+            interval.tick().await;
+            for queue in &queues {
+                info!(
+                    "queue {}: depth {}/{}, max depth {}, enqueued {}, dropped {}",
+                    queue.name,
+                    queue.depth(),
+                    queue.capacity,
+                    queue.max_depth(),
+                    queue.enqueued(),
+                    queue.dropped(),
+                );
+            }
+            let tips = self.tips.lock().unwrap();
+            let top = [
+                ("pool", tips.top_pools(TOP_TIPPED)),
+                ("signer", tips.top_signers(TOP_TIPPED)),
+            ];
+            for (kind, top) in top {
+                for (key, summary) in top {
+                    info!(
+                        "tips of {} {}: tipped {}, average {:?}, max {}",
+                        kind,
+                        key,
+                        summary.tipped,
+                        summary.average_lamports(),
+                        summary.max_lamports,
+                    );
+                }
+            }
+        }
     }
-}
 
-fn parsed_tx(
-    tx_info: &yellowstone_grpc_proto::geyser::SubscribeUpdateTransactionInfo,
-) -> Result<String> {
-    // 将监听到的grpc响应格式交易转换成一个普通交易，以便于后续打包到 jito
-    // 1. 解码 Signature（64 bytes）
-    let sig_array: [u8; 64] = tx_info.signature.as_slice().try_into()?; // 注意必须是64字节
-    let signature = Signature::from(sig_array);
+    // 按池子最新的状态构造 swap 交易
+    fn swap_tx(
+        &self,
+        sender: &Keypair,
+        action: &Action,
+        recent_blockhash: &Hash,
+    ) -> Result<VersionedTransaction> {
+        let owner = sender.pubkey();
+        let instructions = swap_instructions(&self.state.lock().unwrap(), action, &owner)?;
+        let mut tx = Transaction::new_with_payer(&instructions, Some(&owner));
+        tx.sign(&[sender], *recent_blockhash);
+        Ok(VersionedTransaction::from(tx))
+    }
 
-    // 2. 把 proto::Message 转换成 solana_sdk::Message 《VersionedMessage》
-    let origin_message_header = tx_info.transaction.clone().unwrap().message.unwrap();
+    // 给随机一个 jito 小费账户转账
+    async fn tip_tx(&self, sender: &Keypair, tip: u64, recent_blockhash: &Hash) -> Result<String> {
+        let tip_account = Pubkey::from_str(&self.jito_sdk.get_random_tip_account().await?)?;
+        let tip_tx = utils::create_tip_tx(sender, &tip_account, tip, *recent_blockhash)?;
+        Ok(base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&tip_tx)?))
+    }
+}
 
-    // convert_from::create_tx_versioned(tx_info);
-    let versioned_message = convert_from::create_message(origin_message_header).unwrap();
+pub fn watched_accounts() -> Vec<String> {
+    vec![
+        // main-beta
+        constants::RAYDIUM_AAM_ID.to_string(),
+        // raydium devnet
+        // "HWy1jotHpo6UqeQxx49dpYYdQB8wj9Qk9MdxwjLvDHB8".to_string(),
+    ]
+}
 
-    // 3. 组装成 Transaction
-    let rebuilt_tx = solana_sdk::transaction::VersionedTransaction {
-        signatures: vec![signature],
-        message: versioned_message,
+// Buy / Sell 的指令：按最新储备报价，扣除滑点后作为 minimum_amount_out，目前只支持 Raydium AMM
+fn swap_instructions(
+    state: &StateStore,
+    action: &Action,
+    owner: &Pubkey,
+) -> Result<Vec<Instruction>> {
+    let (venue, pool, mint, amount_in, slippage_bps, buy) = match action {
+        Action::Buy {
+            venue,
+            pool,
+            mint,
+            amount_in,
+            slippage_bps,
+            ..
+        } => (*venue, pool, mint, *amount_in, *slippage_bps, true),
+        Action::Sell {
+            venue,
+            pool,
+            mint,
+            amount_in,
+            slippage_bps,
+            ..
+        } => (*venue, pool, mint, *amount_in, *slippage_bps, false),
+        _ => return Err(anyhow!("{:?} is not a swap", action)),
     };
+    if venue != Venue::RaydiumAmm {
+        return Err(anyhow!("{:?} swaps can't be built yet", venue));
+    }
+    let info = state
+        .amm(pool)
+        .ok_or_else(|| anyhow!("AMM {} has not been seen yet", pool))?;
+    let market = state
+        .amm_market_accounts(pool)
+        .ok_or_else(|| anyhow!("market of AMM {} has not been seen yet", pool))?;
+    let other = if *mint == info.coin_vault_mint {
+        info.pc_vault_mint
+    } else if *mint == info.pc_vault_mint {
+        info.coin_vault_mint
+    } else {
+        return Err(anyhow!("{} is not traded in AMM {}", mint, pool));
+    };
+    let (mint_in, mint_out) = if buy { (other, *mint) } else { (*mint, other) };
+    let quoted = state
+        .quote_amm_swap_base_in(pool, &mint_in, amount_in)
+        .ok_or_else(|| anyhow!("no quote for AMM {}, vaults not seen yet", pool))?;
+    let minimum_amount_out =
+        (quoted as u128 * (10_000 - slippage_bps.min(10_000)) as u128 / 10_000) as u64;
+
+    let token_program = constants::TOKEN_PROGRAM;
+    let user_source = utils::associated_token_address(owner, &mint_in, &token_program);
+    let user_destination = utils::associated_token_address(owner, &mint_out, &token_program);
+    let swap = SwapInstructionBaseIn {
+        amount_in,
+        minimum_amount_out,
+    }
+    .instruction(pool, info, &market, &user_source, &user_destination, owner)?;
+    Ok(vec![
+        // 第一次买入时还没有目标 token 的账户
+        utils::create_associated_token_account_idempotent(owner, owner, &mint_out, &token_program),
+        swap,
+    ])
+}
 
-    println!("rebuilt_tx = {:?}\n\n\n\n", rebuilt_tx);
+// 将监听到的交易序列化，以便于后续打包到 jito
+fn serialize_tx(tx: &VersionedTransaction) -> Result<String> {
+    debug!("rebuilt_tx = {:?}", tx);
+    Ok(base64::engine::general_purpose::STANDARD.encode(bincode::serialize(tx)?))
+}
 
-    Ok(base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&rebuilt_tx).unwrap()))
+// 定期获取最新的 blockhash，直到提交阶段退出
+async fn refresh_blockhash<S: TransactionSource>(mut source: S, blockhash_tx: watch::Sender<Hash>) {
+    let mut interval = tokio::time::interval(BLOCKHASH_REFRESH_INTERVAL);
+    // 第一次 tick 立即返回，启动时已经取过
+    interval.tick().await;
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = blockhash_tx.closed() => return,
+        }
+        match source.latest_blockhash().await {
+            Result::Ok(Some(blockhash)) => {
+                blockhash_tx.send_replace(blockhash);
+            }
+            Result::Ok(None) => {}
+            Err(err) => warn!("failed to refresh blockhash: {:?}", err),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use solana_sdk::pubkey::Pubkey;
-    use yellowstone_grpc_proto::solana::storage::confirmed_block::MessageAddressTableLookup;
-
-    #[tokio::test]
-    async fn test_engine() {
-        let input = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo";
-        let bs = bs58::decode(input).into_vec().unwrap();
-        println!("Decoded bytes: {:?}", bs);
-    }
+    use super::*;
+
+    use borsh::BorshDeserialize;
+
+    use crate::openbook;
+    use crate::raydium::{AMM_INFO_LEN, AmmInfo, amm_authority};
+    use crate::spl_token::TokenAccountState;
+    use crate::state::AccountState;
+
+    #[test]
+    fn test_swap_instructions() {
+        let (amm, market) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (coin_mint, pc_mint) = (Pubkey::new_unique(), constants::WSOL_MINT_PUBKEY);
+        let mut info = AmmInfo::try_from_account_data(&[0u8; AMM_INFO_LEN]).unwrap();
+        info.nonce = (0u64..=255).find(|n| amm_authority(*n).is_ok()).unwrap();
+        info.coin_vault = Pubkey::new_unique();
+        info.pc_vault = Pubkey::new_unique();
+        info.coin_vault_mint = coin_mint;
+        info.pc_vault_mint = pc_mint;
+        info.market = market;
+        info.market_program = constants::OPENBOOK_PROGRAM;
+        info.fees.swap_fee_numerator = 25;
+        info.fees.swap_fee_denominator = 10_000;
+
+        let openbook_program = constants::OPENBOOK_PROGRAM;
+        let nonce = (0u64..)
+            .find(|n| openbook::vault_signer(&market, *n, &openbook_program).is_ok())
+            .unwrap();
+        let market_state = openbook::market_state(market, nonce);
+
+        let mut state = StateStore::new();
+        let vault = |mint, amount| {
+            AccountState::TokenAccount(TokenAccountState {
+                mint,
+                owner: Pubkey::new_unique(),
+                amount,
+            })
+        };
+        state.insert(info.coin_vault, 1, vault(coin_mint, 2_000_000));
+        state.insert(info.pc_vault, 1, vault(pc_mint, 1_000_000));
+        let buy = Action::Buy {
+            venue: Venue::RaydiumAmm,
+            pool: amm,
+            mint: coin_mint,
+            amount_in: 10_000,
+            slippage_bps: 100,
+            tip: 1_000,
+        };
+        let owner = Pubkey::new_unique();
+        // 还没有池子的状态
+        assert!(swap_instructions(&state, &buy, &owner).is_err());
+        state.insert(amm, 1, AccountState::RaydiumAmm(Box::new(info.clone())));
+        // 还没有 market 的状态
+        assert!(swap_instructions(&state, &buy, &owner).is_err());
+        state.insert(
+            market,
+            1,
+            AccountState::OpenbookMarket(Box::new(market_state)),
+        );
 
-    #[tokio::test]
-    async fn test_bs58() {
-        // let account_key = [
-        //     39, 245, 50, 118, 56, 33, 10, 103, 136, 229, 202, 96, 137, 27, 228, 49, 156, 69, 149,
-        //     203, 173, 178, 188, 223, 214, 48, 126, 12, 2, 98, 70, 160,
-        // ];
-
-        // // 将字节数组转换为 Solana 公钥
-        // let pubkey = Pubkey::new_from_array(account_key);
-
-        // // 转换为 Base58 地址
-        // let base58_address = pubkey.to_string();
-
-        // // 输出 Base58 地址
-        // println!("Base58 Address: {}", base58_address);
-        //
-
-        // 假设这是 address_table_lookups 中的数据
-        // let address_table_lookups = vec![
-        //     // 账户查找表
-        //     MessageAddressTableLookup {
-        //         account_key: vec![
-        //             39, 245, 50, 118, 56, 33, 10, 103, 136, 229, 202, 96, 137, 27, 228, 49, 156,
-        //             69, 149, 203, 173, 178, 188, 223, 214, 48, 126, 12, 2, 98, 70, 160,
-        //         ],
-        //         writable_indexes: vec![49, 52, 53, 96, 97, 98, 99],
-        //         readonly_indexes: vec![45, 50, 47, 64, 34],
-        //     },
-        // ];
-
-        // // 假设你要查找的账户索引是 49
-        // let target_account_index = 49;
-
-        // // 从 writable_indexes 中查找账户
-        // for lookup in address_table_lookups.iter() {
-        //     if lookup.writable_indexes.contains(&target_account_index) {
-        //         let account_key = lookup.account_key;
-        //         let pubkey = Pubkey::from(&account_key.as_slice());
-        //         println!("Account found at index 49 with address: {}", pubkey);
-        //     }
-        // }
+        let instructions = swap_instructions(&state, &buy, &owner).unwrap();
+        assert_eq!(instructions.len(), 2);
+        let swap = &instructions[1];
+        assert_eq!(swap.program_id, constants::RAYDIUM_AAM_PROGRAM);
+        assert_eq!(swap.data[0], 9);
+        assert_eq!(swap.accounts.len(), 18);
+        assert_eq!(swap.accounts[1].pubkey, amm);
+        assert_eq!(swap.accounts[9].pubkey, market_state.bids);
+        let token_program = constants::TOKEN_PROGRAM;
+        assert_eq!(
+            swap.accounts[15].pubkey,
+            utils::associated_token_address(&owner, &pc_mint, &token_program)
+        );
+        assert_eq!(
+            swap.accounts[16].pubkey,
+            utils::associated_token_address(&owner, &coin_mint, &token_program)
+        );
+        assert!(swap.accounts[17].is_signer);
+        // 报价 2_000_000 * 9_975 / (1_000_000 + 9_975) = 19_752，扣除 1% 滑点
+        let quoted = state
+            .quote_amm_swap_base_in(&amm, &pc_mint, 10_000)
+            .unwrap();
+        assert_eq!(quoted, 19_752);
+        let args = SwapInstructionBaseIn::try_from_slice(&swap.data[1..]).unwrap();
+        assert_eq!((args.amount_in, args.minimum_amount_out), (10_000, 19_554));
+
+        let sell = Action::Sell {
+            venue: Venue::RaydiumAmm,
+            pool: amm,
+            mint: coin_mint,
+            amount_in: 10_000,
+            slippage_bps: 0,
+            tip: 1_000,
+        };
+        let instructions = swap_instructions(&state, &sell, &owner).unwrap();
+        let args = SwapInstructionBaseIn::try_from_slice(&instructions[1].data[1..]).unwrap();
+        assert_eq!(
+            args.minimum_amount_out,
+            state
+                .quote_amm_swap_base_in(&amm, &coin_mint, 10_000)
+                .unwrap()
+        );
+
+        let pumpfun = Action::Buy {
+            venue: Venue::Pumpfun,
+            pool: amm,
+            mint: coin_mint,
+            amount_in: 10_000,
+            slippage_bps: 100,
+            tip: 1_000,
+        };
+        assert!(swap_instructions(&state, &pumpfun, &owner).is_err());
     }
 }
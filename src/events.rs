@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use solana_sdk::signature::Signature;
+use tokio::sync::broadcast;
+
+use crate::commitment::{CommitmentEvent, SlotUpdate};
+use crate::decoder::{PoolCreated, SwapEvent};
+use crate::transaction::DecodedTransaction;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleResult {
+    /// Accepted by the block engine, not yet landed
+    Sent {
+        bundle_id: String,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+/// Outcome of the bundle submitted in response to an observed transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleStatus {
+    /// Signature of the observed transaction the bundle responds to
+    pub signature: Signature,
+    pub result: BundleResult,
+}
+
+/// Everything the engine produces, in the order it produces it.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// Every decoded transaction, failed ones included
+    Transaction(Arc<DecodedTransaction>),
+    /// Every decoded swap, those of failed transactions with `error` set
+    Swap(SwapEvent),
+    PoolCreated(PoolCreated),
+    Bundle(BundleStatus),
+    Slot(SlotUpdate),
+    /// Status changes of the swaps followed by the engine
+    Commitment(CommitmentEvent),
+}
+
+/// Publish/subscribe bus fanning the engine's events out to strategies,
+/// persistence and metrics.
+///
+/// Every subscriber sees every event published after it subscribed. A subscriber
+/// more than `capacity` events behind loses the oldest ones and gets
+/// `RecvError::Lagged`, publishing never waits.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        EventBus { sender }
+    }
+
+    /// Publishes `event`, returns how many subscribers will receive it.
+    pub fn publish(&self, event: Event) -> usize {
+        // 没有订阅者时 send 返回 Err，事件直接丢弃
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    pub fn subscribers(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::sync::broadcast::error::RecvError;
+
+    use crate::commitment::SlotStatus;
+
+    fn slot(slot: u64) -> Event {
+        Event::Slot(SlotUpdate {
+            slot,
+            parent: None,
+            status: SlotStatus::Processed,
+        })
+    }
+
+    fn received_slot(event: Event) -> u64 {
+        match event {
+            Event::Slot(update) => update.slot,
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_bus() {
+        let bus = EventBus::new(2);
+        assert_eq!(bus.publish(slot(1)), 0);
+
+        let mut strategy = bus.subscribe();
+        let mut metrics = bus.subscribe();
+        assert_eq!(bus.subscribers(), 2);
+        assert_eq!(bus.publish(slot(2)), 2);
+        assert_eq!(received_slot(strategy.recv().await.unwrap()), 2);
+
+        let status = BundleStatus {
+            signature: Signature::default(),
+            result: BundleResult::Sent {
+                bundle_id: "bundle".to_string(),
+            },
+        };
+        bus.publish(Event::Bundle(status.clone()));
+        match strategy.recv().await.unwrap() {
+            Event::Bundle(received) => assert_eq!(received, status),
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        // 落后的订阅者丢失最旧的事件，不影响其他订阅者
+        bus.publish(slot(3));
+        assert_eq!(received_slot(strategy.recv().await.unwrap()), 3);
+        assert!(matches!(metrics.recv().await, Err(RecvError::Lagged(1))));
+        assert!(matches!(metrics.recv().await.unwrap(), Event::Bundle(_)));
+        assert_eq!(received_slot(metrics.recv().await.unwrap()), 3);
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::{Result, anyhow};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_transaction_status::{UiCompiledInstruction, UiInstruction};
+
+use crate::constants;
+use crate::decoder::TransactionContext;
+
+// 未设置 SetComputeUnitLimit 时，每条非 compute budget 指令默认 200k CU，总数不超过 1.4M
+const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+// https://github.com/anza-xyz/solana-sdk/blob/master/compute-budget-interface/src/lib.rs
+// borsh 编码，第一个字节为指令下标
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub enum ComputeBudgetInstruction {
+    /// Deprecated, kept so the variant indexes line up
+    Unused,
+    /// Request a specific transaction-wide program heap region size in bytes
+    RequestHeapFrame(u32),
+    /// Set a specific compute unit limit that the transaction is allowed to consume
+    SetComputeUnitLimit(u32),
+    /// Set a compute unit price in micro-lamports to pay a higher transaction fee
+    /// for higher transaction prioritization
+    SetComputeUnitPrice(u64),
+    /// Set a specific transaction-wide account data size limit, in bytes
+    SetLoadedAccountsDataSizeLimit(u32),
+}
+
+impl TryFrom<UiInstruction> for ComputeBudgetInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ix: UiInstruction) -> Result<ComputeBudgetInstruction> {
+        match ix {
+            UiInstruction::Compiled(ui_ix) => ComputeBudgetInstruction::try_from(&ui_ix),
+            _ => Err(anyhow!(
+                "failed to convert to target ComputeBudgetInstruction"
+            )),
+        }
+    }
+}
+
+impl TryFrom<&UiCompiledInstruction> for ComputeBudgetInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ui_ix: &UiCompiledInstruction) -> Result<ComputeBudgetInstruction> {
+        let data = bs58::decode(&ui_ix.data).into_vec()?;
+        Ok(ComputeBudgetInstruction::try_from_slice(&data)?)
+    }
+}
+
+/// Compute budget requested and used by a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ComputeBudget {
+    /// `SetComputeUnitLimit`, if present
+    pub unit_limit: Option<u32>,
+    /// `SetComputeUnitPrice` in micro-lamports per compute unit, if present
+    pub unit_price: Option<u64>,
+    /// Compute units actually consumed, from meta
+    pub units_consumed: Option<u64>,
+    /// Priority fee in lamports: unit price times the effective unit limit
+    pub priority_fee: u64,
+}
+
+impl ComputeBudget {
+    /// Reads the compute budget instructions, which are only honoured at the top level.
+    pub fn from_context(ctx: &TransactionContext) -> Self {
+        let program_id = constants::COMPUTE_BUDGET_PROGRAM;
+
+        let mut budget = ComputeBudget {
+            units_consumed: ctx.compute_units_consumed,
+            ..Default::default()
+        };
+        let mut other_instructions = 0u32;
+        for ix in &ctx.instructions {
+            if ctx.program_id(ix) != Some(program_id) {
+                other_instructions += 1;
+                continue;
+            }
+            match ComputeBudgetInstruction::try_from(ix) {
+                Ok(ComputeBudgetInstruction::SetComputeUnitLimit(limit)) => {
+                    budget.unit_limit = Some(limit)
+                }
+                Ok(ComputeBudgetInstruction::SetComputeUnitPrice(price)) => {
+                    budget.unit_price = Some(price)
+                }
+                _ => {}
+            }
+        }
+
+        let unit_limit = budget
+            .unit_limit
+            .unwrap_or(other_instructions.saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT))
+            .min(MAX_COMPUTE_UNIT_LIMIT);
+        budget.priority_fee = priority_fee(budget.unit_price.unwrap_or_default(), unit_limit);
+        budget
+    }
+
+    /// Micro-lamports paid per compute unit actually consumed, the number to
+    /// compare across transactions that over-request their limit.
+    pub fn effective_unit_price(&self) -> Option<u64> {
+        let consumed = self.units_consumed.filter(|units| *units > 0)?;
+        let unit_price = self.priority_fee as u128 * MICRO_LAMPORTS_PER_LAMPORT / consumed as u128;
+        Some(u64::try_from(unit_price).unwrap_or(u64::MAX))
+    }
+}
+
+/// Priority fee in lamports, rounded up like the runtime does and saturating at `u64::MAX`.
+pub fn priority_fee(unit_price: u64, unit_limit: u32) -> u64 {
+    let micro_lamports = unit_price as u128 * unit_limit as u128;
+    u64::try_from(micro_lamports.div_ceil(MICRO_LAMPORTS_PER_LAMPORT)).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+
+    use crate::decoder::compiled_instruction;
+
+    fn ui_ix(program_id_index: u8, ix: ComputeBudgetInstruction) -> UiCompiledInstruction {
+        compiled_instruction(program_id_index, vec![], borsh::to_vec(&ix).unwrap())
+    }
+
+    fn context(instructions: Vec<UiCompiledInstruction>) -> TransactionContext {
+        TransactionContext {
+            slot: 1,
+            signature: Signature::default(),
+            account_keys: vec![Pubkey::new_unique(), constants::COMPUTE_BUDGET_PROGRAM],
+            inner_instructions: Some(vec![vec![]; instructions.len()]),
+            instructions,
+            pre_token_balances: HashMap::new(),
+            post_token_balances: HashMap::new(),
+            log_messages: vec![],
+            compute_units_consumed: Some(50_000),
+            error: None,
+            writable: vec![],
+        }
+    }
+
+    #[test]
+    fn test_compute_budget() {
+        let other = UiCompiledInstruction {
+            program_id_index: 0,
+            accounts: vec![],
+            data: String::new(),
+            stack_height: None,
+        };
+
+        let ctx = context(vec![
+            ui_ix(1, ComputeBudgetInstruction::SetComputeUnitLimit(100_000)),
+            ui_ix(1, ComputeBudgetInstruction::SetComputeUnitPrice(1_000_001)),
+            other.clone(),
+        ]);
+        let budget = ComputeBudget::from_context(&ctx);
+        assert_eq!(budget.unit_limit, Some(100_000));
+        assert_eq!(budget.unit_price, Some(1_000_001));
+        assert_eq!(budget.units_consumed, Some(50_000));
+        // 100_000 * 1_000_001 / 1_000_000 = 100_000.1，向上取整
+        assert_eq!(budget.priority_fee, 100_001);
+        assert_eq!(budget.effective_unit_price(), Some(2_000_020));
+
+        // 未设置 limit 时按指令数计算默认值
+        let ctx = context(vec![
+            ui_ix(1, ComputeBudgetInstruction::SetComputeUnitPrice(10_000)),
+            other.clone(),
+            other,
+        ]);
+        let budget = ComputeBudget::from_context(&ctx);
+        assert_eq!(budget.unit_limit, None);
+        assert_eq!(budget.priority_fee, 4_000);
+
+        // 超出 u64 时取最大值而不是截断
+        assert_eq!(priority_fee(u64::MAX, u32::MAX), u64::MAX);
+    }
+}
@@ -0,0 +1,508 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use solana_transaction_status::{
+    TransactionStatusMeta, TransactionTokenBalance, UiCompiledInstruction,
+};
+
+use crate::transaction::TransactionFailure;
+
+/// The venue a `SwapEvent` was decoded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Venue {
+    RaydiumAmm,
+    Pumpfun,
+    OrcaWhirlpool,
+    MeteoraDlmm,
+    MeteoraDamm,
+    RaydiumLaunchlab,
+    Phoenix,
+}
+
+/// Position of an instruction within a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InstructionPath {
+    /// Index of the top level instruction
+    pub outer: usize,
+    /// Index within the inner instructions of `outer`, `None` for the top level instruction itself
+    pub inner: Option<usize>,
+}
+
+/// A swap normalized across venues.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapEvent {
+    pub venue: Venue,
+    pub pool: Pubkey,
+    pub trader: Pubkey,
+    /// `Pubkey::default()` when decoded from instruction args and the accounts don't name the mint
+    pub mint_in: Pubkey,
+    pub mint_out: Pubkey,
+    /// Requested amounts when decoded from instruction args, `amount_out` is then the slippage bound
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Pool reserves of (`mint_in`, `mint_out`) after the transaction, if known
+    pub reserves_after: Option<(u64, u64)>,
+    pub slot: u64,
+    pub signature: Signature,
+    /// `None` when the swap was decoded from log messages
+    pub instruction_path: Option<InstructionPath>,
+    /// Why the transaction failed, the swap then never executed and is decoded from its args
+    pub error: Option<TransactionFailure>,
+}
+
+/// A new pool (or bonding curve) initialized by a transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolCreated {
+    pub venue: Venue,
+    pub pool: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub creator: Pubkey,
+    pub slot: u64,
+    pub signature: Signature,
+}
+
+/// Implemented once per program, turns its instructions (or logs) into `SwapEvent`s.
+pub trait DexDecoder: Send + Sync {
+    fn program_id(&self) -> Pubkey;
+
+    fn venue(&self) -> Venue;
+
+    /// Decodes a single instruction invoking `program_id`, `None` if it is not a swap.
+    fn decode_instruction(
+        &self,
+        ctx: &TransactionContext,
+        path: InstructionPath,
+        ix: &UiCompiledInstruction,
+    ) -> Option<SwapEvent>;
+
+    /// Decodes every swap an instruction performed, one per hop for multi-hop
+    /// instructions. Defaults to `decode_instruction`.
+    fn decode_instruction_swaps(
+        &self,
+        ctx: &TransactionContext,
+        path: InstructionPath,
+        ix: &UiCompiledInstruction,
+    ) -> Vec<SwapEvent> {
+        self.decode_instruction(ctx, path, ix).into_iter().collect()
+    }
+
+    /// Decodes swaps from log messages, used when the source has no inner instructions.
+    fn decode_logs(&self, _ctx: &TransactionContext) -> Vec<SwapEvent> {
+        Vec::new()
+    }
+
+    /// Decodes a top level swap instruction from its args and accounts alone,
+    /// used for failed transactions and when there is neither meta nor logs,
+    /// e.g. ShredStream.
+    fn decode_instruction_args(
+        &self,
+        _ctx: &TransactionContext,
+        _path: InstructionPath,
+        _ix: &UiCompiledInstruction,
+    ) -> Option<SwapEvent> {
+        None
+    }
+
+    /// Decodes an instruction (or event CPI) invoking `program_id` that creates a pool.
+    fn decode_pool_created(
+        &self,
+        _ctx: &TransactionContext,
+        _ix: &UiCompiledInstruction,
+    ) -> Option<PoolCreated> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenBalance {
+    pub mint: Pubkey,
+    pub owner: Option<Pubkey>,
+    pub amount: u64,
+}
+
+impl TokenBalance {
+    fn from_transaction_token_balance(balance: &TransactionTokenBalance) -> Option<Self> {
+        Some(TokenBalance {
+            mint: Pubkey::from_str(&balance.mint).ok()?,
+            owner: Pubkey::from_str(&balance.owner).ok(),
+            amount: balance.ui_token_amount.amount.parse().ok()?,
+        })
+    }
+}
+
+/// Everything a decoder needs to know about one transaction.
+#[derive(Debug, Clone)]
+pub struct TransactionContext {
+    pub slot: u64,
+    pub signature: Signature,
+    /// Static account keys followed by the writable and readonly loaded addresses
+    pub account_keys: Vec<Pubkey>,
+    /// Whether each of `account_keys` is write-locked by the transaction
+    pub writable: Vec<bool>,
+    pub instructions: Vec<UiCompiledInstruction>,
+    /// Inner instructions per top level instruction, `None` without status meta
+    pub inner_instructions: Option<Vec<Vec<UiCompiledInstruction>>>,
+    /// Token balances keyed by account index
+    pub pre_token_balances: HashMap<usize, TokenBalance>,
+    pub post_token_balances: HashMap<usize, TokenBalance>,
+    pub log_messages: Vec<String>,
+    /// Compute units used by the transaction, from meta
+    pub compute_units_consumed: Option<u64>,
+    /// `meta.err`, `None` for successful transactions or without status meta
+    pub error: Option<TransactionError>,
+}
+
+impl TransactionContext {
+    pub fn new(
+        slot: u64,
+        transaction: &VersionedTransaction,
+        meta: Option<&TransactionStatusMeta>,
+    ) -> Self {
+        let message = &transaction.message;
+        let mut account_keys = message.static_account_keys().to_vec();
+        if let Some(meta) = meta {
+            account_keys.extend(&meta.loaded_addresses.writable);
+            account_keys.extend(&meta.loaded_addresses.readonly);
+        }
+
+        let writable = (0..account_keys.len())
+            .map(|index| message.is_maybe_writable(index, None))
+            .collect();
+
+        let instructions: Vec<UiCompiledInstruction> = message
+            .instructions()
+            .iter()
+            .map(|ix| UiCompiledInstruction::from(ix, None))
+            .collect();
+
+        let inner_instructions = meta.map(|meta| {
+            let mut inner = vec![Vec::new(); instructions.len()];
+            for ixs in meta.inner_instructions.iter().flatten() {
+                if let Some(slot) = inner.get_mut(ixs.index as usize) {
+                    *slot = ixs
+                        .instructions
+                        .iter()
+                        .map(|ix| UiCompiledInstruction::from(&ix.instruction, ix.stack_height))
+                        .collect();
+                }
+            }
+            inner
+        });
+
+        let token_balances = |balances: Option<&Vec<TransactionTokenBalance>>| {
+            balances
+                .into_iter()
+                .flatten()
+                .filter_map(|b| {
+                    TokenBalance::from_transaction_token_balance(b)
+                        .map(|balance| (b.account_index as usize, balance))
+                })
+                .collect::<HashMap<_, _>>()
+        };
+
+        TransactionContext {
+            slot,
+            signature: transaction.signatures.first().copied().unwrap_or_default(),
+            account_keys,
+            writable,
+            instructions,
+            inner_instructions,
+            pre_token_balances: token_balances(meta.and_then(|m| m.pre_token_balances.as_ref())),
+            post_token_balances: token_balances(meta.and_then(|m| m.post_token_balances.as_ref())),
+            log_messages: meta
+                .and_then(|m| m.log_messages.clone())
+                .unwrap_or_default(),
+            compute_units_consumed: meta.and_then(|m| m.compute_units_consumed),
+            error: meta.and_then(|m| m.status.clone().err()),
+        }
+    }
+
+    pub fn program_id(&self, ix: &UiCompiledInstruction) -> Option<Pubkey> {
+        self.account_keys.get(ix.program_id_index as usize).copied()
+    }
+
+    /// Accounts the transaction write-locks, the ones it contends on with other transactions.
+    pub fn writable_accounts(&self) -> impl Iterator<Item = Pubkey> + '_ {
+        self.account_keys
+            .iter()
+            .zip(&self.writable)
+            .filter(|(_, writable)| **writable)
+            .map(|(key, _)| *key)
+    }
+
+    /// Resolves the `position`-th account of `ix`.
+    pub fn account(&self, ix: &UiCompiledInstruction, position: usize) -> Option<Pubkey> {
+        let index = *ix.accounts.get(position)? as usize;
+        self.account_keys.get(index).copied()
+    }
+
+    /// Every instruction in execution order, each top level instruction followed by its inner ones.
+    pub fn instructions_with_path(
+        &self,
+    ) -> impl Iterator<Item = (InstructionPath, &UiCompiledInstruction)> {
+        self.instructions
+            .iter()
+            .enumerate()
+            .flat_map(move |(outer, ix)| {
+                let inner = self
+                    .inner_instructions
+                    .as_ref()
+                    .and_then(|inner| inner.get(outer))
+                    .into_iter()
+                    .flatten()
+                    .enumerate()
+                    .map(move |(i, ix)| {
+                        (
+                            InstructionPath {
+                                outer,
+                                inner: Some(i),
+                            },
+                            ix,
+                        )
+                    });
+                std::iter::once((InstructionPath { outer, inner: None }, ix)).chain(inner)
+            })
+    }
+
+    /// Mint of a token account, known if the account shows up in the token balances.
+    pub fn token_mint(&self, account_index: usize) -> Option<Pubkey> {
+        self.post_token_balances
+            .get(&account_index)
+            .or_else(|| self.pre_token_balances.get(&account_index))
+            .map(|b| b.mint)
+    }
+
+    /// Change of a token account balance over the transaction, with its mint and post balance.
+    pub fn token_delta(&self, account_index: usize) -> Option<(Pubkey, i128, u64)> {
+        let post = self.post_token_balances.get(&account_index)?;
+        let pre = self
+            .pre_token_balances
+            .get(&account_index)
+            .map(|b| b.amount)
+            .unwrap_or_default();
+        Some((post.mint, post.amount as i128 - pre as i128, post.amount))
+    }
+
+    /// Builds a `SwapEvent` from the balance changes of the two pool vaults of `ix`:
+    /// the vault that grew received `mint_in`, the one that shrank paid out `mint_out`.
+    ///
+    /// Balances cover the whole transaction, so several swaps through the same pool
+    /// in one transaction are reported as their net effect.
+    pub fn swap_from_vaults(
+        &self,
+        venue: Venue,
+        path: InstructionPath,
+        ix: &UiCompiledInstruction,
+        pool: usize,
+        trader: usize,
+        vaults: [usize; 2],
+    ) -> Option<SwapEvent> {
+        let vault_a = self.token_delta(*ix.accounts.get(vaults[0])? as usize)?;
+        let vault_b = self.token_delta(*ix.accounts.get(vaults[1])? as usize)?;
+        let (vault_in, vault_out) = if vault_a.1 > 0 && vault_b.1 < 0 {
+            (vault_a, vault_b)
+        } else if vault_b.1 > 0 && vault_a.1 < 0 {
+            (vault_b, vault_a)
+        } else {
+            return None;
+        };
+
+        Some(SwapEvent {
+            venue,
+            pool: self.account(ix, pool)?,
+            trader: self.account(ix, trader)?,
+            mint_in: vault_in.0,
+            mint_out: vault_out.0,
+            amount_in: vault_in.1 as u64,
+            amount_out: vault_out.1.unsigned_abs() as u64,
+            reserves_after: Some((vault_in.2, vault_out.2)),
+            slot: self.slot,
+            signature: self.signature,
+            instruction_path: Some(path),
+            error: None,
+        })
+    }
+
+    /// Runs `decoders` over the transaction. Falls back to log decoding
+    /// when the source delivered no inner instructions, and to the args of the
+    /// top level instructions when it delivered no logs either.
+    ///
+    /// Failed transactions move no balances and emit no events, their swaps are
+    /// always decoded from the args.
+    pub fn decode_swaps(&self, decoders: &[Box<dyn DexDecoder>]) -> Vec<SwapEvent> {
+        let failed = self.error.is_some();
+        if !failed && self.inner_instructions.is_none() && !self.log_messages.is_empty() {
+            return decoders.iter().flat_map(|d| d.decode_logs(self)).collect();
+        }
+
+        let program_ids: Vec<Pubkey> = decoders.iter().map(|d| d.program_id()).collect();
+        let from_args = failed || self.inner_instructions.is_none();
+        self.instructions_with_path()
+            .flat_map(|(path, ix)| {
+                let Some(position) = self
+                    .program_id(ix)
+                    .and_then(|program_id| program_ids.iter().position(|id| *id == program_id))
+                else {
+                    return Vec::new();
+                };
+                if from_args {
+                    decoders[position]
+                        .decode_instruction_args(self, path, ix)
+                        .into_iter()
+                        .collect()
+                } else {
+                    decoders[position].decode_instruction_swaps(self, path, ix)
+                }
+            })
+            .collect()
+    }
+
+    /// Pools created by the transaction, found in its instructions and event CPIs.
+    pub fn decode_pool_creations(&self, decoders: &[Box<dyn DexDecoder>]) -> Vec<PoolCreated> {
+        let program_ids: Vec<Pubkey> = decoders.iter().map(|d| d.program_id()).collect();
+        self.instructions_with_path()
+            .filter_map(|(_, ix)| {
+                let program_id = self.program_id(ix)?;
+                let position = program_ids.iter().position(|id| *id == program_id)?;
+                decoders[position].decode_pool_created(self, ix)
+            })
+            .collect()
+    }
+}
+
+/// Compiled instruction with `data` base58 encoded, for decoder tests.
+#[cfg(test)]
+pub(crate) fn compiled_instruction(
+    program_id_index: u8,
+    accounts: Vec<u8>,
+    data: impl AsRef<[u8]>,
+) -> UiCompiledInstruction {
+    UiCompiledInstruction {
+        program_id_index,
+        accounts,
+        data: bs58::encode(data).into_string(),
+        stack_height: None,
+    }
+}
+
+/// Anchor instruction without accounts: the discriminator followed by the borsh encoded `args`.
+#[cfg(test)]
+pub(crate) fn anchor_instruction(
+    discriminator: [u8; 8],
+    args: impl borsh::BorshSerialize,
+) -> UiCompiledInstruction {
+    let mut data = discriminator.to_vec();
+    data.extend(borsh::to_vec(&args).unwrap());
+    compiled_instruction(0, vec![], data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::{Message, VersionedMessage, v0::LoadedAddresses};
+
+    fn token_balance(account_index: u8, mint: &Pubkey, amount: u64) -> TransactionTokenBalance {
+        TransactionTokenBalance {
+            account_index,
+            mint: mint.to_string(),
+            ui_token_amount: serde_json::from_value(serde_json::json!({
+                "uiAmount": null,
+                "decimals": 6,
+                "amount": amount.to_string(),
+                "uiAmountString": "",
+            }))
+            .unwrap(),
+            owner: Pubkey::new_unique().to_string(),
+            program_id: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_swap_from_vaults() {
+        let program = Pubkey::new_unique();
+        let trader = Pubkey::new_unique();
+        let (pool, vault_a, vault_b) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = Instruction::new_with_bytes(
+            program,
+            &[],
+            vec![
+                AccountMeta::new(pool, false),
+                AccountMeta::new(vault_a, false),
+                AccountMeta::new(vault_b, false),
+                AccountMeta::new(trader, true),
+            ],
+        );
+        let message = Message::new(&[ix], Some(&trader));
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::Legacy(message.clone()),
+        };
+        let index =
+            |key: &Pubkey| message.account_keys.iter().position(|k| k == key).unwrap() as u8;
+
+        let meta = TransactionStatusMeta {
+            status: Ok(()),
+            fee: 5000,
+            pre_balances: vec![],
+            post_balances: vec![],
+            inner_instructions: Some(vec![]),
+            log_messages: None,
+            pre_token_balances: Some(vec![
+                token_balance(index(&vault_a), &mint_a, 1_000),
+                token_balance(index(&vault_b), &mint_b, 2_000),
+            ]),
+            post_token_balances: Some(vec![
+                token_balance(index(&vault_a), &mint_a, 900),
+                token_balance(index(&vault_b), &mint_b, 2_250),
+            ]),
+            rewards: None,
+            loaded_addresses: LoadedAddresses::default(),
+            return_data: None,
+            compute_units_consumed: None,
+        };
+
+        struct TestDecoder(Pubkey);
+        impl DexDecoder for TestDecoder {
+            fn program_id(&self) -> Pubkey {
+                self.0
+            }
+            fn venue(&self) -> Venue {
+                Venue::RaydiumAmm
+            }
+            fn decode_instruction(
+                &self,
+                ctx: &TransactionContext,
+                path: InstructionPath,
+                ix: &UiCompiledInstruction,
+            ) -> Option<SwapEvent> {
+                ctx.swap_from_vaults(self.venue(), path, ix, 0, 3, [1, 2])
+            }
+        }
+
+        let ctx = TransactionContext::new(42, &tx, Some(&meta));
+        let decoders: Vec<Box<dyn DexDecoder>> = vec![Box::new(TestDecoder(program))];
+        let swaps = ctx.decode_swaps(&decoders);
+        assert_eq!(swaps.len(), 1);
+        let swap = &swaps[0];
+        assert_eq!(swap.pool, pool);
+        assert_eq!(swap.trader, trader);
+        assert_eq!((swap.mint_in, swap.amount_in), (mint_b, 250));
+        assert_eq!((swap.mint_out, swap.amount_out), (mint_a, 100));
+        assert_eq!(swap.reserves_after, Some((2_250, 900)));
+        assert_eq!(swap.slot, 42);
+
+        // 没有 meta 时只能依赖日志
+        let ctx = TransactionContext::new(42, &tx, None);
+        assert!(ctx.decode_swaps(&decoders).is_empty());
+    }
+}
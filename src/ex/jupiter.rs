@@ -0,0 +1,734 @@
+use anyhow::{Result, anyhow};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{UiCompiledInstruction, UiInstruction};
+
+use crate::constants;
+use crate::decoder::TransactionContext;
+use crate::whirlpool::RemainingAccountsInfo;
+
+// Anchor 指令 discriminator: sha256("global:<name>")[..8]
+const ROUTE_IX: [u8; 8] = [229, 23, 203, 151, 122, 227, 173, 42];
+const SHARED_ACCOUNTS_ROUTE_IX: [u8; 8] = [193, 32, 155, 51, 65, 214, 156, 129];
+const EXACT_OUT_ROUTE_IX: [u8; 8] = [208, 51, 239, 151, 123, 43, 237, 92];
+const SHARED_ACCOUNTS_EXACT_OUT_ROUTE_IX: [u8; 8] = [176, 209, 105, 168, 154, 125, 69, 62];
+
+// emit_cpi! 事件: EVENT_IX_TAG(8 字节) + sha256("event:SwapEvent")[..8] + 数据
+const EVENT_IX_TAG: [u8; 8] = [228, 69, 165, 46, 81, 203, 154, 29];
+const SWAP_EVENT: [u8; 8] = [64, 198, 205, 232, 38, 8, 113, 226];
+
+// IDL: https://github.com/jup-ag/jupiter-cpi/blob/main/idl.json
+// 这里监听的是指令，每一个 leg 对应的 DEX 指令作为 Jupiter 指令的 inner instruction 出现
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+pub enum JupiterInstruction {
+    ///   Route with an exact input amount.
+    ///
+    ///   0. `[]` Token program id
+    ///   1. `[signer]` User transfer authority
+    ///   2. `[writable]` User source token Account
+    ///   3. `[writable]` User destination token Account
+    ///   4. `[writable]` (optional) Destination token Account
+    ///   5. `[]` Destination mint
+    ///   6. `[writable]` (optional) Platform fee Account
+    ///   7. `[]` Event authority
+    ///   8. `[]` Program
+    ///
+    ///   Followed by the accounts of every leg, in route plan order.
+    Route(RouteInstruction),
+
+    ///   Route with an exact input amount through program owned token accounts.
+    ///
+    ///   0. `[]` Token program id
+    ///   1. `[]` Program authority
+    ///   2. `[signer]` User transfer authority
+    ///   3. `[writable]` User source token Account
+    ///   4. `[writable]` Program source token Account
+    ///   5. `[writable]` Program destination token Account
+    ///   6. `[writable]` User destination token Account
+    ///   7. `[]` Source mint
+    ///   8. `[]` Destination mint
+    ///   9. `[writable]` (optional) Platform fee Account
+    ///   10. `[]` (optional) Token-2022 program
+    ///   11. `[]` Event authority
+    ///   12. `[]` Program
+    ///
+    ///   Followed by the accounts of every leg, in route plan order.
+    SharedAccountsRoute(SharedAccountsRouteInstruction),
+
+    ///   Route with an exact output amount. Accounts as in `Route`, with the source mint
+    ///   inserted before the destination mint.
+    ExactOutRoute(ExactOutRouteInstruction),
+
+    ///   Route with an exact output amount. Accounts as in `SharedAccountsRoute`.
+    SharedAccountsExactOutRoute(SharedAccountsExactOutRouteInstruction),
+}
+
+impl TryFrom<UiInstruction> for JupiterInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ix: UiInstruction) -> Result<JupiterInstruction> {
+        match ix {
+            UiInstruction::Compiled(ui_ix) => JupiterInstruction::try_from(&ui_ix),
+            _ => Err(anyhow!("failed to convert to target JupiterInstruction")),
+        }
+    }
+}
+
+impl TryFrom<&UiCompiledInstruction> for JupiterInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ui_ix: &UiCompiledInstruction) -> Result<JupiterInstruction> {
+        let data = bs58::decode(&ui_ix.data).into_vec()?;
+        if data.len() < 8 {
+            return Err(anyhow!("failed to convert to target JupiterInstruction"));
+        }
+
+        let (discriminator, args) = data.split_at(8);
+        let ix = match discriminator {
+            d if d == ROUTE_IX => Self::Route(RouteInstruction::try_from_slice(args)?),
+            d if d == SHARED_ACCOUNTS_ROUTE_IX => {
+                Self::SharedAccountsRoute(SharedAccountsRouteInstruction::try_from_slice(args)?)
+            }
+            d if d == EXACT_OUT_ROUTE_IX => {
+                Self::ExactOutRoute(ExactOutRouteInstruction::try_from_slice(args)?)
+            }
+            d if d == SHARED_ACCOUNTS_EXACT_OUT_ROUTE_IX => Self::SharedAccountsExactOutRoute(
+                SharedAccountsExactOutRouteInstruction::try_from_slice(args)?,
+            ),
+            _ => return Err(anyhow!("failed to convert to target JupiterInstruction")),
+        };
+        Ok(ix)
+    }
+}
+
+impl JupiterInstruction {
+    pub fn route_plan(&self) -> &[RoutePlanStep] {
+        match self {
+            Self::Route(ix) => &ix.route_plan,
+            Self::SharedAccountsRoute(ix) => &ix.route_plan,
+            Self::ExactOutRoute(ix) => &ix.route_plan,
+            Self::SharedAccountsExactOutRoute(ix) => &ix.route_plan,
+        }
+    }
+
+    pub fn slippage_bps(&self) -> u16 {
+        match self {
+            Self::Route(ix) => ix.slippage_bps,
+            Self::SharedAccountsRoute(ix) => ix.slippage_bps,
+            Self::ExactOutRoute(ix) => ix.slippage_bps,
+            Self::SharedAccountsExactOutRoute(ix) => ix.slippage_bps,
+        }
+    }
+
+    pub fn is_exact_out(&self) -> bool {
+        matches!(
+            self,
+            Self::ExactOutRoute(_) | Self::SharedAccountsExactOutRoute(_)
+        )
+    }
+
+    /// Slippage limit of the route: the minimum output for exact in routes, the maximum
+    /// input for exact out routes.
+    pub fn other_amount_threshold(&self) -> u64 {
+        let slippage_bps = self.slippage_bps() as u128;
+        match self {
+            Self::Route(RouteInstruction {
+                quoted_out_amount, ..
+            })
+            | Self::SharedAccountsRoute(SharedAccountsRouteInstruction {
+                quoted_out_amount, ..
+            }) => {
+                (*quoted_out_amount as u128 * (10_000 - slippage_bps.min(10_000)) / 10_000) as u64
+            }
+            Self::ExactOutRoute(ExactOutRouteInstruction {
+                quoted_in_amount, ..
+            })
+            | Self::SharedAccountsExactOutRoute(SharedAccountsExactOutRouteInstruction {
+                quoted_in_amount,
+                ..
+            }) => (*quoted_in_amount as u128 * (10_000 + slippage_bps) / 10_000) as u64,
+        }
+    }
+
+    /// Resolves the user facing accounts of the route from the transaction account keys.
+    pub fn route_accounts(
+        &self,
+        ui_ix: &UiCompiledInstruction,
+        account_keys: &[Pubkey],
+    ) -> Option<RouteAccounts> {
+        let key = |position: usize| -> Option<Pubkey> {
+            let index = *ui_ix.accounts.get(position)? as usize;
+            account_keys.get(index).copied()
+        };
+        match self {
+            Self::Route(_) => Some(RouteAccounts {
+                user: key(1)?,
+                source_token_account: key(2)?,
+                destination_token_account: key(3)?,
+                source_mint: None,
+                destination_mint: key(5)?,
+            }),
+            Self::ExactOutRoute(_) => Some(RouteAccounts {
+                user: key(1)?,
+                source_token_account: key(2)?,
+                destination_token_account: key(3)?,
+                source_mint: key(5),
+                destination_mint: key(6)?,
+            }),
+            Self::SharedAccountsRoute(_) | Self::SharedAccountsExactOutRoute(_) => {
+                Some(RouteAccounts {
+                    user: key(2)?,
+                    source_token_account: key(3)?,
+                    destination_token_account: key(6)?,
+                    source_mint: key(7),
+                    destination_mint: key(8)?,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteAccounts {
+    pub user: Pubkey,
+    pub source_token_account: Pubkey,
+    pub destination_token_account: Pubkey,
+    /// `None` for `route`, which doesn't pass it, use the `input_mint` of the first
+    /// leg's `RouteLeg::event` instead
+    pub source_mint: Option<Pubkey>,
+    pub destination_mint: Pubkey,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+// 变体顺序必须与 IDL 完全一致，borsh 按下标解析；
+// 比 BoopdotfunWrappedSell 更新的 AMM 尚未收录，包含它们的 route 会解析失败
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub enum Swap {
+    Saber,
+    SaberAddDecimalsDeposit,
+    SaberAddDecimalsWithdraw,
+    TokenSwap,
+    Sencha,
+    Step,
+    Cropper,
+    Raydium,
+    Crema {
+        a_to_b: bool,
+    },
+    Lifinity,
+    Mercurial,
+    Cykura,
+    Serum {
+        side: Side,
+    },
+    MarinadeDeposit,
+    MarinadeUnstake,
+    Aldrin {
+        side: Side,
+    },
+    AldrinV2 {
+        side: Side,
+    },
+    Whirlpool {
+        a_to_b: bool,
+    },
+    Invariant {
+        x_to_y: bool,
+    },
+    Meteora,
+    GooseFX,
+    DeltaFi {
+        stable: bool,
+    },
+    Balansol,
+    MarcoPolo {
+        x_to_y: bool,
+    },
+    Dradex {
+        side: Side,
+    },
+    LifinityV2,
+    RaydiumClmm,
+    Openbook {
+        side: Side,
+    },
+    Phoenix {
+        side: Side,
+    },
+    Symmetry {
+        from_token_id: u64,
+        to_token_id: u64,
+    },
+    TokenSwapV2,
+    HeliumTreasuryManagementRedeemV0,
+    StakeDexStakeWrappedSol,
+    StakeDexSwapViaStake {
+        bridge_stake_seed: u32,
+    },
+    GooseFXV2,
+    Perps,
+    PerpsAddLiquidity,
+    PerpsRemoveLiquidity,
+    MeteoraDlmm,
+    OpenBookV2 {
+        side: Side,
+    },
+    RaydiumClmmV2,
+    StakeDexPrefundWithdrawStakeAndDepositStake {
+        bridge_stake_seed: u32,
+    },
+    Clone {
+        pool_index: u8,
+        quantity_is_input: bool,
+        quantity_is_collateral: bool,
+    },
+    SanctumS {
+        src_lst_value_calc_accs: u8,
+        dst_lst_value_calc_accs: u8,
+        src_lst_index: u32,
+        dst_lst_index: u32,
+    },
+    SanctumSAddLiquidity {
+        lst_value_calc_accs: u8,
+        lst_index: u32,
+    },
+    SanctumSRemoveLiquidity {
+        lst_value_calc_accs: u8,
+        lst_index: u32,
+    },
+    RaydiumCP,
+    WhirlpoolSwapV2 {
+        a_to_b: bool,
+        remaining_accounts_info: Option<RemainingAccountsInfo>,
+    },
+    OneIntro,
+    PumpdotfunWrappedBuy,
+    PumpdotfunWrappedSell,
+    PerpsV2,
+    PerpsV2AddLiquidity,
+    PerpsV2RemoveLiquidity,
+    MoonshotWrappedBuy,
+    MoonshotWrappedSell,
+    StabbleStableSwap,
+    StabbleWeightedSwap,
+    Obric {
+        x_to_y: bool,
+    },
+    FoxBuyFromEstimatedCost,
+    FoxClaimPartial {
+        is_y: bool,
+    },
+    SolFi {
+        is_quote_to_base: bool,
+    },
+    SolayerDelegateNoInit,
+    SolayerUndelegateNoInit,
+    TokenMill {
+        side: Side,
+    },
+    DaosFunBuy,
+    DaosFunSell,
+    ZeroFi,
+    StakeDexWithdrawWrappedSol,
+    VirtualsBuy,
+    VirtualsSell,
+    Perena {
+        in_index: u8,
+        out_index: u8,
+    },
+    PumpdotfunAmmBuy,
+    PumpdotfunAmmSell,
+    Gamma,
+    MeteoraDlmmSwapV2 {
+        remaining_accounts_info: RemainingAccountsInfo,
+    },
+    Woofi,
+    MeteoraDammV2,
+    MeteoraDynamicBondingCurveSwap,
+    StabbleStableSwapV2,
+    StabbleWeightedSwapV2,
+    RaydiumLaunchlabBuy {
+        share_fee_rate: u64,
+    },
+    RaydiumLaunchlabSell {
+        share_fee_rate: u64,
+    },
+    BoopdotfunWrappedBuy,
+    BoopdotfunWrappedSell,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct RoutePlanStep {
+    pub swap: Swap,
+    /// Share of the input amount routed through this step, in percent
+    pub percent: u8,
+    /// Index of the input token in the route's intermediate token list
+    pub input_index: u8,
+    pub output_index: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct RouteInstruction {
+    pub route_plan: Vec<RoutePlanStep>,
+    pub in_amount: u64,
+    pub quoted_out_amount: u64,
+    pub slippage_bps: u16,
+    pub platform_fee_bps: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct SharedAccountsRouteInstruction {
+    pub id: u8,
+    pub route_plan: Vec<RoutePlanStep>,
+    pub in_amount: u64,
+    pub quoted_out_amount: u64,
+    pub slippage_bps: u16,
+    pub platform_fee_bps: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct ExactOutRouteInstruction {
+    pub route_plan: Vec<RoutePlanStep>,
+    pub out_amount: u64,
+    pub quoted_in_amount: u64,
+    pub slippage_bps: u16,
+    pub platform_fee_bps: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct SharedAccountsExactOutRouteInstruction {
+    pub id: u8,
+    pub route_plan: Vec<RoutePlanStep>,
+    pub out_amount: u64,
+    pub quoted_in_amount: u64,
+    pub slippage_bps: u16,
+    pub platform_fee_bps: u8,
+}
+
+/// Emitted by Jupiter after every leg, through a self CPI.
+#[derive(Debug, BorshSerialize, Clone, BorshDeserialize, Copy)]
+pub struct SwapEvent {
+    /// Program id of the DEX used by the leg
+    pub amm: Pubkey,
+    pub input_mint: Pubkey,
+    pub input_amount: u64,
+    pub output_mint: Pubkey,
+    pub output_amount: u64,
+}
+
+impl SwapEvent {
+    pub fn try_from_compiled_instruction(
+        ui_compiled_instruction: &UiCompiledInstruction,
+    ) -> Option<SwapEvent> {
+        let data = bs58::decode(&ui_compiled_instruction.data)
+            .into_vec()
+            .ok()?;
+        if data.len() > 16 && data[..8] == EVENT_IX_TAG && data[8..16] == SWAP_EVENT {
+            SwapEvent::try_from_slice(&data[16..]).ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// One leg of a route: the Jupiter `SwapEvent` with the route plan step and the inner DEX
+/// instruction that performed it.
+#[derive(Debug, Clone)]
+pub struct RouteLeg {
+    pub step: Option<RoutePlanStep>,
+    pub event: SwapEvent,
+    /// Position of the DEX instruction in the inner instruction list
+    pub dex_instruction_index: Option<usize>,
+    pub dex_instruction: Option<UiCompiledInstruction>,
+}
+
+/// Links every `SwapEvent` in `inner_instructions` (the inner instructions of one Jupiter
+/// route instruction) to the DEX instruction it describes and to its route plan step.
+///
+/// The DEX instruction is the closest preceding instruction invoked on `event.amm`, searching
+/// back no further than the previous event.
+pub fn link_legs(
+    route: &JupiterInstruction,
+    inner_instructions: &[UiCompiledInstruction],
+    account_keys: &[Pubkey],
+) -> Vec<RouteLeg> {
+    let mut legs = Vec::new();
+    let mut search_from = 0;
+    for (idx, ui_ix) in inner_instructions.iter().enumerate() {
+        let Some(event) = SwapEvent::try_from_compiled_instruction(ui_ix) else {
+            continue;
+        };
+
+        let dex_instruction_index = (search_from..idx).rev().find(|&i| {
+            account_keys.get(inner_instructions[i].program_id_index as usize) == Some(&event.amm)
+        });
+
+        legs.push(RouteLeg {
+            step: route.route_plan().get(legs.len()).cloned(),
+            event,
+            dex_instruction_index,
+            dex_instruction: dex_instruction_index.map(|i| inner_instructions[i].clone()),
+        });
+        search_from = idx + 1;
+    }
+    legs
+}
+
+/// A top level Jupiter route instruction with its legs linked to the DEX instructions.
+#[derive(Debug, Clone)]
+pub struct JupiterRoute {
+    /// Index of the route in the top level instructions, the DEX instructions of its legs
+    /// are inner instructions of it
+    pub instruction_index: usize,
+    pub instruction: JupiterInstruction,
+    /// `None` when the instruction has fewer accounts than the route requires
+    pub accounts: Option<RouteAccounts>,
+    /// Empty for failed transactions, which have no events
+    pub legs: Vec<RouteLeg>,
+}
+
+impl JupiterRoute {
+    pub fn input_mint(&self) -> Option<Pubkey> {
+        self.accounts
+            .as_ref()
+            .and_then(|accounts| accounts.source_mint)
+            .or_else(|| self.legs.first().map(|leg| leg.event.input_mint))
+    }
+
+    pub fn output_mint(&self) -> Option<Pubkey> {
+        self.accounts
+            .as_ref()
+            .map(|accounts| accounts.destination_mint)
+            .or_else(|| self.legs.last().map(|leg| leg.event.output_mint))
+    }
+
+    /// Amount the user actually paid, summed over the legs taking the input mint since a
+    /// split route starts several legs from it.
+    pub fn input_amount(&self) -> u64 {
+        let Some(mint) = self.input_mint() else {
+            return 0;
+        };
+        self.legs
+            .iter()
+            .filter(|leg| leg.event.input_mint == mint)
+            .map(|leg| leg.event.input_amount)
+            .sum()
+    }
+
+    /// Amount the user actually received, summed over the legs ending in the output mint.
+    pub fn output_amount(&self) -> u64 {
+        let Some(mint) = self.output_mint() else {
+            return 0;
+        };
+        self.legs
+            .iter()
+            .filter(|leg| leg.event.output_mint == mint)
+            .map(|leg| leg.event.output_amount)
+            .sum()
+    }
+
+    /// See `JupiterInstruction::other_amount_threshold`.
+    pub fn slippage_limit(&self) -> u64 {
+        self.instruction.other_amount_threshold()
+    }
+}
+
+/// Decodes the top level Jupiter route instructions of the transaction and links their legs.
+///
+/// Routes invoked through CPI are skipped, their legs can't be told apart from the other
+/// inner instructions of the caller.
+pub fn decode_routes(ctx: &TransactionContext) -> Vec<JupiterRoute> {
+    let jupiter = constants::JUPITER_V6_PROGRAM;
+    ctx.instructions
+        .iter()
+        .enumerate()
+        .filter_map(|(index, ix)| {
+            if ctx.program_id(ix)? != jupiter {
+                return None;
+            }
+            let instruction = JupiterInstruction::try_from(ix).ok()?;
+            let inner = ctx
+                .inner_instructions
+                .as_ref()
+                .and_then(|inner| inner.get(index))
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            Some(JupiterRoute {
+                instruction_index: index,
+                accounts: instruction.route_accounts(ix, &ctx.account_keys),
+                legs: link_legs(&instruction, inner, &ctx.account_keys),
+                instruction,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use solana_sdk::signature::Signature;
+
+    use crate::decoder::compiled_instruction;
+    use crate::utils::calculate_discriminator;
+
+    fn compiled(program_id_index: u8, data: Vec<u8>) -> UiInstruction {
+        UiInstruction::Compiled(compiled_instruction(program_id_index, vec![], data))
+    }
+
+    fn event_data(event: &SwapEvent) -> Vec<u8> {
+        let mut data = EVENT_IX_TAG.to_vec();
+        data.extend(SWAP_EVENT);
+        data.extend(borsh::to_vec(event).unwrap());
+        data
+    }
+
+    #[test]
+    fn test_discriminators() {
+        assert_eq!(ROUTE_IX, calculate_discriminator("route"));
+        assert_eq!(
+            SHARED_ACCOUNTS_ROUTE_IX,
+            calculate_discriminator("shared_accounts_route")
+        );
+        assert_eq!(
+            EXACT_OUT_ROUTE_IX,
+            calculate_discriminator("exact_out_route")
+        );
+        assert_eq!(
+            SHARED_ACCOUNTS_EXACT_OUT_ROUTE_IX,
+            calculate_discriminator("shared_accounts_exact_out_route")
+        );
+    }
+
+    #[test]
+    fn test_route() {
+        let route = RouteInstruction {
+            route_plan: vec![
+                RoutePlanStep {
+                    swap: Swap::Raydium,
+                    percent: 100,
+                    input_index: 0,
+                    output_index: 1,
+                },
+                RoutePlanStep {
+                    swap: Swap::Whirlpool { a_to_b: false },
+                    percent: 100,
+                    input_index: 1,
+                    output_index: 2,
+                },
+            ],
+            in_amount: 1_000_000,
+            quoted_out_amount: 2_000_000,
+            slippage_bps: 50,
+            platform_fee_bps: 0,
+        };
+        let mut data = ROUTE_IX.to_vec();
+        data.extend(borsh::to_vec(&route).unwrap());
+
+        let ix = JupiterInstruction::try_from(compiled(0, data)).unwrap();
+        assert_eq!(ix.route_plan().len(), 2);
+        assert!(!ix.is_exact_out());
+        assert_eq!(ix.other_amount_threshold(), 1_990_000);
+    }
+
+    #[test]
+    fn test_decode_routes() {
+        let keys: Vec<Pubkey> = (0..10).map(|_| Pubkey::new_unique()).collect();
+        let (user, destination_mint) = (keys[1], keys[5]);
+        let raydium = constants::RAYDIUM_AAM_PROGRAM;
+        let whirlpool = constants::ORCA_WHIRLPOOL_PROGRAM;
+        let token = constants::TOKEN_PROGRAM;
+        // 0..9 是 route 的账户，之后是 jupiter 和各 DEX
+        let mut account_keys = keys.clone();
+        account_keys.extend([constants::JUPITER_V6_PROGRAM, raydium, whirlpool, token]);
+        let (jupiter_index, raydium_index, whirlpool_index, token_index) = (10, 11, 12, 13);
+
+        let route = RouteInstruction {
+            route_plan: vec![
+                RoutePlanStep {
+                    swap: Swap::Raydium,
+                    percent: 100,
+                    input_index: 0,
+                    output_index: 1,
+                },
+                RoutePlanStep {
+                    swap: Swap::Whirlpool { a_to_b: false },
+                    percent: 100,
+                    input_index: 1,
+                    output_index: 2,
+                },
+            ],
+            in_amount: 1_000,
+            quoted_out_amount: 2_000,
+            slippage_bps: 50,
+            platform_fee_bps: 0,
+        };
+        let mut data = ROUTE_IX.to_vec();
+        data.extend(borsh::to_vec(&route).unwrap());
+        let route_ix = compiled_instruction(jupiter_index, (0..9).collect(), data);
+
+        let (input_mint, middle_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let first = SwapEvent {
+            amm: raydium,
+            input_mint,
+            input_amount: 1_000,
+            output_mint: middle_mint,
+            output_amount: 500,
+        };
+        let second = SwapEvent {
+            amm: whirlpool,
+            input_mint: middle_mint,
+            input_amount: 500,
+            output_mint: destination_mint,
+            output_amount: 1_995,
+        };
+        let inner = vec![
+            compiled_instruction(raydium_index, vec![], [9, 1, 2, 3]), // raydium swap_base_in
+            compiled_instruction(token_index, vec![], [3]),            // token transfer
+            compiled_instruction(token_index, vec![], [3]),
+            compiled_instruction(jupiter_index, vec![], event_data(&first)),
+            compiled_instruction(whirlpool_index, vec![], [1]),
+            compiled_instruction(jupiter_index, vec![], event_data(&second)),
+        ];
+        let ctx = TransactionContext {
+            slot: 1,
+            signature: Signature::default(),
+            writable: vec![false; account_keys.len()],
+            account_keys,
+            instructions: vec![route_ix],
+            inner_instructions: Some(vec![inner]),
+            pre_token_balances: HashMap::new(),
+            post_token_balances: HashMap::new(),
+            log_messages: vec![],
+            compute_units_consumed: None,
+            error: None,
+        };
+
+        let routes = decode_routes(&ctx);
+        assert_eq!(routes.len(), 1);
+        let route = &routes[0];
+        assert_eq!(route.instruction_index, 0);
+        assert_eq!(route.accounts.as_ref().unwrap().user, user);
+        assert_eq!(route.legs.len(), 2);
+        assert_eq!(route.legs[0].dex_instruction_index, Some(0));
+        assert_eq!(route.legs[1].dex_instruction_index, Some(4));
+        assert!(matches!(
+            route.legs[1].step.as_ref().unwrap().swap,
+            Swap::Whirlpool { a_to_b: false }
+        ));
+        // route 不传 source mint，取第一个 leg 的输入
+        assert_eq!(route.input_mint(), Some(input_mint));
+        assert_eq!(route.output_mint(), Some(destination_mint));
+        assert_eq!(
+            (route.input_amount(), route.output_amount()),
+            (1_000, 1_995)
+        );
+        assert_eq!(route.slippage_limit(), 1_990);
+    }
+}
@@ -0,0 +1,523 @@
+use anyhow::{Result, anyhow};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{UiCompiledInstruction, UiInstruction};
+
+use crate::constants;
+use crate::decoder::{DexDecoder, InstructionPath, SwapEvent, TransactionContext, Venue};
+
+// Anchor 指令 discriminator: sha256("global:<name>")[..8]
+const INITIALIZE_IX: [u8; 8] = [175, 175, 109, 31, 13, 152, 155, 237];
+const BUY_EXACT_IN_IX: [u8; 8] = [250, 234, 13, 123, 213, 156, 19, 236];
+const BUY_EXACT_OUT_IX: [u8; 8] = [24, 211, 116, 40, 105, 3, 153, 56];
+const SELL_EXACT_IN_IX: [u8; 8] = [149, 39, 222, 155, 211, 124, 152, 26];
+const SELL_EXACT_OUT_IX: [u8; 8] = [95, 200, 71, 34, 8, 9, 11, 166];
+const MIGRATE_TO_AMM_IX: [u8; 8] = [207, 82, 192, 145, 254, 207, 145, 223];
+const MIGRATE_TO_CPSWAP_IX: [u8; 8] = [136, 92, 200, 103, 28, 218, 144, 140];
+
+// sha256("account:PoolState")[..8]
+const POOL_STATE_ACCOUNT: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+
+/// 费率分母，global/platform config 中的费率均以百万分之一为单位
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+// https://github.com/raydium-io/raydium-idl/blob/master/raydium_launchpad/raydium_launchpad.json
+// 这里监听的是指令
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+pub enum LaunchlabInstruction {
+    ///   Create a new token and its bonding curve pool.
+    ///
+    ///   0. `[writable, signer]` Payer
+    ///   1. `[signer]` Creator
+    ///   2. `[]` Global config Account
+    ///   3. `[]` Platform config Account
+    ///   4. `[]` $authority, vault authority PDA
+    ///   5. `[writable]` Pool state Account
+    ///   6. `[writable, signer]` Base mint
+    ///   7. `[]` Quote mint
+    ///   8. `[writable]` Base vault
+    ///   9. `[writable]` Quote vault
+    ///   10. `[writable]` Metadata Account
+    ///   11. `[]` Base token program
+    ///   12. `[]` Quote token program
+    ///   13. `[]` Metadata program
+    ///   14. `[]` Sys program id
+    ///   15. `[]` Rent program id
+    ///   16. `[]` Event authority
+    ///   17. `[]` Program
+    Initialize(InitializeInstruction),
+
+    ///   Buy base token with an exact amount of quote token.
+    ///
+    ///   0. `[signer]` User wallet Account
+    ///   1. `[]` $authority, vault authority PDA
+    ///   2. `[]` Global config Account
+    ///   3. `[]` Platform config Account
+    ///   4. `[writable]` Pool state Account
+    ///   5. `[writable]` User base token Account
+    ///   6. `[writable]` User quote token Account
+    ///   7. `[writable]` Base vault
+    ///   8. `[writable]` Quote vault
+    ///   9. `[]` Base token mint
+    ///   10. `[]` Quote token mint
+    ///   11. `[]` Base token program
+    ///   12. `[]` Quote token program
+    ///   13. `[]` Event authority
+    ///   14. `[]` Program
+    BuyExactIn(SwapExactInInstruction),
+
+    ///   Buy an exact amount of base token. Accounts as in `BuyExactIn`.
+    BuyExactOut(SwapExactOutInstruction),
+
+    ///   Sell an exact amount of base token. Accounts as in `BuyExactIn`.
+    SellExactIn(SwapExactInInstruction),
+
+    ///   Sell base token for an exact amount of quote token. Accounts as in `BuyExactIn`.
+    SellExactOut(SwapExactOutInstruction),
+
+    ///   Migrate a completed curve to a Raydium AMM v4 pool with a new OpenBook market.
+    MigrateToAmm(MigrateToAmmInstruction),
+
+    ///   Migrate a completed curve to a Raydium CPMM pool.
+    MigrateToCpswap,
+}
+
+impl TryFrom<UiInstruction> for LaunchlabInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ix: UiInstruction) -> Result<LaunchlabInstruction> {
+        match ix {
+            UiInstruction::Compiled(ui_ix) => LaunchlabInstruction::try_from(&ui_ix),
+            _ => Err(anyhow!("failed to convert to target LaunchlabInstruction")),
+        }
+    }
+}
+
+impl TryFrom<&UiCompiledInstruction> for LaunchlabInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ui_ix: &UiCompiledInstruction) -> Result<LaunchlabInstruction> {
+        let data = bs58::decode(&ui_ix.data).into_vec()?;
+        if data.len() < 8 {
+            return Err(anyhow!("failed to convert to target LaunchlabInstruction"));
+        }
+
+        let (discriminator, args) = data.split_at(8);
+        let ix = match discriminator {
+            d if d == INITIALIZE_IX => {
+                Self::Initialize(InitializeInstruction::try_from_slice(args)?)
+            }
+            d if d == BUY_EXACT_IN_IX => {
+                Self::BuyExactIn(SwapExactInInstruction::try_from_slice(args)?)
+            }
+            d if d == BUY_EXACT_OUT_IX => {
+                Self::BuyExactOut(SwapExactOutInstruction::try_from_slice(args)?)
+            }
+            d if d == SELL_EXACT_IN_IX => {
+                Self::SellExactIn(SwapExactInInstruction::try_from_slice(args)?)
+            }
+            d if d == SELL_EXACT_OUT_IX => {
+                Self::SellExactOut(SwapExactOutInstruction::try_from_slice(args)?)
+            }
+            d if d == MIGRATE_TO_AMM_IX => {
+                Self::MigrateToAmm(MigrateToAmmInstruction::try_from_slice(args)?)
+            }
+            d if d == MIGRATE_TO_CPSWAP_IX && args.is_empty() => Self::MigrateToCpswap,
+            _ => return Err(anyhow!("failed to convert to target LaunchlabInstruction")),
+        };
+        Ok(ix)
+    }
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct MintParams {
+    pub decimals: u8,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct ConstantCurve {
+    pub supply: u64,
+    pub total_base_sell: u64,
+    pub total_quote_fund_raising: u64,
+    pub migrate_type: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct FixedCurve {
+    pub supply: u64,
+    pub total_quote_fund_raising: u64,
+    pub migrate_type: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct LinearCurve {
+    pub supply: u64,
+    pub total_quote_fund_raising: u64,
+    pub migrate_type: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub enum CurveParams {
+    Constant { data: ConstantCurve },
+    Fixed { data: FixedCurve },
+    Linear { data: LinearCurve },
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct VestingParams {
+    pub total_locked_amount: u64,
+    pub cliff_period: u64,
+    pub unlock_period: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct InitializeInstruction {
+    pub base_mint_param: MintParams,
+    pub curve_param: CurveParams,
+    pub vesting_param: VestingParams,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct SwapExactInInstruction {
+    /// Quote amount for buys, base amount for sells
+    pub amount_in: u64,
+    /// Minimum amount of output token, prevents excessive slippage
+    pub minimum_amount_out: u64,
+    /// Fee shared with the referrer, in FEE_RATE_DENOMINATOR units
+    pub share_fee_rate: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct SwapExactOutInstruction {
+    pub amount_out: u64,
+    /// Maximum amount of input token, prevents excessive slippage
+    pub maximum_amount_in: u64,
+    pub share_fee_rate: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct MigrateToAmmInstruction {
+    pub base_lot_size: u64,
+    pub quote_tick_size: u64,
+    pub market_vault_signer_nonce: u8,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct VestingSchedule {
+    pub total_locked_amount: u64,
+    pub cliff_period: u64,
+    pub unlock_period: u64,
+    pub start_time: u64,
+    pub allocated_share_amount: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolStatus {
+    /// Bonding curve still trading
+    Fund,
+    /// Fundraising target reached, waiting for migration
+    Migrate,
+    /// Liquidity migrated to an AMM
+    Trade,
+}
+
+// https://github.com/raydium-io/raydium-idl/blob/master/raydium_launchpad/raydium_launchpad.json
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct PoolState {
+    pub epoch: u64,
+    pub auth_bump: u8,
+    /// 0: Fund, 1: Migrate, 2: Trade
+    pub status: u8,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub migrate_type: u8,
+    pub supply: u64,
+    pub total_base_sell: u64,
+    pub virtual_base: u64,
+    pub virtual_quote: u64,
+    pub real_base: u64,
+    pub real_quote: u64,
+    pub total_quote_fund_raising: u64,
+    pub quote_protocol_fee: u64,
+    pub platform_fee: u64,
+    pub migrate_fee: u64,
+    pub vesting_schedule: VestingSchedule,
+    pub global_config: Pubkey,
+    pub platform_config: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub creator: Pubkey,
+}
+
+impl PoolState {
+    /// Decodes the raw `PoolState` account data, including the 8 byte account discriminator.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 || data[..8] != POOL_STATE_ACCOUNT {
+            return Err(anyhow!(
+                "failed to convert to target LaunchLab PoolState account"
+            ));
+        }
+        Ok(PoolState::deserialize(&mut &data[8..])?)
+    }
+
+    pub fn pool_status(&self) -> PoolStatus {
+        match self.status {
+            0 => PoolStatus::Fund,
+            1 => PoolStatus::Migrate,
+            _ => PoolStatus::Trade,
+        }
+    }
+
+    // 恒定乘积曲线: (virtual_base - real_base) * (virtual_quote + real_quote) = k
+    fn base_reserve(&self) -> u128 {
+        self.virtual_base.saturating_sub(self.real_base) as u128
+    }
+
+    fn quote_reserve(&self) -> u128 {
+        self.virtual_quote as u128 + self.real_quote as u128
+    }
+
+    /// Price of one base unit in quote units, adjusted for mint decimals.
+    pub fn price(&self) -> f64 {
+        let base = self.base_reserve() as f64 / 10f64.powi(self.base_decimals as i32);
+        let quote = self.quote_reserve() as f64 / 10f64.powi(self.quote_decimals as i32);
+        if base == 0.0 { 0.0 } else { quote / base }
+    }
+
+    /// Share of the fundraising target already raised, between 0 and 1.
+    pub fn progress(&self) -> f64 {
+        if self.total_quote_fund_raising == 0 {
+            return 0.0;
+        }
+        self.real_quote as f64 / self.total_quote_fund_raising as f64
+    }
+
+    /// Base tokens received for `quote_in`, after deducting `fee_rate` (trade + platform + share
+    /// fee, in FEE_RATE_DENOMINATOR units) from the input. `None` for a fee rate above 100% or an
+    /// empty pool.
+    pub fn quote_buy_exact_in(&self, quote_in: u64, fee_rate: u64) -> Option<u64> {
+        let quote_in = (quote_in as u128).checked_sub(fee(quote_in, fee_rate)? as u128)?;
+        let base_out =
+            (self.base_reserve() * quote_in).checked_div(self.quote_reserve() + quote_in)?;
+        u64::try_from(base_out).ok()
+    }
+
+    /// Quote tokens needed, fee included, to receive exactly `base_out`.
+    pub fn quote_buy_exact_out(&self, base_out: u64, fee_rate: u64) -> Option<u64> {
+        let base_reserve = self.base_reserve();
+        let base_out = base_out as u128;
+        if base_out >= base_reserve {
+            return None;
+        }
+        // 分母大于 0，只需检查乘法
+        let quote_in = self
+            .quote_reserve()
+            .checked_mul(base_out)?
+            .div_ceil(base_reserve - base_out);
+        gross_up(quote_in, fee_rate)
+    }
+
+    /// Quote tokens received for `base_in`, after deducting `fee_rate` from the output.
+    pub fn quote_sell_exact_in(&self, base_in: u64, fee_rate: u64) -> Option<u64> {
+        let base_in = base_in as u128;
+        let quote_out = self
+            .quote_reserve()
+            .checked_mul(base_in)?
+            .checked_div(self.base_reserve() + base_in)?;
+        let quote_out = u64::try_from(quote_out).ok()?;
+        quote_out.checked_sub(fee(quote_out, fee_rate)?)
+    }
+
+    /// Base tokens needed to receive exactly `quote_out` after fees.
+    pub fn quote_sell_exact_out(&self, quote_out: u64, fee_rate: u64) -> Option<u64> {
+        let quote_reserve = self.quote_reserve();
+        let quote_out = gross_up(quote_out as u128, fee_rate)? as u128;
+        if quote_out >= quote_reserve {
+            return None;
+        }
+        let base_in = self
+            .base_reserve()
+            .checked_mul(quote_out)?
+            .div_ceil(quote_reserve - quote_out);
+        u64::try_from(base_in).ok()
+    }
+}
+
+fn fee(amount: u64, fee_rate: u64) -> Option<u64> {
+    u64::try_from((amount as u128 * fee_rate as u128).div_ceil(FEE_RATE_DENOMINATOR as u128)).ok()
+}
+
+// 反推含手续费的金额: amount / (1 - fee_rate)
+fn gross_up(amount: u128, fee_rate: u64) -> Option<u64> {
+    if fee_rate >= FEE_RATE_DENOMINATOR {
+        return None;
+    }
+    let denominator = (FEE_RATE_DENOMINATOR - fee_rate) as u128;
+    let amount = amount.checked_mul(FEE_RATE_DENOMINATOR as u128)?;
+    u64::try_from(amount.div_ceil(denominator)).ok()
+}
+
+/// Emits a `SwapEvent` for every bonding-curve buy or sell.
+pub struct LaunchlabDecoder;
+
+impl DexDecoder for LaunchlabDecoder {
+    fn program_id(&self) -> Pubkey {
+        constants::RAYDIUM_LAUNCHLAB_PROGRAM
+    }
+
+    fn venue(&self) -> Venue {
+        Venue::RaydiumLaunchlab
+    }
+
+    fn decode_instruction(
+        &self,
+        ctx: &TransactionContext,
+        path: InstructionPath,
+        ix: &UiCompiledInstruction,
+    ) -> Option<SwapEvent> {
+        match LaunchlabInstruction::try_from(ix).ok()? {
+            LaunchlabInstruction::BuyExactIn(_)
+            | LaunchlabInstruction::BuyExactOut(_)
+            | LaunchlabInstruction::SellExactIn(_)
+            | LaunchlabInstruction::SellExactOut(_) => {
+                ctx.swap_from_vaults(self.venue(), path, ix, 4, 0, [7, 8])
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::anchor_instruction;
+    use crate::utils::calculate_discriminator;
+
+    fn pool() -> PoolState {
+        PoolState {
+            epoch: 0,
+            auth_bump: 255,
+            status: 0,
+            base_decimals: 6,
+            quote_decimals: 9,
+            migrate_type: 1,
+            supply: 1_000_000_000_000_000,
+            total_base_sell: 793_100_000_000_000,
+            virtual_base: 1_073_025_605_596_382,
+            virtual_quote: 30_000_852_951,
+            real_base: 0,
+            real_quote: 0,
+            total_quote_fund_raising: 85_000_000_000,
+            quote_protocol_fee: 0,
+            platform_fee: 0,
+            migrate_fee: 0,
+            vesting_schedule: VestingSchedule {
+                total_locked_amount: 0,
+                cliff_period: 0,
+                unlock_period: 0,
+                start_time: 0,
+                allocated_share_amount: 0,
+            },
+            global_config: Pubkey::new_unique(),
+            platform_config: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            base_vault: Pubkey::new_unique(),
+            quote_vault: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+        }
+    }
+
+    #[test]
+    fn test_discriminators() {
+        assert_eq!(INITIALIZE_IX, calculate_discriminator("initialize"));
+        assert_eq!(BUY_EXACT_IN_IX, calculate_discriminator("buy_exact_in"));
+        assert_eq!(SELL_EXACT_OUT_IX, calculate_discriminator("sell_exact_out"));
+        assert_eq!(
+            MIGRATE_TO_CPSWAP_IX,
+            calculate_discriminator("migrate_to_cpswap")
+        );
+    }
+
+    #[test]
+    fn test_buy_exact_in() {
+        let ui_ix = anchor_instruction(
+            BUY_EXACT_IN_IX,
+            SwapExactInInstruction {
+                amount_in: 1_000_000_000,
+                minimum_amount_out: 1,
+                share_fee_rate: 0,
+            },
+        );
+        match LaunchlabInstruction::try_from(UiInstruction::Compiled(ui_ix)).unwrap() {
+            LaunchlabInstruction::BuyExactIn(buy) => assert_eq!(buy.amount_in, 1_000_000_000),
+            x => panic!("unexpected instruction {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_pool_state_account() {
+        let mut data = POOL_STATE_ACCOUNT.to_vec();
+        data.extend(borsh::to_vec(&pool()).unwrap());
+        data.extend([0u8; 64]); // padding
+        let decoded = PoolState::try_from_account_data(&data).unwrap();
+        assert_eq!(decoded.virtual_base, pool().virtual_base);
+        assert_eq!(decoded.pool_status(), PoolStatus::Fund);
+    }
+
+    #[test]
+    fn test_quotes() {
+        let pool = pool();
+        let fee_rate = 10_000; // 1%
+
+        let base_out = pool.quote_buy_exact_in(1_000_000_000, fee_rate).unwrap();
+        assert!(base_out > 0);
+        // 反向报价需要不少于原输入
+        let quote_in = pool.quote_buy_exact_out(base_out, fee_rate).unwrap();
+        assert!(quote_in <= 1_000_000_000 && quote_in > 999_000_000);
+
+        let quote_out = pool.quote_sell_exact_in(base_out, fee_rate).unwrap();
+        assert!(quote_out < 1_000_000_000);
+        let base_in = pool.quote_sell_exact_out(quote_out, fee_rate).unwrap();
+        assert!(base_in <= base_out);
+
+        assert!(
+            pool.quote_buy_exact_out(pool.virtual_base, fee_rate)
+                .is_none()
+        );
+
+        // 手续费率超过 100% 时没有报价，而不是溢出
+        let bad_fee = FEE_RATE_DENOMINATOR + 1;
+        assert_eq!(pool.quote_buy_exact_in(1_000_000_000, bad_fee), None);
+        assert_eq!(pool.quote_sell_exact_in(base_out, bad_fee), None);
+        assert_eq!(pool.quote_buy_exact_out(base_out, bad_fee), None);
+        assert_eq!(pool.quote_buy_exact_in(1_000, u64::MAX), None);
+
+        // 空池子且输入为 0 时不能除以 0
+        let empty = PoolState {
+            virtual_base: 0,
+            virtual_quote: 0,
+            real_base: 0,
+            real_quote: 0,
+            ..pool.clone()
+        };
+        assert_eq!(empty.quote_buy_exact_in(0, fee_rate), None);
+        assert_eq!(empty.quote_sell_exact_in(0, fee_rate), None);
+
+        // quote 储备可达 2^65，乘积超出 u128 时没有报价而不是溢出
+        let full = PoolState {
+            virtual_base: u64::MAX,
+            virtual_quote: u64::MAX,
+            real_base: 0,
+            real_quote: u64::MAX,
+            ..pool.clone()
+        };
+        assert_eq!(full.quote_buy_exact_out(u64::MAX - 1, 0), None);
+        assert_eq!(full.quote_buy_exact_out(u64::MAX / 2, 0), None);
+    }
+}
@@ -0,0 +1,620 @@
+use anyhow::{Result, anyhow};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{UiCompiledInstruction, UiInstruction};
+
+use crate::constants;
+use crate::decoder::{DexDecoder, InstructionPath, SwapEvent, TransactionContext, Venue};
+use crate::raydium::{SwapInstructionBaseIn, SwapInstructionBaseOut};
+
+// Anchor 指令 discriminator: sha256("global:<name>")[..8]
+// DLMM 与 DAMM 的 `swap` discriminator 相同，需先按 program id 区分再解析
+const SWAP_IX: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+const DLMM_SWAP2_IX: [u8; 8] = [65, 75, 63, 76, 235, 91, 91, 136];
+const DLMM_SWAP_EXACT_OUT_IX: [u8; 8] = [250, 73, 101, 33, 38, 207, 75, 184];
+const DLMM_ADD_LIQUIDITY_IX: [u8; 8] = [181, 157, 89, 67, 143, 182, 52, 72];
+const DLMM_ADD_LIQUIDITY_BY_STRATEGY_IX: [u8; 8] = [7, 3, 150, 127, 148, 40, 61, 200];
+const DLMM_REMOVE_LIQUIDITY_IX: [u8; 8] = [80, 85, 209, 72, 24, 206, 177, 108];
+const DLMM_REMOVE_LIQUIDITY_BY_RANGE_IX: [u8; 8] = [26, 82, 102, 152, 240, 74, 105, 26];
+const DAMM_ADD_BALANCE_LIQUIDITY_IX: [u8; 8] = [168, 227, 50, 62, 189, 171, 84, 176];
+const DAMM_REMOVE_BALANCE_LIQUIDITY_IX: [u8; 8] = [133, 109, 44, 179, 56, 238, 114, 33];
+const DAMM_ADD_IMBALANCE_LIQUIDITY_IX: [u8; 8] = [79, 35, 122, 84, 173, 15, 93, 191];
+const DAMM_REMOVE_LIQUIDITY_SINGLE_SIDE_IX: [u8; 8] = [84, 84, 177, 66, 254, 185, 10, 251];
+
+// sha256("account:<name>")[..8]
+const LB_PAIR_ACCOUNT: [u8; 8] = [33, 11, 49, 98, 181, 101, 177, 13];
+const DAMM_POOL_ACCOUNT: [u8; 8] = [241, 154, 109, 4, 17, 177, 109, 188];
+
+// https://github.com/MeteoraAg/dlmm-sdk/blob/main/idls/dlmm.json
+// 这里监听的是指令，swap 参数复用 raydium 的结构，方便策略统一处理
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+pub enum DlmmInstruction {
+    ///   Swap exact amount_in with a slippage of min_amount_out
+    ///
+    ///   0. `[writable]` LbPair Account
+    ///   1. `[writable]` (optional) Bin array bitmap extension
+    ///   2. `[writable]` Reserve X
+    ///   3. `[writable]` Reserve Y
+    ///   4. `[writable]` User token in Account
+    ///   5. `[writable]` User token out Account
+    ///   6. `[]` Token X mint
+    ///   7. `[]` Token Y mint
+    ///   8. `[writable]` Oracle
+    ///   9. `[writable]` (optional) Host fee in Account
+    ///   10. `[signer]` User wallet Account
+    ///   11. `[]` Token X program
+    ///   12. `[]` Token Y program
+    ///   13. `[]` Event authority
+    ///   14. `[]` Program
+    Swap(SwapInstructionBaseIn),
+
+    ///   Same as `Swap` plus remaining accounts info for Token-2022 transfer hooks.
+    ///   Accounts as in `Swap`, with the memo program inserted after the token programs.
+    Swap2(SwapInstructionBaseIn),
+
+    ///   Swap exact amount_out with a slippage of max_amount_in. Accounts as in `Swap`.
+    SwapExactOut(SwapInstructionBaseOut),
+
+    ///   Add liquidity with an explicit distribution per bin.
+    ///
+    ///   0. `[writable]` Position Account
+    ///   1. `[writable]` LbPair Account
+    ///   2. `[writable]` (optional) Bin array bitmap extension
+    ///   3. `[writable]` User token X Account
+    ///   4. `[writable]` User token Y Account
+    ///   5. `[writable]` Reserve X
+    ///   6. `[writable]` Reserve Y
+    ///   7. `[]` Token X mint
+    ///   8. `[]` Token Y mint
+    ///   9. `[writable]` Bin array lower
+    ///   10. `[writable]` Bin array upper
+    ///   11. `[signer]` Sender
+    AddLiquidity(LiquidityParameter),
+
+    ///   Add liquidity following a distribution strategy. Accounts as in `AddLiquidity`.
+    AddLiquidityByStrategy(LiquidityParameterByStrategy),
+
+    ///   Remove liquidity from explicit bins. Accounts as in `AddLiquidity`.
+    RemoveLiquidity(RemoveLiquidityInstruction),
+
+    ///   Remove liquidity from a bin range. Accounts as in `AddLiquidity`.
+    RemoveLiquidityByRange(RemoveLiquidityByRangeInstruction),
+}
+
+impl TryFrom<UiInstruction> for DlmmInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ix: UiInstruction) -> Result<DlmmInstruction> {
+        match ix {
+            UiInstruction::Compiled(ui_ix) => DlmmInstruction::try_from(&ui_ix),
+            _ => Err(anyhow!("failed to convert to target DlmmInstruction")),
+        }
+    }
+}
+
+impl TryFrom<&UiCompiledInstruction> for DlmmInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ui_ix: &UiCompiledInstruction) -> Result<DlmmInstruction> {
+        let data = bs58::decode(&ui_ix.data).into_vec()?;
+        if data.len() < 8 {
+            return Err(anyhow!("failed to convert to target DlmmInstruction"));
+        }
+
+        let (discriminator, mut args) = data.split_at(8);
+        let ix = match discriminator {
+            d if d == SWAP_IX => Self::Swap(SwapInstructionBaseIn::try_from_slice(args)?),
+            // swap2 在金额后面追加了 remaining_accounts_info，这里只取金额部分
+            d if d == DLMM_SWAP2_IX => Self::Swap2(SwapInstructionBaseIn::deserialize(&mut args)?),
+            d if d == DLMM_SWAP_EXACT_OUT_IX => {
+                Self::SwapExactOut(SwapInstructionBaseOut::try_from_slice(args)?)
+            }
+            d if d == DLMM_ADD_LIQUIDITY_IX => {
+                Self::AddLiquidity(LiquidityParameter::try_from_slice(args)?)
+            }
+            d if d == DLMM_ADD_LIQUIDITY_BY_STRATEGY_IX => {
+                Self::AddLiquidityByStrategy(LiquidityParameterByStrategy::try_from_slice(args)?)
+            }
+            d if d == DLMM_REMOVE_LIQUIDITY_IX => {
+                Self::RemoveLiquidity(RemoveLiquidityInstruction::try_from_slice(args)?)
+            }
+            d if d == DLMM_REMOVE_LIQUIDITY_BY_RANGE_IX => Self::RemoveLiquidityByRange(
+                RemoveLiquidityByRangeInstruction::try_from_slice(args)?,
+            ),
+            _ => return Err(anyhow!("failed to convert to target DlmmInstruction")),
+        };
+        Ok(ix)
+    }
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct BinLiquidityDistribution {
+    pub bin_id: i32,
+    /// Share of amount_x in basis points
+    pub distribution_x: u16,
+    /// Share of amount_y in basis points
+    pub distribution_y: u16,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct LiquidityParameter {
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub bin_liquidity_dist: Vec<BinLiquidityDistribution>,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Eq)]
+pub enum StrategyType {
+    SpotOneSide,
+    CurveOneSide,
+    BidAskOneSide,
+    SpotBalanced,
+    CurveBalanced,
+    BidAskBalanced,
+    SpotImBalanced,
+    CurveImBalanced,
+    BidAskImBalanced,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct StrategyParameters {
+    pub min_bin_id: i32,
+    pub max_bin_id: i32,
+    pub strategy_type: StrategyType,
+    pub parameteres: [u8; 64],
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct LiquidityParameterByStrategy {
+    pub amount_x: u64,
+    pub amount_y: u64,
+    /// Active bin seen by the client, checked against max_active_bin_slippage
+    pub active_id: i32,
+    pub max_active_bin_slippage: i32,
+    pub strategy_parameters: StrategyParameters,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct BinLiquidityReduction {
+    pub bin_id: i32,
+    pub bps_to_remove: u16,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct RemoveLiquidityInstruction {
+    pub bin_liquidity_removal: Vec<BinLiquidityReduction>,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct RemoveLiquidityByRangeInstruction {
+    pub from_bin_id: i32,
+    pub to_bin_id: i32,
+    pub bps_to_remove: u16,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct StaticParameters {
+    pub base_factor: u16,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub variable_fee_control: u32,
+    pub max_volatility_accumulator: u32,
+    pub min_bin_id: i32,
+    pub max_bin_id: i32,
+    pub protocol_share: u16,
+    pub base_fee_power_factor: u8,
+    pub padding: [u8; 5],
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct VariableParameters {
+    pub volatility_accumulator: u32,
+    pub volatility_reference: u32,
+    pub index_reference: i32,
+    pub padding: [u8; 4],
+    pub last_update_timestamp: i64,
+    pub padding1: [u8; 8],
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct ProtocolFee {
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct RewardInfo {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub funder: Pubkey,
+    pub reward_duration: u64,
+    pub reward_duration_end: u64,
+    pub reward_rate: u128,
+    pub last_update_time: u64,
+    pub cumulative_seconds_with_empty_liquidity_reward: u64,
+}
+
+// https://github.com/MeteoraAg/dlmm-sdk/blob/main/programs/lb_clmm/src/state/lb_pair/mod.rs
+// 账户为 zero-copy 布局，字段之间没有隐式 padding，可以直接按 borsh 顺序解析；
+// 只解析到 last_updated_at，后面的字段用不到
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct LbPair {
+    pub parameters: StaticParameters,
+    pub v_parameters: VariableParameters,
+    pub bump_seed: [u8; 1],
+    pub bin_step_seed: [u8; 2],
+    pub pair_type: u8,
+    /// Bin currently holding the market price
+    pub active_id: i32,
+    /// Price increment between bins in basis points
+    pub bin_step: u16,
+    pub status: u8,
+    pub require_base_factor_seed: u8,
+    pub base_factor_seed: [u8; 2],
+    pub activation_type: u8,
+    pub creator_pool_on_off_control: u8,
+    pub token_x_mint: Pubkey,
+    pub token_y_mint: Pubkey,
+    pub reserve_x: Pubkey,
+    pub reserve_y: Pubkey,
+    pub protocol_fee: ProtocolFee,
+    pub padding1: [u8; 32],
+    pub reward_infos: [RewardInfo; 2],
+    pub oracle: Pubkey,
+    pub bin_array_bitmap: [u64; 16],
+    pub last_updated_at: i64,
+}
+
+impl LbPair {
+    /// Decodes the raw `LbPair` account data, including the 8 byte account discriminator.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 || data[..8] != LB_PAIR_ACCOUNT {
+            return Err(anyhow!("failed to convert to target LbPair account"));
+        }
+        Ok(LbPair::deserialize(&mut &data[8..])?)
+    }
+
+    /// Price of token X denominated in token Y at `bin_id`, adjusted for mint decimals.
+    pub fn bin_price(&self, bin_id: i32, decimals_x: u8, decimals_y: u8) -> f64 {
+        let base = 1.0 + self.bin_step as f64 / 10_000.0;
+        base.powi(bin_id) * 10f64.powi(decimals_x as i32 - decimals_y as i32)
+    }
+
+    /// Price of token X denominated in token Y at the active bin.
+    pub fn active_price(&self, decimals_x: u8, decimals_y: u8) -> f64 {
+        self.bin_price(self.active_id, decimals_x, decimals_y)
+    }
+}
+
+// https://github.com/MeteoraAg/damm-v1-sdk/blob/main/ts-client/src/amm/idl.ts
+// Dynamic AMM，swap 参数与 raydium SwapBaseIn 布局一致
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+pub enum DammInstruction {
+    ///   Swap exact in_amount with a slippage of minimum_out_amount
+    ///
+    ///   0. `[writable]` Pool Account
+    ///   1. `[writable]` User source token Account
+    ///   2. `[writable]` User destination token Account
+    ///   3. `[writable]` Vault A
+    ///   4. `[writable]` Vault B
+    ///   5. `[writable]` Token vault A
+    ///   6. `[writable]` Token vault B
+    ///   7. `[writable]` Vault A lp mint
+    ///   8. `[writable]` Vault B lp mint
+    ///   9. `[writable]` Pool's vault A lp Account
+    ///   10. `[writable]` Pool's vault B lp Account
+    ///   11. `[writable]` Protocol token fee Account
+    ///   12. `[signer]` User wallet Account
+    ///   13. `[]` Vault program
+    ///   14. `[]` Token program
+    Swap(SwapInstructionBaseIn),
+
+    ///   Deposit both tokens at the current pool ratio.
+    ///
+    ///   0. `[writable]` Pool Account
+    ///   1. `[writable]` Lp mint
+    ///   2. `[writable]` User pool lp Account
+    ///   3. `[writable]` Pool's vault A lp Account
+    ///   4. `[writable]` Pool's vault B lp Account
+    ///   5. `[writable]` Vault A
+    ///   6. `[writable]` Vault B
+    ///   7. `[writable]` Vault A lp mint
+    ///   8. `[writable]` Vault B lp mint
+    ///   9. `[writable]` Token vault A
+    ///   10. `[writable]` Token vault B
+    ///   11. `[writable]` User token A Account
+    ///   12. `[writable]` User token B Account
+    ///   13. `[signer]` User wallet Account
+    ///   14. `[]` Vault program
+    ///   15. `[]` Token program
+    AddBalanceLiquidity(AddBalanceLiquidityInstruction),
+
+    ///   Withdraw both tokens at the current pool ratio. Accounts as in `AddBalanceLiquidity`.
+    RemoveBalanceLiquidity(RemoveBalanceLiquidityInstruction),
+
+    ///   Deposit tokens at an arbitrary ratio. Accounts as in `AddBalanceLiquidity`.
+    AddImbalanceLiquidity(AddImbalanceLiquidityInstruction),
+
+    ///   Withdraw a single token.
+    RemoveLiquiditySingleSide(RemoveLiquiditySingleSideInstruction),
+}
+
+impl TryFrom<UiInstruction> for DammInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ix: UiInstruction) -> Result<DammInstruction> {
+        match ix {
+            UiInstruction::Compiled(ui_ix) => DammInstruction::try_from(&ui_ix),
+            _ => Err(anyhow!("failed to convert to target DammInstruction")),
+        }
+    }
+}
+
+impl TryFrom<&UiCompiledInstruction> for DammInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ui_ix: &UiCompiledInstruction) -> Result<DammInstruction> {
+        let data = bs58::decode(&ui_ix.data).into_vec()?;
+        if data.len() < 8 {
+            return Err(anyhow!("failed to convert to target DammInstruction"));
+        }
+
+        let (discriminator, args) = data.split_at(8);
+        let ix = match discriminator {
+            d if d == SWAP_IX => Self::Swap(SwapInstructionBaseIn::try_from_slice(args)?),
+            d if d == DAMM_ADD_BALANCE_LIQUIDITY_IX => {
+                Self::AddBalanceLiquidity(AddBalanceLiquidityInstruction::try_from_slice(args)?)
+            }
+            d if d == DAMM_REMOVE_BALANCE_LIQUIDITY_IX => Self::RemoveBalanceLiquidity(
+                RemoveBalanceLiquidityInstruction::try_from_slice(args)?,
+            ),
+            d if d == DAMM_ADD_IMBALANCE_LIQUIDITY_IX => {
+                Self::AddImbalanceLiquidity(AddImbalanceLiquidityInstruction::try_from_slice(args)?)
+            }
+            d if d == DAMM_REMOVE_LIQUIDITY_SINGLE_SIDE_IX => Self::RemoveLiquiditySingleSide(
+                RemoveLiquiditySingleSideInstruction::try_from_slice(args)?,
+            ),
+            _ => return Err(anyhow!("failed to convert to target DammInstruction")),
+        };
+        Ok(ix)
+    }
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct AddBalanceLiquidityInstruction {
+    pub pool_token_amount: u64,
+    pub maximum_token_a_amount: u64,
+    pub maximum_token_b_amount: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct RemoveBalanceLiquidityInstruction {
+    pub pool_token_amount: u64,
+    pub minimum_a_token_out: u64,
+    pub minimum_b_token_out: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct AddImbalanceLiquidityInstruction {
+    pub minimum_pool_token_amount: u64,
+    pub token_a_amount: u64,
+    pub token_b_amount: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct RemoveLiquiditySingleSideInstruction {
+    pub pool_token_amount: u64,
+    pub minimum_out_amount: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct PoolFees {
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub protocol_trade_fee_numerator: u64,
+    pub protocol_trade_fee_denominator: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Eq)]
+pub enum PoolType {
+    Permissioned,
+    Permissionless,
+}
+
+// https://github.com/MeteoraAg/damm-v1-sdk/blob/main/programs/dynamic-amm/src/state.rs
+// 只解析到 total_locked_lp，后面的字段用不到
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct DammPool {
+    pub lp_mint: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    /// Meteora vault holding token A, the pool owns `a_vault_lp` shares of it
+    pub a_vault: Pubkey,
+    pub b_vault: Pubkey,
+    pub a_vault_lp: Pubkey,
+    pub b_vault_lp: Pubkey,
+    pub a_vault_lp_bump: u8,
+    pub enabled: bool,
+    pub protocol_token_a_fee: Pubkey,
+    pub protocol_token_b_fee: Pubkey,
+    pub fee_last_updated_at: u64,
+    pub padding0: [u8; 24],
+    pub fees: PoolFees,
+    pub pool_type: PoolType,
+    pub stake: Pubkey,
+    pub total_locked_lp: u64,
+}
+
+impl DammPool {
+    /// Decodes the raw `Pool` account data, including the 8 byte account discriminator.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 || data[..8] != DAMM_POOL_ACCOUNT {
+            return Err(anyhow!("failed to convert to target Meteora Pool account"));
+        }
+        Ok(DammPool::deserialize(&mut &data[8..])?)
+    }
+
+    /// Trade fee as a fraction, e.g. 0.0025 for a 0.25% pool.
+    pub fn fee(&self) -> f64 {
+        if self.fees.trade_fee_denominator == 0 {
+            return 0.0;
+        }
+        self.fees.trade_fee_numerator as f64 / self.fees.trade_fee_denominator as f64
+    }
+}
+
+/// Emits a `SwapEvent` for every DLMM `Swap` / `Swap2` / `SwapExactOut`.
+pub struct DlmmDecoder;
+
+impl DexDecoder for DlmmDecoder {
+    fn program_id(&self) -> Pubkey {
+        constants::METEORA_DLMM_PROGRAM
+    }
+
+    fn venue(&self) -> Venue {
+        Venue::MeteoraDlmm
+    }
+
+    fn decode_instruction(
+        &self,
+        ctx: &TransactionContext,
+        path: InstructionPath,
+        ix: &UiCompiledInstruction,
+    ) -> Option<SwapEvent> {
+        match DlmmInstruction::try_from(ix).ok()? {
+            DlmmInstruction::Swap(_)
+            | DlmmInstruction::Swap2(_)
+            | DlmmInstruction::SwapExactOut(_) => {
+                ctx.swap_from_vaults(self.venue(), path, ix, 0, 10, [2, 3])
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Emits a `SwapEvent` for every dynamic AMM `Swap`.
+pub struct DammDecoder;
+
+impl DexDecoder for DammDecoder {
+    fn program_id(&self) -> Pubkey {
+        constants::METEORA_DAMM_PROGRAM
+    }
+
+    fn venue(&self) -> Venue {
+        Venue::MeteoraDamm
+    }
+
+    fn decode_instruction(
+        &self,
+        ctx: &TransactionContext,
+        path: InstructionPath,
+        ix: &UiCompiledInstruction,
+    ) -> Option<SwapEvent> {
+        match DammInstruction::try_from(ix).ok()? {
+            // 代币实际存放在 vault 程序的 token vault 中
+            DammInstruction::Swap(_) => ctx.swap_from_vaults(self.venue(), path, ix, 0, 12, [5, 6]),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::{anchor_instruction, compiled_instruction};
+    use crate::utils::calculate_discriminator;
+
+    #[test]
+    fn test_discriminators() {
+        assert_eq!(DLMM_SWAP2_IX, calculate_discriminator("swap2"));
+        assert_eq!(
+            DLMM_SWAP_EXACT_OUT_IX,
+            calculate_discriminator("swap_exact_out")
+        );
+        assert_eq!(
+            DLMM_ADD_LIQUIDITY_BY_STRATEGY_IX,
+            calculate_discriminator("add_liquidity_by_strategy")
+        );
+        assert_eq!(
+            DLMM_REMOVE_LIQUIDITY_BY_RANGE_IX,
+            calculate_discriminator("remove_liquidity_by_range")
+        );
+        assert_eq!(
+            DAMM_REMOVE_LIQUIDITY_SINGLE_SIDE_IX,
+            calculate_discriminator("remove_liquidity_single_side")
+        );
+    }
+
+    #[test]
+    fn test_swap_normalized() {
+        let args = SwapInstructionBaseIn {
+            amount_in: 5_000_000,
+            minimum_amount_out: 123,
+        };
+
+        match DlmmInstruction::try_from(&anchor_instruction(SWAP_IX, args.clone())).unwrap() {
+            DlmmInstruction::Swap(swap) => {
+                assert_eq!(swap.amount_in, 5_000_000);
+                assert_eq!(swap.minimum_amount_out, 123);
+            }
+            x => panic!("unexpected instruction {:?}", x),
+        }
+        match DammInstruction::try_from(&anchor_instruction(SWAP_IX, args)).unwrap() {
+            DammInstruction::Swap(swap) => assert_eq!(swap.amount_in, 5_000_000),
+            x => panic!("unexpected instruction {:?}", x),
+        }
+
+        // swap2: amount_in, min_amount_out, remaining_accounts_info { slices: [] }
+        let mut data = DLMM_SWAP2_IX.to_vec();
+        data.extend(7u64.to_le_bytes());
+        data.extend(6u64.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        let ui_ix = compiled_instruction(0, vec![], data);
+        match DlmmInstruction::try_from(UiInstruction::Compiled(ui_ix)).unwrap() {
+            DlmmInstruction::Swap2(swap) => {
+                assert_eq!(swap.amount_in, 7);
+                assert_eq!(swap.minimum_amount_out, 6);
+            }
+            x => panic!("unexpected instruction {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_remove_liquidity_by_range() {
+        let ui_ix = anchor_instruction(
+            DLMM_REMOVE_LIQUIDITY_BY_RANGE_IX,
+            RemoveLiquidityByRangeInstruction {
+                from_bin_id: -10,
+                to_bin_id: 10,
+                bps_to_remove: 10_000,
+            },
+        );
+        match DlmmInstruction::try_from(&ui_ix).unwrap() {
+            DlmmInstruction::RemoveLiquidityByRange(remove) => {
+                assert_eq!(remove.from_bin_id, -10);
+                assert_eq!(remove.bps_to_remove, 10_000);
+            }
+            x => panic!("unexpected instruction {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_lb_pair_price() {
+        let mut data = LB_PAIR_ACCOUNT.to_vec();
+        data.extend([0u8; 64]); // parameters + v_parameters
+        data.extend([0u8; 4]); // bump_seed, bin_step_seed, pair_type
+        data.extend(100i32.to_le_bytes()); // active_id
+        data.extend(25u16.to_le_bytes()); // bin_step
+        data.extend([0u8; 6]);
+        data.extend([0u8; 32 * 4 + 16 + 32 + 144 * 2 + 32 + 128 + 8]);
+        // zero-copy 账户尾部还有更多字段
+        data.extend([0u8; 256]);
+
+        let pair = LbPair::try_from_account_data(&data).unwrap();
+        assert_eq!(pair.active_id, 100);
+        assert_eq!(pair.bin_step, 25);
+        let expected = 1.0025f64.powi(100) * 1000.0;
+        assert!((pair.active_price(9, 6) - expected).abs() < 1e-9);
+    }
+}
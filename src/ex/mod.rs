@@ -1,2 +1,12 @@
+pub mod compute_budget;
+pub mod decoder;
+pub mod jupiter;
+pub mod launchlab;
+pub mod meteora;
+pub mod openbook;
+pub mod phoenix;
 pub mod pumpfun;
 pub mod raydium;
+pub mod spl_token;
+pub mod system;
+pub mod whirlpool;
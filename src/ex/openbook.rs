@@ -0,0 +1,202 @@
+use anyhow::{Result, anyhow};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::raydium::AmmInfo;
+
+// serum/openbook 账户数据前后各有一段固定的 padding
+const ACCOUNT_HEAD_PADDING: &[u8; 5] = b"serum";
+const ACCOUNT_TAIL_PADDING: &[u8; 7] = b"padding";
+
+/// Size of a `MarketState` account, paddings included.
+pub const MARKET_STATE_LEN: usize = 388;
+
+// https://github.com/openbook-dex/program/blob/master/dex/src/state.rs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u64)]
+pub enum AccountFlag {
+    Initialized = 1 << 0,
+    Market = 1 << 1,
+    OpenOrders = 1 << 2,
+    RequestQueue = 1 << 3,
+    EventQueue = 1 << 4,
+    Bids = 1 << 5,
+    Asks = 1 << 6,
+    Disabled = 1 << 7,
+    Closed = 1 << 8,
+    Permissioned = 1 << 9,
+    CrankAuthorityRequired = 1 << 10,
+}
+
+// https://github.com/openbook-dex/program/blob/master/dex/src/state.rs
+// 原始定义中 Pubkey 以 [u64; 4] 保存，字节布局与 Pubkey 相同
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct MarketState {
+    pub account_flags: u64,
+    pub own_address: Pubkey,
+    /// Nonce used to derive the vault signer from the market address
+    pub vault_signer_nonce: u64,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub coin_vault: Pubkey,
+    pub coin_deposits_total: u64,
+    pub coin_fees_accrued: u64,
+    pub pc_vault: Pubkey,
+    pub pc_deposits_total: u64,
+    pub pc_fees_accrued: u64,
+    pub pc_dust_threshold: u64,
+    pub req_q: Pubkey,
+    pub event_q: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub fee_rate_bps: u64,
+    pub referrer_rebates_accrued: u64,
+}
+
+impl MarketState {
+    /// Decodes the raw market account data, including the "serum" / "padding" markers.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() != MARKET_STATE_LEN
+            || &data[..5] != ACCOUNT_HEAD_PADDING
+            || &data[MARKET_STATE_LEN - 7..] != ACCOUNT_TAIL_PADDING
+        {
+            return Err(anyhow!("failed to convert to target OpenBook MarketState"));
+        }
+        let state = MarketState::try_from_slice(&data[5..MARKET_STATE_LEN - 7])?;
+        if !state.has_flag(AccountFlag::Initialized) || !state.has_flag(AccountFlag::Market) {
+            return Err(anyhow!("account is not an initialized OpenBook market"));
+        }
+        Ok(state)
+    }
+
+    pub fn has_flag(&self, flag: AccountFlag) -> bool {
+        self.account_flags & flag as u64 != 0
+    }
+}
+
+/// Derives the market vault signer, the authority over the market coin/pc vaults.
+pub fn vault_signer(market: &Pubkey, nonce: u64, market_program: &Pubkey) -> Result<Pubkey> {
+    Pubkey::create_program_address(&[market.as_ref(), &nonce.to_le_bytes()], market_program)
+        .map_err(|e| anyhow!("failed to derive market vault signer: {}", e))
+}
+
+/// Every market account a Raydium v4 swap needs, in the order of
+/// `AmmInstruction::SwapBaseIn` accounts 7..=14.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarketAccounts {
+    pub market_program: Pubkey,
+    pub market: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub event_queue: Pubkey,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub vault_signer: Pubkey,
+}
+
+impl MarketAccounts {
+    pub fn from_market_state(
+        market_program: &Pubkey,
+        market: &Pubkey,
+        state: &MarketState,
+    ) -> Result<Self> {
+        if state.own_address != *market {
+            return Err(anyhow!(
+                "market state belongs to {}, not {}",
+                state.own_address,
+                market
+            ));
+        }
+        Ok(MarketAccounts {
+            market_program: *market_program,
+            market: *market,
+            bids: state.bids,
+            asks: state.asks,
+            event_queue: state.event_q,
+            coin_vault: state.coin_vault,
+            pc_vault: state.pc_vault,
+            vault_signer: vault_signer(market, state.vault_signer_nonce, market_program)?,
+        })
+    }
+}
+
+/// Fetches the market referenced by `amm.market` and resolves its full account set.
+pub async fn fetch_market_accounts(rpc: &RpcClient, amm: &AmmInfo) -> Result<MarketAccounts> {
+    let account = rpc.get_account(&amm.market).await?;
+    if account.owner != amm.market_program {
+        return Err(anyhow!(
+            "market {} is owned by {}, expected {}",
+            amm.market,
+            account.owner,
+            amm.market_program
+        ));
+    }
+    let state = MarketState::try_from_account_data(&account.data)?;
+    MarketAccounts::from_market_state(&amm.market_program, &amm.market, &state)
+}
+
+/// Initialized market with random accounts, for tests.
+#[cfg(test)]
+pub(crate) fn market_state(market: Pubkey, nonce: u64) -> MarketState {
+    MarketState {
+        account_flags: AccountFlag::Initialized as u64 | AccountFlag::Market as u64,
+        own_address: market,
+        vault_signer_nonce: nonce,
+        coin_mint: Pubkey::new_unique(),
+        pc_mint: Pubkey::new_unique(),
+        coin_vault: Pubkey::new_unique(),
+        coin_deposits_total: 0,
+        coin_fees_accrued: 0,
+        pc_vault: Pubkey::new_unique(),
+        pc_deposits_total: 0,
+        pc_fees_accrued: 0,
+        pc_dust_threshold: 0,
+        req_q: Pubkey::new_unique(),
+        event_q: Pubkey::new_unique(),
+        bids: Pubkey::new_unique(),
+        asks: Pubkey::new_unique(),
+        coin_lot_size: 1_000_000,
+        pc_lot_size: 1,
+        fee_rate_bps: 0,
+        referrer_rebates_accrued: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::constants;
+
+    #[test]
+    fn test_market_accounts() {
+        let program = constants::OPENBOOK_PROGRAM;
+        let market = Pubkey::new_unique();
+        // 找到一个能派生出合法地址的 nonce
+        let nonce = (0u64..)
+            .find(|n| vault_signer(&market, *n, &program).is_ok())
+            .unwrap();
+
+        let state = market_state(market, nonce);
+        let mut data = ACCOUNT_HEAD_PADDING.to_vec();
+        data.extend(borsh::to_vec(&state).unwrap());
+        data.extend(ACCOUNT_TAIL_PADDING);
+        assert_eq!(data.len(), MARKET_STATE_LEN);
+
+        let decoded = MarketState::try_from_account_data(&data).unwrap();
+        let accounts = MarketAccounts::from_market_state(&program, &market, &decoded).unwrap();
+        assert_eq!(accounts.bids, state.bids);
+        assert_eq!(accounts.event_queue, state.event_q);
+        assert_eq!(
+            accounts.vault_signer,
+            vault_signer(&market, nonce, &program).unwrap()
+        );
+
+        assert!(
+            MarketAccounts::from_market_state(&program, &Pubkey::new_unique(), &decoded).is_err()
+        );
+    }
+}
@@ -0,0 +1,511 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{UiCompiledInstruction, UiInstruction};
+
+use crate::constants;
+use crate::decoder::{DexDecoder, InstructionPath, SwapEvent, TransactionContext, Venue};
+
+// Phoenix 不是 Anchor 程序，指令第一个字节为指令下标
+const SWAP_IX: u8 = 0;
+const SWAP_WITH_FREE_FUNDS_IX: u8 = 1;
+const PLACE_LIMIT_ORDER_IX: u8 = 2;
+const PLACE_LIMIT_ORDER_WITH_FREE_FUNDS_IX: u8 = 3;
+
+// keccak(program id, "phoenix::program::accounts::MarketHeader") 的前 8 字节
+const MARKET_DISCRIMINANT: u64 = 8167313896524341111;
+
+/// Size of the `MarketHeader` at the start of every market account.
+pub const MARKET_HEADER_LEN: usize = 576;
+
+// FIFOMarket 在订单簿之前的字段: _padding [u64; 32] + 6 个 u64
+const FIFO_MARKET_PREFIX_LEN: usize = 256 + 6 * 8;
+// RedBlackTree: root u32 + _padding [u32; 3] + NodeAllocator { size u64, bump_index u32, free_list_head u32 }
+const TREE_HEADER_LEN: usize = 16 + 16;
+// Node: registers [u32; 4] + FIFOOrderId (16) + FIFORestingOrder (32)
+const ORDER_NODE_LEN: usize = 16 + 16 + 32;
+// 红黑树节点 registers 中左右子节点的位置，0 为哨兵节点
+const LEFT: usize = 0;
+const RIGHT: usize = 1;
+const SENTINEL: u32 = 0;
+
+// https://github.com/Ellipsis-Labs/phoenix-v1/blob/master/src/program/instruction.rs
+// 这里监听的是指令
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+pub enum PhoenixInstruction {
+    ///   Send a take-only order, tokens move from and to the trader's token accounts.
+    ///
+    ///   0. `[]` Phoenix program id
+    ///   1. `[]` Log authority
+    ///   2. `[writable]` Market Account
+    ///   3. `[signer]` Trader wallet Account
+    ///   4. `[writable]` Trader base token Account
+    ///   5. `[writable]` Trader quote token Account
+    ///   6. `[writable]` Base vault
+    ///   7. `[writable]` Quote vault
+    ///   8. `[]` Token program id
+    Swap(OrderPacket),
+
+    ///   Send a take-only order using funds already deposited on the market.
+    ///
+    ///   0. `[]` Phoenix program id
+    ///   1. `[]` Log authority
+    ///   2. `[writable]` Market Account
+    ///   3. `[signer]` Trader wallet Account
+    ///   4. `[]` Seat Account
+    SwapWithFreeFunds(OrderPacket),
+
+    ///   Place a limit order, tokens move from and to the trader's token accounts.
+    ///
+    ///   0. `[]` Phoenix program id
+    ///   1. `[]` Log authority
+    ///   2. `[writable]` Market Account
+    ///   3. `[signer]` Trader wallet Account
+    ///   4. `[]` Seat Account
+    ///   5. `[writable]` Trader base token Account
+    ///   6. `[writable]` Trader quote token Account
+    ///   7. `[writable]` Base vault
+    ///   8. `[writable]` Quote vault
+    ///   9. `[]` Token program id
+    PlaceLimitOrder(OrderPacket),
+
+    ///   Place a limit order using funds already deposited on the market.
+    ///   Accounts as in `SwapWithFreeFunds`.
+    PlaceLimitOrderWithFreeFunds(OrderPacket),
+}
+
+impl TryFrom<UiInstruction> for PhoenixInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ix: UiInstruction) -> Result<PhoenixInstruction> {
+        match ix {
+            UiInstruction::Compiled(ui_ix) => PhoenixInstruction::try_from(&ui_ix),
+            _ => Err(anyhow!("failed to convert to target PhoenixInstruction")),
+        }
+    }
+}
+
+impl TryFrom<&UiCompiledInstruction> for PhoenixInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ui_ix: &UiCompiledInstruction) -> Result<PhoenixInstruction> {
+        let data = bs58::decode(&ui_ix.data).into_vec()?;
+        let Some((index, args)) = data.split_first() else {
+            return Err(anyhow!("failed to convert to target PhoenixInstruction"));
+        };
+
+        let ix = match *index {
+            SWAP_IX => Self::Swap(OrderPacket::try_from_slice(args)?),
+            SWAP_WITH_FREE_FUNDS_IX => Self::SwapWithFreeFunds(OrderPacket::try_from_slice(args)?),
+            PLACE_LIMIT_ORDER_IX => Self::PlaceLimitOrder(OrderPacket::try_from_slice(args)?),
+            PLACE_LIMIT_ORDER_WITH_FREE_FUNDS_IX => {
+                Self::PlaceLimitOrderWithFreeFunds(OrderPacket::try_from_slice(args)?)
+            }
+            _ => return Err(anyhow!("failed to convert to target PhoenixInstruction")),
+        };
+        Ok(ix)
+    }
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTradeBehavior {
+    Abort,
+    CancelProvide,
+    DecrementTake,
+}
+
+// https://github.com/Ellipsis-Labs/phoenix-v1/blob/master/src/state/order_schema/order_packet.rs
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub enum OrderPacket {
+    PostOnly {
+        side: Side,
+        price_in_ticks: u64,
+        num_base_lots: u64,
+        client_order_id: u128,
+        reject_post_only: bool,
+        use_only_deposited_funds: bool,
+        last_valid_slot: Option<u64>,
+        last_valid_unix_timestamp_in_seconds: Option<u64>,
+        fail_silently_on_insufficient_funds: bool,
+    },
+    Limit {
+        side: Side,
+        price_in_ticks: u64,
+        num_base_lots: u64,
+        self_trade_behavior: SelfTradeBehavior,
+        match_limit: Option<u64>,
+        client_order_id: u128,
+        use_only_deposited_funds: bool,
+        last_valid_slot: Option<u64>,
+        last_valid_unix_timestamp_in_seconds: Option<u64>,
+        fail_silently_on_insufficient_funds: bool,
+    },
+    ImmediateOrCancel {
+        side: Side,
+        /// Worst price the order may fill at, `None` for a market order
+        price_in_ticks: Option<u64>,
+        num_base_lots: u64,
+        num_quote_lots: u64,
+        min_base_lots_to_fill: u64,
+        min_quote_lots_to_fill: u64,
+        self_trade_behavior: SelfTradeBehavior,
+        match_limit: Option<u64>,
+        client_order_id: u128,
+        use_only_deposited_funds: bool,
+        last_valid_slot: Option<u64>,
+        last_valid_unix_timestamp_in_seconds: Option<u64>,
+    },
+}
+
+impl OrderPacket {
+    pub fn side(&self) -> Side {
+        match self {
+            Self::PostOnly { side, .. }
+            | Self::Limit { side, .. }
+            | Self::ImmediateOrCancel { side, .. } => *side,
+        }
+    }
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct MarketSizeParams {
+    pub bids_size: u64,
+    pub asks_size: u64,
+    pub num_seats: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct TokenParams {
+    pub decimals: u32,
+    pub vault_bump: u32,
+    pub mint_key: Pubkey,
+    pub vault_key: Pubkey,
+}
+
+// https://github.com/Ellipsis-Labs/phoenix-v1/blob/master/src/program/accounts.rs
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct MarketHeader {
+    pub discriminant: u64,
+    pub status: u64,
+    pub market_size_params: MarketSizeParams,
+    pub base_params: TokenParams,
+    pub base_lot_size: u64,
+    pub quote_params: TokenParams,
+    pub quote_lot_size: u64,
+    pub tick_size_in_quote_atoms_per_base_unit: u64,
+    pub authority: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub market_sequence_number: u64,
+    pub successor: Pubkey,
+    pub raw_base_units_per_base_unit: u32,
+    pub padding1: u32,
+    pub padding2: [u64; 32],
+}
+
+impl MarketHeader {
+    /// Converts a price in ticks to quote units per raw base unit.
+    pub fn ticks_to_price(&self, price_in_ticks: u64) -> f64 {
+        let quote_atoms =
+            price_in_ticks as f64 * self.tick_size_in_quote_atoms_per_base_unit as f64;
+        quote_atoms
+            / 10f64.powi(self.quote_params.decimals as i32)
+            / self.raw_base_units_per_base_unit.max(1) as f64
+    }
+
+    /// Converts base lots to raw base units.
+    pub fn base_lots_to_units(&self, base_lots: u64) -> f64 {
+        base_lots as f64 * self.base_lot_size as f64 / 10f64.powi(self.base_params.decimals as i32)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LadderLevel {
+    pub price_in_ticks: u64,
+    pub size_in_base_lots: u64,
+}
+
+/// Aggregated order book, bids best (highest) first and asks best (lowest) first.
+#[derive(Debug, Clone, Default)]
+pub struct Ladder {
+    pub bids: Vec<LadderLevel>,
+    pub asks: Vec<LadderLevel>,
+}
+
+impl Ladder {
+    pub fn best_bid(&self) -> Option<LadderLevel> {
+        self.bids.first().copied()
+    }
+
+    pub fn best_ask(&self) -> Option<LadderLevel> {
+        self.asks.first().copied()
+    }
+}
+
+/// A decoded Phoenix market account: the header plus the bid/ask ladder.
+#[derive(Debug, Clone)]
+pub struct Market {
+    pub header: MarketHeader,
+    pub taker_fee_bps: u64,
+    pub ladder: Ladder,
+}
+
+impl Market {
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < MARKET_HEADER_LEN + FIFO_MARKET_PREFIX_LEN {
+            return Err(anyhow!(
+                "failed to convert to target Phoenix market account"
+            ));
+        }
+        let header = MarketHeader::try_from_slice(&data[..MARKET_HEADER_LEN])?;
+        if header.discriminant != MARKET_DISCRIMINANT {
+            return Err(anyhow!("account is not a Phoenix market"));
+        }
+        let market = &data[MARKET_HEADER_LEN..];
+        let taker_fee_bps = read_u64(market, 256 + 3 * 8)?;
+
+        let bids_size = header.market_size_params.bids_size as usize;
+        let asks_size = header.market_size_params.asks_size as usize;
+        let bids_offset = FIFO_MARKET_PREFIX_LEN;
+        let asks_offset = bids_offset + TREE_HEADER_LEN + bids_size * ORDER_NODE_LEN;
+
+        let mut bids = read_levels(market, bids_offset, bids_size)?;
+        let asks = read_levels(market, asks_offset, asks_size)?;
+        bids.reverse();
+
+        Ok(Market {
+            header,
+            taker_fee_bps,
+            ladder: Ladder { bids, asks },
+        })
+    }
+
+    /// Best bid and ask in quote units per raw base unit.
+    pub fn top_of_book(&self) -> (Option<f64>, Option<f64>) {
+        let price = |level: LadderLevel| self.header.ticks_to_price(level.price_in_ticks);
+        (
+            self.ladder.best_bid().map(price),
+            self.ladder.best_ask().map(price),
+        )
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| anyhow!("Phoenix market account too short"))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    data.get(offset..offset + 8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| anyhow!("Phoenix market account too short"))
+}
+
+// 中序遍历红黑树，按价格从低到高聚合每一档的挂单数量
+// https://github.com/Ellipsis-Labs/sokoban/blob/main/src/red_black_tree.rs
+fn read_levels(market: &[u8], tree_offset: usize, max_size: usize) -> Result<Vec<LadderLevel>> {
+    let root = read_u32(market, tree_offset)?;
+    let nodes_offset = tree_offset + TREE_HEADER_LEN;
+    let node_offset = |index: u32| nodes_offset + (index as usize - 1) * ORDER_NODE_LEN;
+    let register =
+        |index: u32, register: usize| read_u32(market, node_offset(index) + register * 4);
+
+    let mut levels: BTreeMap<u64, u64> = BTreeMap::new();
+    let mut stack = Vec::new();
+    let mut current = root;
+    let mut visited = 0;
+    while current != SENTINEL || !stack.is_empty() {
+        while current != SENTINEL {
+            if current as usize > max_size {
+                return Err(anyhow!("Phoenix order tree node {} out of range", current));
+            }
+            stack.push(current);
+            current = register(current, LEFT)?;
+        }
+        let Some(index) = stack.pop() else { break };
+
+        visited += 1;
+        if visited > max_size {
+            return Err(anyhow!("Phoenix order tree is corrupted"));
+        }
+        // FIFOOrderId { price_in_ticks, order_sequence_number }
+        // FIFORestingOrder { trader_index, num_base_lots, .. }
+        let price_in_ticks = read_u64(market, node_offset(index) + 16)?;
+        let num_base_lots = read_u64(market, node_offset(index) + 32 + 8)?;
+        *levels.entry(price_in_ticks).or_default() += num_base_lots;
+
+        current = register(index, RIGHT)?;
+    }
+
+    Ok(levels
+        .into_iter()
+        .map(|(price_in_ticks, size_in_base_lots)| LadderLevel {
+            price_in_ticks,
+            size_in_base_lots,
+        })
+        .collect())
+}
+
+/// Emits a `SwapEvent` for every `Swap` settled through the market vaults.
+pub struct PhoenixDecoder;
+
+impl DexDecoder for PhoenixDecoder {
+    fn program_id(&self) -> Pubkey {
+        constants::PHOENIX_PROGRAM
+    }
+
+    fn venue(&self) -> Venue {
+        Venue::Phoenix
+    }
+
+    fn decode_instruction(
+        &self,
+        ctx: &TransactionContext,
+        path: InstructionPath,
+        ix: &UiCompiledInstruction,
+    ) -> Option<SwapEvent> {
+        match PhoenixInstruction::try_from(ix).ok()? {
+            PhoenixInstruction::Swap(_) => {
+                ctx.swap_from_vaults(self.venue(), path, ix, 2, 3, [6, 7])
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::compiled_instruction;
+
+    fn header(bids_size: u64, asks_size: u64) -> MarketHeader {
+        let token = |decimals| TokenParams {
+            decimals,
+            vault_bump: 255,
+            mint_key: Pubkey::new_unique(),
+            vault_key: Pubkey::new_unique(),
+        };
+        MarketHeader {
+            discriminant: MARKET_DISCRIMINANT,
+            status: 1,
+            market_size_params: MarketSizeParams {
+                bids_size,
+                asks_size,
+                num_seats: 1,
+            },
+            base_params: token(9),
+            base_lot_size: 1_000_000,
+            quote_params: token(6),
+            quote_lot_size: 1,
+            tick_size_in_quote_atoms_per_base_unit: 1_000,
+            authority: Pubkey::new_unique(),
+            fee_recipient: Pubkey::new_unique(),
+            market_sequence_number: 0,
+            successor: Pubkey::new_unique(),
+            raw_base_units_per_base_unit: 1,
+            padding1: 0,
+            padding2: [0; 32],
+        }
+    }
+
+    // node: (index, left, right, price, base_lots)
+    fn write_tree(data: &mut [u8], offset: usize, root: u32, nodes: &[(u32, u32, u32, u64, u64)]) {
+        data[offset..offset + 4].copy_from_slice(&root.to_le_bytes());
+        for (index, left, right, price, lots) in nodes {
+            let node = offset + TREE_HEADER_LEN + (*index as usize - 1) * ORDER_NODE_LEN;
+            data[node..node + 4].copy_from_slice(&left.to_le_bytes());
+            data[node + 4..node + 8].copy_from_slice(&right.to_le_bytes());
+            data[node + 16..node + 24].copy_from_slice(&price.to_le_bytes());
+            data[node + 40..node + 48].copy_from_slice(&lots.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn test_place_limit_order() {
+        let packet = OrderPacket::Limit {
+            side: Side::Ask,
+            price_in_ticks: 150,
+            num_base_lots: 10,
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            match_limit: None,
+            client_order_id: 7,
+            use_only_deposited_funds: false,
+            last_valid_slot: None,
+            last_valid_unix_timestamp_in_seconds: None,
+            fail_silently_on_insufficient_funds: false,
+        };
+        let mut data = vec![PLACE_LIMIT_ORDER_IX];
+        data.extend(borsh::to_vec(&packet).unwrap());
+        let ui_ix = compiled_instruction(0, vec![], data);
+
+        match PhoenixInstruction::try_from(UiInstruction::Compiled(ui_ix)).unwrap() {
+            PhoenixInstruction::PlaceLimitOrder(packet) => assert_eq!(packet.side(), Side::Ask),
+            x => panic!("unexpected instruction {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_market_ladder() {
+        let (bids_size, asks_size) = (4u64, 4u64);
+        let header = header(bids_size, asks_size);
+        let mut data = borsh::to_vec(&header).unwrap();
+        assert_eq!(data.len(), MARKET_HEADER_LEN);
+
+        let bids_offset = FIFO_MARKET_PREFIX_LEN;
+        let asks_offset = bids_offset + TREE_HEADER_LEN + bids_size as usize * ORDER_NODE_LEN;
+        let mut market =
+            vec![0u8; asks_offset + TREE_HEADER_LEN + asks_size as usize * ORDER_NODE_LEN];
+        market[256 + 24..256 + 32].copy_from_slice(&5u64.to_le_bytes());
+
+        //        2 (101)
+        //       /       \
+        //   1 (100)    3 (101)
+        write_tree(
+            &mut market,
+            bids_offset,
+            2,
+            &[(1, 0, 0, 100, 5), (2, 1, 3, 101, 2), (3, 0, 0, 101, 3)],
+        );
+        write_tree(
+            &mut market,
+            asks_offset,
+            1,
+            &[(1, 0, 2, 105, 1), (2, 0, 0, 110, 4)],
+        );
+        data.extend(market);
+
+        let market = Market::try_from_account_data(&data).unwrap();
+        assert_eq!(market.taker_fee_bps, 5);
+        assert_eq!(
+            market.ladder.bids,
+            vec![
+                LadderLevel {
+                    price_in_ticks: 101,
+                    size_in_base_lots: 5
+                },
+                LadderLevel {
+                    price_in_ticks: 100,
+                    size_in_base_lots: 5
+                },
+            ]
+        );
+        assert_eq!(market.ladder.best_ask().unwrap().price_in_ticks, 105);
+
+        let (bid, ask) = market.top_of_book();
+        assert!((bid.unwrap() - 0.101).abs() < 1e-12);
+        assert!((ask.unwrap() - 0.105).abs() < 1e-12);
+        assert!((market.header.base_lots_to_units(5) - 0.005).abs() < 1e-12);
+
+        // 其他账户（例如 seat）的 discriminant 不同
+        data[..8].copy_from_slice(&0u64.to_le_bytes());
+        assert!(Market::try_from_account_data(&data).is_err());
+    }
+}
@@ -1,11 +1,20 @@
 use anyhow::anyhow;
+use base64::Engine as _;
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_sdk::{bs58, pubkey::Pubkey};
 use solana_transaction_status::{UiCompiledInstruction, UiInstruction};
 
+use crate::constants;
+use crate::decoder::{
+    DexDecoder, InstructionPath, PoolCreated, SwapEvent, TransactionContext, Venue,
+};
+
 const PUMPFUN_CREATE_EVENT: [u8; 8] = [27, 114, 169, 77, 222, 235, 99, 118];
 const PUMPFUN_COMPLETE_EVENT: [u8; 8] = [95, 114, 97, 156, 212, 46, 152, 8];
 const PUMPFUN_TRADE_EVENT: [u8; 8] = [189, 219, 127, 211, 78, 230, 97, 238];
+const PUMPFUN_BONDING_CURVE_ACCOUNT: [u8; 8] = [23, 183, 248, 55, 96, 216, 172, 96];
+const PUMPFUN_BUY_IX: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+const PUMPFUN_SELL_IX: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
 
 // IDL: https://github.com/cfanbo/pumpdotfun-sdk/blob/main/src/IDL/pump-fun.json
 // 这里监听的是事件
@@ -110,6 +119,12 @@ pub struct BuyArgs {
     pub max_sol_cost: u64,
 }
 
+#[derive(Debug, BorshSerialize, Clone, BorshDeserialize)]
+pub struct SellArgs {
+    pub amount: u64,
+    pub min_sol_output: u64,
+}
+
 #[derive(Debug, BorshSerialize, Clone, BorshDeserialize, Copy)]
 pub struct TradeEvent {
     pub mint: Pubkey,
@@ -142,6 +157,205 @@ impl TradeEvent {
     }
 }
 
+// https://github.com/cfanbo/pumpdotfun-sdk/blob/main/src/IDL/pump-fun.json
+// 新版本的账户在末尾追加了 creator 等字段，只解析前面的部分
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct BondingCurve {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+}
+
+impl BondingCurve {
+    /// Decodes the bonding curve account data, discriminator included.
+    pub fn try_from_account_data(data: &[u8]) -> anyhow::Result<Self> {
+        if data.len() <= 8 || !data[..8].eq(&PUMPFUN_BONDING_CURVE_ACCOUNT) {
+            return Err(anyhow!(
+                "failed to convert to target pump.fun BondingCurve account"
+            ));
+        }
+        Ok(BondingCurve::deserialize(&mut &data[8..])?)
+    }
+
+    /// Bonding curve account of `mint`.
+    pub fn address(mint: &Pubkey) -> Pubkey {
+        let program_id = constants::PUMP_FUN_PROGRAM;
+        Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], &program_id).0
+    }
+}
+
+impl TradeEvent {
+    /// Decodes an `emit!` log line: "Program data: <base64(discriminator + event)>".
+    pub fn try_from_log(log: &str) -> Option<TradeEvent> {
+        let encoded = log.strip_prefix("Program data: ")?;
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .ok()?;
+        if data.len() > 8 && data[..8].eq(&PUMPFUN_TRADE_EVENT) {
+            TradeEvent::try_from_slice(&data[8..]).ok()
+        } else {
+            None
+        }
+    }
+
+    fn to_swap_event(self, ctx: &TransactionContext, path: Option<InstructionPath>) -> SwapEvent {
+        let bonding_curve = BondingCurve::address(&self.mint);
+        let wsol = constants::WSOL_MINT_PUBKEY;
+        let sol = (wsol, self.sol_amount, self.virtual_sol_reserves);
+        let token = (self.mint, self.token_amount, self.virtual_token_reserves);
+        let (token_in, token_out) = if self.is_buy {
+            (sol, token)
+        } else {
+            (token, sol)
+        };
+
+        SwapEvent {
+            venue: Venue::Pumpfun,
+            pool: bonding_curve,
+            trader: self.user,
+            mint_in: token_in.0,
+            mint_out: token_out.0,
+            amount_in: token_in.1,
+            amount_out: token_out.1,
+            // 使用虚拟储备，价格由虚拟储备决定
+            reserves_after: Some((token_in.2, token_out.2)),
+            slot: ctx.slot,
+            signature: ctx.signature,
+            instruction_path: path,
+            error: None,
+        }
+    }
+}
+
+/// Emits a `SwapEvent` for every pump.fun `TradeEvent`, from the event CPI or the logs,
+/// or for every `buy` / `sell` instruction when there are neither.
+pub struct PumpfunDecoder;
+
+impl DexDecoder for PumpfunDecoder {
+    fn program_id(&self) -> Pubkey {
+        constants::PUMP_FUN_PROGRAM
+    }
+
+    fn venue(&self) -> Venue {
+        Venue::Pumpfun
+    }
+
+    fn decode_instruction(
+        &self,
+        ctx: &TransactionContext,
+        path: InstructionPath,
+        ix: &UiCompiledInstruction,
+    ) -> Option<SwapEvent> {
+        TradeEvent::try_from_compiled_instruction(ix)
+            .map(|trade| trade.to_swap_event(ctx, Some(path)))
+    }
+
+    fn decode_logs(&self, ctx: &TransactionContext) -> Vec<SwapEvent> {
+        ctx.log_messages
+            .iter()
+            .filter_map(|log| TradeEvent::try_from_log(log))
+            .map(|trade| trade.to_swap_event(ctx, None))
+            .collect()
+    }
+
+    fn decode_instruction_args(
+        &self,
+        ctx: &TransactionContext,
+        path: InstructionPath,
+        ix: &UiCompiledInstruction,
+    ) -> Option<SwapEvent> {
+        let data = bs58::decode(&ix.data).into_vec().ok()?;
+        if data.len() < 8 {
+            return None;
+        }
+        let sol = constants::WSOL_MINT_PUBKEY;
+        // 账户顺序: 2 mint, 3 bonding curve, 6 user
+        let mint = ctx.account(ix, 2)?;
+        let (mint_in, amount_in, mint_out, amount_out) = if data[..8].eq(&PUMPFUN_BUY_IX) {
+            let args = BuyArgs::try_from_slice(&data[8..]).ok()?;
+            (sol, args.max_sol_cost, mint, args.amount)
+        } else if data[..8].eq(&PUMPFUN_SELL_IX) {
+            let args = SellArgs::try_from_slice(&data[8..]).ok()?;
+            (mint, args.amount, sol, args.min_sol_output)
+        } else {
+            return None;
+        };
+        Some(SwapEvent {
+            venue: self.venue(),
+            pool: ctx.account(ix, 3)?,
+            trader: ctx.account(ix, 6)?,
+            mint_in,
+            mint_out,
+            amount_in,
+            amount_out,
+            reserves_after: None,
+            slot: ctx.slot,
+            signature: ctx.signature,
+            instruction_path: Some(path),
+            error: None,
+        })
+    }
+
+    fn decode_pool_created(
+        &self,
+        ctx: &TransactionContext,
+        ix: &UiCompiledInstruction,
+    ) -> Option<PoolCreated> {
+        let create = CreateEvent::try_from_compiled_instruction(ix)?;
+        Some(PoolCreated {
+            venue: self.venue(),
+            pool: create.bonding_curve,
+            base_mint: create.mint,
+            quote_mint: constants::WSOL_MINT_PUBKEY,
+            creator: create.user,
+            slot: ctx.slot,
+            signature: ctx.signature,
+        })
+    }
+}
+
+// IDL 中的 errors，Anchor 自定义错误码从 6000 开始
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PumpfunError {
+    /// The given account is not authorized to execute this instruction
+    NotAuthorized,
+    /// The program is already initialized
+    AlreadyInitialized,
+    /// slippage: Too much SOL required to buy the given amount of tokens
+    TooMuchSolRequired,
+    /// slippage: Too little SOL received to sell the given amount of tokens
+    TooLittleSolReceived,
+    /// The mint does not match the bonding curve
+    MintDoesNotMatchBondingCurve,
+    /// The bonding curve has completed and liquidity migrated to raydium
+    BondingCurveComplete,
+    /// The bonding curve has not completed
+    BondingCurveNotComplete,
+    /// The program is not initialized
+    NotInitialized,
+}
+
+impl PumpfunError {
+    pub fn from_code(code: u32) -> Option<Self> {
+        use PumpfunError::*;
+        let error = match code {
+            6000 => NotAuthorized,
+            6001 => AlreadyInitialized,
+            6002 => TooMuchSolRequired,
+            6003 => TooLittleSolReceived,
+            6004 => MintDoesNotMatchBondingCurve,
+            6005 => BondingCurveComplete,
+            6006 => BondingCurveNotComplete,
+            6007 => NotInitialized,
+            _ => return None,
+        };
+        Some(error)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Reason {
     USUAL,
@@ -163,3 +377,180 @@ async fn test() {
     let result = TradeEvent::try_from_slice(&data[16..]).unwrap();
     println!("result {:?}", result);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use solana_sdk::signature::Signature;
+    use solana_sdk::transaction::TransactionError;
+
+    use super::*;
+    use crate::decoder::compiled_instruction;
+
+    // 主网一笔买入的 emit! 日志，和上面 test() 中 CPI 事件是同一个 TradeEvent
+    const TRADE_LOG: &str = "Program data: vdt/007mYe72OHc+lAoUnE1IlFvcgNSCLlBY63A2x421OX4iw9RkP8hn+AsAAAAA2kMYekwEAAABaF8y6nu3cpt2NpZKCS0bXj+Vb9vct94gPkWUwjOXDxwk0rpnAAAAAGpoW6IIAAAAQnSrzXkVAwBqvDemAQAAAELcmIHoFgIA";
+
+    fn context(
+        account_keys: Vec<Pubkey>,
+        instructions: Vec<UiCompiledInstruction>,
+        log_messages: Vec<String>,
+    ) -> TransactionContext {
+        TransactionContext {
+            slot: 1,
+            signature: Signature::default(),
+            writable: vec![true; account_keys.len()],
+            account_keys,
+            inner_instructions: Some(vec![vec![]; instructions.len()]),
+            instructions,
+            pre_token_balances: HashMap::new(),
+            post_token_balances: HashMap::new(),
+            log_messages,
+            compute_units_consumed: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_trade_event_from_log() {
+        let mint = Pubkey::from_str_const("Ha9EUR96afpCPaGrbQgcEMAZQbzjcjrCTdPYRrEapump");
+        let user = Pubkey::from_str_const("82RbWQawL2u5dmh9t1Hgw58qXs15bwq237beUjpZJr6F");
+        let trade = TradeEvent::try_from_log(TRADE_LOG).unwrap();
+        assert_eq!((trade.mint, trade.user), (mint, user));
+        assert_eq!(
+            (trade.sol_amount, trade.token_amount),
+            (200_828_872, 4_726_512_436_186)
+        );
+        assert!(trade.is_buy);
+        assert_eq!(trade.virtual_sol_reserves, 37_083_637_866);
+        assert_eq!(trade.virtual_token_reserves, 868_037_815_923_778);
+        assert!(TradeEvent::try_from_log("Program log: Instruction: Buy").is_none());
+
+        let ctx = context(vec![], vec![], vec![TRADE_LOG.to_string()]);
+        let swaps = PumpfunDecoder.decode_logs(&ctx);
+        assert_eq!(swaps.len(), 1);
+        let swap = &swaps[0];
+        assert_eq!(swap.pool, BondingCurve::address(&mint));
+        assert_eq!(
+            (swap.mint_in, swap.mint_out),
+            (constants::WSOL_MINT_PUBKEY, mint)
+        );
+        assert_eq!(
+            (swap.amount_in, swap.amount_out),
+            (200_828_872, 4_726_512_436_186)
+        );
+        assert_eq!(swap.trader, user);
+        assert_eq!(
+            swap.reserves_after,
+            Some((37_083_637_866, 868_037_815_923_778))
+        );
+    }
+
+    #[test]
+    fn test_failed_instruction_args() {
+        let account_keys: Vec<Pubkey> = (0..8).map(|_| Pubkey::new_unique()).collect();
+        let (mint, curve, user) = (account_keys[2], account_keys[3], account_keys[6]);
+        let mut buy = PUMPFUN_BUY_IX.to_vec();
+        buy.extend(
+            borsh::to_vec(&BuyArgs {
+                amount: 1_000_000,
+                max_sol_cost: 5_000,
+            })
+            .unwrap(),
+        );
+        let mut sell = PUMPFUN_SELL_IX.to_vec();
+        sell.extend(
+            borsh::to_vec(&SellArgs {
+                amount: 2_000_000,
+                min_sol_output: 7_000,
+            })
+            .unwrap(),
+        );
+        let accounts: Vec<u8> = (0..8).collect();
+        let instructions = vec![
+            compiled_instruction(1, accounts.clone(), buy),
+            compiled_instruction(1, accounts, sell),
+        ];
+        let mut ctx = context(account_keys, instructions, vec![]);
+        // 滑点失败的交易没有事件，只能从指令参数解析
+        ctx.error = Some(TransactionError::InstructionError(
+            0,
+            solana_sdk::instruction::InstructionError::Custom(6002),
+        ));
+
+        let path = |index| InstructionPath {
+            outer: index,
+            inner: None,
+        };
+        let swap = PumpfunDecoder
+            .decode_instruction_args(&ctx, path(0), &ctx.instructions[0])
+            .unwrap();
+        assert_eq!(
+            (swap.mint_in, swap.mint_out),
+            (constants::WSOL_MINT_PUBKEY, mint)
+        );
+        assert_eq!((swap.amount_in, swap.amount_out), (5_000, 1_000_000));
+        assert_eq!((swap.pool, swap.trader), (curve, user));
+
+        let swap = PumpfunDecoder
+            .decode_instruction_args(&ctx, path(1), &ctx.instructions[1])
+            .unwrap();
+        assert_eq!(
+            (swap.mint_in, swap.mint_out),
+            (mint, constants::WSOL_MINT_PUBKEY)
+        );
+        assert_eq!((swap.amount_in, swap.amount_out), (2_000_000, 7_000));
+    }
+
+    #[test]
+    fn test_pool_created() {
+        let create = CreateEvent {
+            name: "name".to_string(),
+            symbol: "SYM".to_string(),
+            uri: "uri".to_string(),
+            mint: Pubkey::new_unique(),
+            bonding_curve: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+        };
+        // 事件 CPI: 8 字节 event ix tag + 事件 discriminator + 事件
+        let mut data = vec![0u8; 8];
+        data.extend(PUMPFUN_CREATE_EVENT);
+        data.extend(borsh::to_vec(&create).unwrap());
+        let ctx = context(vec![], vec![], vec![]);
+        let pool = PumpfunDecoder
+            .decode_pool_created(&ctx, &compiled_instruction(0, vec![], data))
+            .unwrap();
+        assert_eq!(pool.pool, create.bonding_curve);
+        assert_eq!(
+            (pool.base_mint, pool.quote_mint),
+            (create.mint, constants::WSOL_MINT_PUBKEY)
+        );
+        assert_eq!(pool.creator, create.user);
+    }
+
+    #[test]
+    fn test_bonding_curve() {
+        let curve = BondingCurve {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+        };
+        let mut data = PUMPFUN_BONDING_CURVE_ACCOUNT.to_vec();
+        data.extend(borsh::to_vec(&curve).unwrap());
+        // 新版本追加的 creator
+        data.extend(Pubkey::new_unique().to_bytes());
+        assert_eq!(BondingCurve::try_from_account_data(&data).unwrap(), curve);
+        data[0] ^= 1;
+        assert!(BondingCurve::try_from_account_data(&data).is_err());
+
+        let mint = Pubkey::new_unique();
+        let (address, _) = Pubkey::find_program_address(
+            &[b"bonding-curve", mint.as_ref()],
+            &constants::PUMP_FUN_PROGRAM,
+        );
+        assert_eq!(BondingCurve::address(&mint), address);
+    }
+}
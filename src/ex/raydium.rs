@@ -1,8 +1,15 @@
 use anyhow::{Result, anyhow};
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_sdk::instruction::{AccountMeta, Instruction};
 use solana_sdk::pubkey::Pubkey;
 use solana_transaction_status::{UiCompiledInstruction, UiInstruction};
 
+use crate::constants;
+use crate::decoder::{
+    DexDecoder, InstructionPath, PoolCreated, SwapEvent, TransactionContext, Venue,
+};
+use crate::openbook::MarketAccounts;
+
 // https://github.com/raydium-io/raydium-amm/blob/master/program/src/instruction.rs#L134-L372
 // 这里监听的是指令
 #[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
@@ -302,6 +309,229 @@ impl TryFrom<UiInstruction> for AmmInstruction {
     }
 }
 
+/// Emits a `SwapEvent` for every `SwapBaseIn` / `SwapBaseOut` and a
+/// `PoolCreated` for every `Initialize2`.
+pub struct RaydiumAmmDecoder;
+
+impl DexDecoder for RaydiumAmmDecoder {
+    fn program_id(&self) -> Pubkey {
+        constants::RAYDIUM_AAM_PROGRAM
+    }
+
+    fn venue(&self) -> Venue {
+        Venue::RaydiumAmm
+    }
+
+    fn decode_instruction(
+        &self,
+        ctx: &TransactionContext,
+        path: InstructionPath,
+        ix: &UiCompiledInstruction,
+    ) -> Option<SwapEvent> {
+        if SwapInstructionBaseIn::try_from(ix).is_err()
+            && SwapInstructionBaseOut::try_from(ix).is_err()
+        {
+            return None;
+        }
+        // 不传 target orders 账户时只有 17 个账户，vault 前移一位
+        let vaults = if ix.accounts.len() >= 18 {
+            [5, 6]
+        } else {
+            [4, 5]
+        };
+        let trader = ix.accounts.len().checked_sub(1)?;
+        ctx.swap_from_vaults(self.venue(), path, ix, 1, trader, vaults)
+    }
+
+    fn decode_instruction_args(
+        &self,
+        ctx: &TransactionContext,
+        path: InstructionPath,
+        ix: &UiCompiledInstruction,
+    ) -> Option<SwapEvent> {
+        let (amount_in, amount_out) = if let Ok(args) = SwapInstructionBaseIn::try_from(ix) {
+            (args.amount_in, args.minimum_amount_out)
+        } else if let Ok(args) = SwapInstructionBaseOut::try_from(ix) {
+            (args.max_amount_in, args.amount_out)
+        } else {
+            return None;
+        };
+        // 账户末尾依次是 user source、user destination、user owner；
+        // 账户中没有 mint，只能从 token balances 中取得
+        let trader = ix.accounts.len().checked_sub(1)?;
+        let mint = |position: usize| {
+            let index = *ix.accounts.get(position)? as usize;
+            ctx.token_mint(index)
+        };
+        Some(SwapEvent {
+            venue: self.venue(),
+            pool: ctx.account(ix, 1)?,
+            trader: ctx.account(ix, trader)?,
+            mint_in: mint(trader.checked_sub(2)?).unwrap_or_default(),
+            mint_out: mint(trader - 1).unwrap_or_default(),
+            amount_in,
+            amount_out,
+            reserves_after: None,
+            slot: ctx.slot,
+            signature: ctx.signature,
+            instruction_path: Some(path),
+            error: None,
+        })
+    }
+
+    fn decode_pool_created(
+        &self,
+        ctx: &TransactionContext,
+        ix: &UiCompiledInstruction,
+    ) -> Option<PoolCreated> {
+        Initialize2Instruction::try_from(ix).ok()?;
+        // 账户顺序: 4 amm, 8 coin mint, 9 pc mint, 17 user wallet
+        Some(PoolCreated {
+            venue: self.venue(),
+            pool: ctx.account(ix, 4)?,
+            base_mint: ctx.account(ix, 8)?,
+            quote_mint: ctx.account(ix, 9)?,
+            creator: ctx.account(ix, 17)?,
+            slot: ctx.slot,
+            signature: ctx.signature,
+        })
+    }
+}
+
+// https://github.com/raydium-io/raydium-amm/blob/master/program/src/error.rs
+// 按声明顺序编号，对应 custom program error 的错误码，例如 0x1e 为 ExceededSlippage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RaydiumAmmError {
+    AlreadyInUse,
+    InvalidProgramAddress,
+    ExpectedMint,
+    ExpectedAccount,
+    InvalidCoinVault,
+    InvalidPCVault,
+    InvalidTokenLP,
+    InvalidDestTokenCoin,
+    InvalidDestTokenPC,
+    InvalidPoolMint,
+    InvalidOpenOrders,
+    InvalidSerumMarket,
+    InvalidSerumProgram,
+    InvalidTargetOrders,
+    InvalidWithdrawQueue,
+    InvalidTempLp,
+    InvalidCoinMint,
+    InvalidPCMint,
+    InvalidOwner,
+    InvalidSupply,
+    InvalidDelegate,
+    InvalidSignAccount,
+    InvalidStatus,
+    InvalidInstruction,
+    WrongAccountsNumber,
+    InvalidTargetAccountOwner,
+    InvalidTargetOwner,
+    InvalidAmmAccountOwner,
+    InvalidParamsSet,
+    InvalidInput,
+    ExceededSlippage,
+    CalculationExRateFailure,
+    CheckedSubOverflow,
+    CheckedAddOverflow,
+    CheckedMulOverflow,
+    CheckedDivOverflow,
+    CheckedEmptyFunds,
+    CalcPnlError,
+    InvalidSplTokenProgram,
+    TakePnlError,
+    InsufficientFunds,
+    ConversionFailure,
+    InvalidUserToken,
+    InvalidSrmMint,
+    InvalidSrmToken,
+    TooManyOpenOrders,
+    OrderAtSlotIsPlaced,
+    InvalidSysProgramAddress,
+    InvalidFee,
+    RepeatCreateAmm,
+    NotAllowZeroLP,
+    InvalidCloseAuthority,
+    InvalidFreezeAuthority,
+    InvalidReferPCMint,
+    InvalidConfigAccount,
+    RepeatCreateConfigAccount,
+    MarketLotSizeIsTooLarge,
+    InitLpAmountTooLess,
+    UnknownAmmError,
+}
+
+impl RaydiumAmmError {
+    pub fn from_code(code: u32) -> Option<Self> {
+        use RaydiumAmmError::*;
+        let error = match code {
+            0 => AlreadyInUse,
+            1 => InvalidProgramAddress,
+            2 => ExpectedMint,
+            3 => ExpectedAccount,
+            4 => InvalidCoinVault,
+            5 => InvalidPCVault,
+            6 => InvalidTokenLP,
+            7 => InvalidDestTokenCoin,
+            8 => InvalidDestTokenPC,
+            9 => InvalidPoolMint,
+            10 => InvalidOpenOrders,
+            11 => InvalidSerumMarket,
+            12 => InvalidSerumProgram,
+            13 => InvalidTargetOrders,
+            14 => InvalidWithdrawQueue,
+            15 => InvalidTempLp,
+            16 => InvalidCoinMint,
+            17 => InvalidPCMint,
+            18 => InvalidOwner,
+            19 => InvalidSupply,
+            20 => InvalidDelegate,
+            21 => InvalidSignAccount,
+            22 => InvalidStatus,
+            23 => InvalidInstruction,
+            24 => WrongAccountsNumber,
+            25 => InvalidTargetAccountOwner,
+            26 => InvalidTargetOwner,
+            27 => InvalidAmmAccountOwner,
+            28 => InvalidParamsSet,
+            29 => InvalidInput,
+            30 => ExceededSlippage,
+            31 => CalculationExRateFailure,
+            32 => CheckedSubOverflow,
+            33 => CheckedAddOverflow,
+            34 => CheckedMulOverflow,
+            35 => CheckedDivOverflow,
+            36 => CheckedEmptyFunds,
+            37 => CalcPnlError,
+            38 => InvalidSplTokenProgram,
+            39 => TakePnlError,
+            40 => InsufficientFunds,
+            41 => ConversionFailure,
+            42 => InvalidUserToken,
+            43 => InvalidSrmMint,
+            44 => InvalidSrmToken,
+            45 => TooManyOpenOrders,
+            46 => OrderAtSlotIsPlaced,
+            47 => InvalidSysProgramAddress,
+            48 => InvalidFee,
+            49 => RepeatCreateAmm,
+            50 => NotAllowZeroLP,
+            51 => InvalidCloseAuthority,
+            52 => InvalidFreezeAuthority,
+            53 => InvalidReferPCMint,
+            54 => InvalidConfigAccount,
+            55 => RepeatCreateConfigAccount,
+            56 => MarketLotSizeIsTooLarge,
+            57 => InitLpAmountTooLess,
+            58 => UnknownAmmError,
+            _ => return None,
+        };
+        Some(error)
+    }
+}
+
 // https://github.com/raydium-io/raydium-amm/blob/master/program/src/instruction.rs#L95C1-L100C2
 #[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
 pub struct SwapInstructionBaseIn {
@@ -330,6 +560,60 @@ impl TryFrom<&UiCompiledInstruction> for SwapInstructionBaseIn {
     }
 }
 
+impl SwapInstructionBaseIn {
+    /// Builds the instruction with the 18 accounts listed on `AmmInstruction::SwapBaseIn`,
+    /// signed by `user_owner`.
+    pub fn instruction(
+        &self,
+        amm: &Pubkey,
+        info: &AmmInfo,
+        market: &MarketAccounts,
+        user_source: &Pubkey,
+        user_destination: &Pubkey,
+        user_owner: &Pubkey,
+    ) -> Result<Instruction> {
+        let accounts = vec![
+            AccountMeta::new_readonly(constants::TOKEN_PROGRAM, false),
+            AccountMeta::new(*amm, false),
+            AccountMeta::new_readonly(amm_authority(info.nonce)?, false),
+            AccountMeta::new(info.open_orders, false),
+            AccountMeta::new(info.target_orders, false),
+            AccountMeta::new(info.coin_vault, false),
+            AccountMeta::new(info.pc_vault, false),
+            AccountMeta::new_readonly(market.market_program, false),
+            AccountMeta::new(market.market, false),
+            AccountMeta::new(market.bids, false),
+            AccountMeta::new(market.asks, false),
+            AccountMeta::new(market.event_queue, false),
+            AccountMeta::new(market.coin_vault, false),
+            AccountMeta::new(market.pc_vault, false),
+            AccountMeta::new_readonly(market.vault_signer, false),
+            AccountMeta::new(*user_source, false),
+            AccountMeta::new(*user_destination, false),
+            AccountMeta::new_readonly(*user_owner, true),
+        ];
+        let mut data = vec![9u8];
+        self.serialize(&mut data)?;
+        Ok(Instruction::new_with_bytes(
+            constants::RAYDIUM_AAM_PROGRAM,
+            &data,
+            accounts,
+        ))
+    }
+}
+
+// https://github.com/raydium-io/raydium-amm/blob/master/program/src/processor.rs
+const AUTHORITY_AMM: &[u8] = b"amm authority";
+
+/// Authority over the AMM vaults, derived from the pool's `nonce`.
+pub fn amm_authority(nonce: u64) -> Result<Pubkey> {
+    Pubkey::create_program_address(
+        &[AUTHORITY_AMM, &[nonce as u8]],
+        &constants::RAYDIUM_AAM_PROGRAM,
+    )
+    .map_err(|e| anyhow!("failed to derive AMM authority: {}", e))
+}
+
 // https://github.com/raydium-io/raydium-amm/blob/master/program/src/instruction.rs#L104C1-L109C2
 #[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
 pub struct SwapInstructionBaseOut {
@@ -723,3 +1007,159 @@ impl TryFrom<&UiCompiledInstruction> for MigrateToOpenBook {
         ));
     }
 }
+
+/// Size of an `AmmInfo` account.
+pub const AMM_INFO_LEN: usize = 752;
+
+// https://github.com/raydium-io/raydium-amm/blob/master/program/src/state.rs#L264-L278
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct StateData {
+    /// delay to take pnl coin
+    pub need_take_pnl_coin: u64,
+    /// delay to take pnl pc
+    pub need_take_pnl_pc: u64,
+    /// total pnl pc
+    pub total_pnl_pc: u64,
+    /// total pnl coin
+    pub total_pnl_coin: u64,
+    /// ido pool open time
+    pub pool_open_time: u64,
+    /// padding for future updates
+    pub padding: [u64; 2],
+    /// switch from orderbookonly to init
+    pub orderbook_to_init_time: u64,
+    /// swap coin in amount
+    pub swap_coin_in_amount: u128,
+    /// swap pc out amount
+    pub swap_pc_out_amount: u128,
+    /// charge pc as swap fee while swap pc to coin
+    pub swap_acc_pc_fee: u64,
+    /// swap pc in amount
+    pub swap_pc_in_amount: u128,
+    /// swap coin out amount
+    pub swap_coin_out_amount: u128,
+    /// charge coin as swap fee while swap coin to pc
+    pub swap_acc_coin_fee: u64,
+}
+
+// https://github.com/raydium-io/raydium-amm/blob/master/program/src/state.rs#L293-L353
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct AmmInfo {
+    /// Initialized status.
+    pub status: u64,
+    /// Nonce used in program address.
+    pub nonce: u64,
+    /// max order count
+    pub order_num: u64,
+    /// within this range, 5 => 5% range
+    pub depth: u64,
+    /// coin decimal
+    pub coin_decimals: u64,
+    /// pc decimal
+    pub pc_decimals: u64,
+    /// amm machine state
+    pub state: u64,
+    /// amm reset_flag
+    pub reset_flag: u64,
+    /// min size 1->0.000001
+    pub min_size: u64,
+    /// vol_max_cut_ratio numerator, sys_decimal_value as denominator
+    pub vol_max_cut_ratio: u64,
+    /// amount wave numerator, sys_decimal_value as denominator
+    pub amount_wave: u64,
+    /// coinLotSize 1 -> 0.000001
+    pub coin_lot_size: u64,
+    /// pcLotSize 1 -> 0.000001
+    pub pc_lot_size: u64,
+    /// min_cur_price: (2 * amm.order_num * amm.pc_lot_size) * max_price_multiplier
+    pub min_price_multiplier: u64,
+    /// max_cur_price: (2 * amm.order_num * amm.pc_lot_size) * max_price_multiplier
+    pub max_price_multiplier: u64,
+    /// system decimal value, used to normalize the value of coin and pc amount
+    pub sys_decimal_value: u64,
+    /// All fee information
+    pub fees: Fees,
+    /// Statistical data
+    pub state_data: StateData,
+    /// Coin vault
+    pub coin_vault: Pubkey,
+    /// Pc vault
+    pub pc_vault: Pubkey,
+    /// Coin vault mint
+    pub coin_vault_mint: Pubkey,
+    /// Pc vault mint
+    pub pc_vault_mint: Pubkey,
+    /// lp mint
+    pub lp_mint: Pubkey,
+    /// open_orders key
+    pub open_orders: Pubkey,
+    /// market key
+    pub market: Pubkey,
+    /// market program key
+    pub market_program: Pubkey,
+    /// target_orders key
+    pub target_orders: Pubkey,
+    /// padding
+    pub padding1: [u64; 8],
+    /// amm owner key
+    pub amm_owner: Pubkey,
+    /// pool lp amount
+    pub lp_amount: u64,
+    /// client order id
+    pub client_order_id: u64,
+    /// recent epoch
+    pub recent_epoch: u64,
+    /// padding
+    pub padding2: u64,
+}
+
+impl AmmInfo {
+    /// Decodes the raw AMM account data. Raydium v4 accounts carry no discriminator.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() != AMM_INFO_LEN {
+            return Err(anyhow!(
+                "failed to convert to target Raydium AmmInfo account"
+            ));
+        }
+        Ok(AmmInfo::try_from_slice(data)?)
+    }
+
+    /// Tokens received for `amount_in` on the constant product curve, after the swap fee is
+    /// deducted from the input. `None` for a fee above 100% or an empty pool.
+    pub fn quote_swap_base_in(
+        &self,
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+    ) -> Option<u64> {
+        let fees = &self.fees;
+        if fees.swap_fee_denominator == 0 || fees.swap_fee_numerator > fees.swap_fee_denominator {
+            return None;
+        }
+        let fee = (amount_in as u128 * fees.swap_fee_numerator as u128)
+            .div_ceil(fees.swap_fee_denominator as u128);
+        let amount_in = amount_in as u128 - fee;
+        let amount_out =
+            (reserve_out as u128 * amount_in).checked_div(reserve_in as u128 + amount_in)?;
+        u64::try_from(amount_out).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amm_info_layout() {
+        // borsh 要求刚好读完所有字节，长度不一致说明布局有误
+        let mut data = vec![0u8; AMM_INFO_LEN];
+        let market = Pubkey::new_unique();
+        // market 位于 16 个 u64 + Fees(8 个 u64) + StateData(144 字节) + 6 个 Pubkey 之后
+        let offset = 16 * 8 + 8 * 8 + 144 + 6 * 32;
+        data[offset..offset + 32].copy_from_slice(market.as_ref());
+
+        let amm = AmmInfo::try_from_account_data(&data).unwrap();
+        assert_eq!(amm.market, market);
+        assert!(AmmInfo::try_from_account_data(&data[1..]).is_err());
+    }
+}
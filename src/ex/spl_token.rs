@@ -0,0 +1,451 @@
+use anyhow::{Result, anyhow};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{UiCompiledInstruction, UiInstruction};
+
+use crate::constants;
+use crate::decoder::{InstructionPath, TransactionContext};
+
+// SPL Token 指令第一个字节为指令下标，Token-2022 沿用相同的下标
+const INITIALIZE_ACCOUNT_IX: u8 = 1;
+const TRANSFER_IX: u8 = 3;
+const MINT_TO_IX: u8 = 7;
+const BURN_IX: u8 = 8;
+const CLOSE_ACCOUNT_IX: u8 = 9;
+const TRANSFER_CHECKED_IX: u8 = 12;
+const MINT_TO_CHECKED_IX: u8 = 14;
+const BURN_CHECKED_IX: u8 = 15;
+const INITIALIZE_ACCOUNT2_IX: u8 = 16;
+const INITIALIZE_ACCOUNT3_IX: u8 = 18;
+// Token-2022 transfer fee 扩展，第二个字节为扩展内的指令下标
+const TRANSFER_FEE_EXTENSION_IX: u8 = 26;
+const TRANSFER_CHECKED_WITH_FEE_IX: u8 = 1;
+
+// https://github.com/solana-program/token/blob/main/program/src/instruction.rs
+// https://github.com/solana-program/token-2022/blob/main/program/src/extension/transfer_fee/instruction.rs
+// 只解析与资金流向相关的指令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenInstruction {
+    ///   Initializes a new account to hold tokens.
+    ///
+    ///   0. `[writable]` The account to initialize
+    ///   1. `[]` The mint this account will be associated with
+    ///   2. `[]` The new account's owner/multisignature
+    ///   3. `[]` Rent sysvar
+    InitializeAccount,
+
+    ///   Like `InitializeAccount`, with the owner passed in the instruction data.
+    ///
+    ///   0. `[writable]` The account to initialize
+    ///   1. `[]` The mint this account will be associated with
+    ///   2. `[]` Rent sysvar
+    InitializeAccount2(OwnerArgs),
+
+    ///   Like `InitializeAccount2`, without the rent sysvar.
+    InitializeAccount3(OwnerArgs),
+
+    ///   Transfers tokens from one account to another.
+    ///
+    ///   0. `[writable]` The source account
+    ///   1. `[writable]` The destination account
+    ///   2. `[signer]` The source account's owner/delegate
+    Transfer(AmountArgs),
+
+    ///   Transfers tokens, asserting the mint and decimals.
+    ///
+    ///   0. `[writable]` The source account
+    ///   1. `[]` The token mint
+    ///   2. `[writable]` The destination account
+    ///   3. `[signer]` The source account's owner/delegate
+    TransferChecked(AmountDecimalsArgs),
+
+    ///   Token-2022 transfer that also asserts the withheld transfer fee.
+    ///   Accounts as in `TransferChecked`.
+    TransferCheckedWithFee(TransferCheckedWithFeeArgs),
+
+    ///   Mints new tokens to an account.
+    ///
+    ///   0. `[writable]` The mint
+    ///   1. `[writable]` The account to mint tokens to
+    ///   2. `[signer]` The mint's minting authority
+    MintTo(AmountArgs),
+
+    ///   Like `MintTo`, asserting the decimals. Accounts as in `MintTo`.
+    MintToChecked(AmountDecimalsArgs),
+
+    ///   Burns tokens by removing them from an account.
+    ///
+    ///   0. `[writable]` The account to burn from
+    ///   1. `[writable]` The token mint
+    ///   2. `[signer]` The account's owner/delegate
+    Burn(AmountArgs),
+
+    ///   Like `Burn`, asserting the decimals. Accounts as in `Burn`.
+    BurnChecked(AmountDecimalsArgs),
+
+    ///   Closes an account by transferring all its SOL to the destination account.
+    ///
+    ///   0. `[writable]` The account to close
+    ///   1. `[writable]` The destination account
+    ///   2. `[signer]` The account's owner
+    CloseAccount,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct AmountArgs {
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct AmountDecimalsArgs {
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct TransferCheckedWithFeeArgs {
+    pub amount: u64,
+    pub decimals: u8,
+    pub fee: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct OwnerArgs {
+    pub owner: Pubkey,
+}
+
+impl TryFrom<UiInstruction> for TokenInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ix: UiInstruction) -> Result<TokenInstruction> {
+        match ix {
+            UiInstruction::Compiled(ui_ix) => TokenInstruction::try_from(&ui_ix),
+            _ => Err(anyhow!("failed to convert to target TokenInstruction")),
+        }
+    }
+}
+
+impl TryFrom<&UiCompiledInstruction> for TokenInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ui_ix: &UiCompiledInstruction) -> Result<TokenInstruction> {
+        let data = bs58::decode(&ui_ix.data).into_vec()?;
+        let Some((index, mut args)) = data.split_first() else {
+            return Err(anyhow!("failed to convert to target TokenInstruction"));
+        };
+        let args = &mut args;
+
+        let ix = match *index {
+            INITIALIZE_ACCOUNT_IX => Self::InitializeAccount,
+            INITIALIZE_ACCOUNT2_IX => Self::InitializeAccount2(OwnerArgs::deserialize(args)?),
+            INITIALIZE_ACCOUNT3_IX => Self::InitializeAccount3(OwnerArgs::deserialize(args)?),
+            TRANSFER_IX => Self::Transfer(AmountArgs::deserialize(args)?),
+            TRANSFER_CHECKED_IX => Self::TransferChecked(AmountDecimalsArgs::deserialize(args)?),
+            MINT_TO_IX => Self::MintTo(AmountArgs::deserialize(args)?),
+            MINT_TO_CHECKED_IX => Self::MintToChecked(AmountDecimalsArgs::deserialize(args)?),
+            BURN_IX => Self::Burn(AmountArgs::deserialize(args)?),
+            BURN_CHECKED_IX => Self::BurnChecked(AmountDecimalsArgs::deserialize(args)?),
+            CLOSE_ACCOUNT_IX => Self::CloseAccount,
+            TRANSFER_FEE_EXTENSION_IX => match args.split_first() {
+                Some((&TRANSFER_CHECKED_WITH_FEE_IX, rest)) => Self::TransferCheckedWithFee(
+                    TransferCheckedWithFeeArgs::deserialize(&mut &rest[..])?,
+                ),
+                _ => return Err(anyhow!("failed to convert to target TokenInstruction")),
+            },
+            _ => return Err(anyhow!("failed to convert to target TokenInstruction")),
+        };
+        Ok(ix)
+    }
+}
+
+impl TokenInstruction {
+    /// Token amount moved, minted or burned.
+    pub fn amount(&self) -> Option<u64> {
+        match self {
+            Self::Transfer(args) | Self::MintTo(args) | Self::Burn(args) => Some(args.amount),
+            Self::TransferChecked(args) | Self::MintToChecked(args) | Self::BurnChecked(args) => {
+                Some(args.amount)
+            }
+            Self::TransferCheckedWithFee(args) => Some(args.amount),
+            _ => None,
+        }
+    }
+
+    /// Transfer fee withheld by a Token-2022 mint, as declared by the instruction.
+    pub fn fee(&self) -> Option<u64> {
+        match self {
+            Self::TransferCheckedWithFee(args) => Some(args.fee),
+            _ => None,
+        }
+    }
+}
+
+/// Accounts of a token instruction, resolved to addresses.
+///
+/// `source` is the account debited (or closed), `destination` the account credited,
+/// initialized or receiving the lamports of a closed account. `authority` is the
+/// signing owner/delegate, or the new owner for `InitializeAccount*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TokenAccounts {
+    pub source: Option<Pubkey>,
+    pub destination: Option<Pubkey>,
+    pub mint: Option<Pubkey>,
+    pub authority: Option<Pubkey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedTokenInstruction {
+    /// Token or Token-2022 program
+    pub program_id: Pubkey,
+    pub instruction: TokenInstruction,
+    pub accounts: TokenAccounts,
+    pub instruction_path: InstructionPath,
+}
+
+impl ResolvedTokenInstruction {
+    pub fn is_token_2022(&self) -> bool {
+        self.program_id == constants::TOKEN_2022_PROGRAM
+    }
+}
+
+/// Size of a token account without Token-2022 extensions.
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+
+// Token-2022 带扩展的账户在 165 字节处记录账户类型，mint 也补齐到 165 字节后再记录
+// https://github.com/solana-program/token-2022/blob/main/program/src/extension/mod.rs
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+/// Balance part of a token account, e.g. an AMM vault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenAccountState {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+impl TokenAccountState {
+    /// Decodes Token and Token-2022 accounts, extensions are ignored.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        // 没有扩展的 mint 只有 82 字节；有扩展时按账户类型区分账户和 mint
+        let is_account = match data.len() {
+            TOKEN_ACCOUNT_LEN => true,
+            len if len > TOKEN_ACCOUNT_LEN => data[TOKEN_ACCOUNT_LEN] == ACCOUNT_TYPE_ACCOUNT,
+            _ => false,
+        };
+        if !is_account {
+            return Err(anyhow!("failed to convert to target token account"));
+        }
+        Ok(TokenAccountState {
+            mint: Pubkey::try_from(&data[..32])?,
+            owner: Pubkey::try_from(&data[32..64])?,
+            amount: u64::from_le_bytes(data[64..72].try_into()?),
+        })
+    }
+}
+
+fn resolve_accounts(
+    ctx: &TransactionContext,
+    ix: &UiCompiledInstruction,
+    instruction: &TokenInstruction,
+) -> TokenAccounts {
+    let key = |position: usize| ctx.account(ix, position);
+    // Transfer 不带 mint，通过 token balances 反查
+    let mint_of = |position: usize| {
+        ix.accounts
+            .get(position)
+            .and_then(|index| ctx.token_mint(*index as usize))
+    };
+
+    match instruction {
+        TokenInstruction::InitializeAccount => TokenAccounts {
+            destination: key(0),
+            mint: key(1),
+            authority: key(2),
+            ..Default::default()
+        },
+        TokenInstruction::InitializeAccount2(args) | TokenInstruction::InitializeAccount3(args) => {
+            TokenAccounts {
+                destination: key(0),
+                mint: key(1),
+                authority: Some(args.owner),
+                ..Default::default()
+            }
+        }
+        TokenInstruction::Transfer(_) => TokenAccounts {
+            source: key(0),
+            destination: key(1),
+            mint: mint_of(0).or_else(|| mint_of(1)),
+            authority: key(2),
+        },
+        TokenInstruction::TransferChecked(_) | TokenInstruction::TransferCheckedWithFee(_) => {
+            TokenAccounts {
+                source: key(0),
+                destination: key(2),
+                mint: key(1),
+                authority: key(3),
+            }
+        }
+        TokenInstruction::MintTo(_) | TokenInstruction::MintToChecked(_) => TokenAccounts {
+            destination: key(1),
+            mint: key(0),
+            authority: key(2),
+            ..Default::default()
+        },
+        TokenInstruction::Burn(_) | TokenInstruction::BurnChecked(_) => TokenAccounts {
+            source: key(0),
+            mint: key(1),
+            authority: key(2),
+            ..Default::default()
+        },
+        TokenInstruction::CloseAccount => TokenAccounts {
+            source: key(0),
+            destination: key(1),
+            mint: mint_of(0),
+            authority: key(2),
+        },
+    }
+}
+
+/// Decodes every Token / Token-2022 instruction of the transaction, inner ones included.
+pub fn decode_token_instructions(ctx: &TransactionContext) -> Vec<ResolvedTokenInstruction> {
+    let token_program = constants::TOKEN_PROGRAM;
+    let token_2022_program = constants::TOKEN_2022_PROGRAM;
+
+    ctx.instructions_with_path()
+        .filter_map(|(path, ix)| {
+            let program_id = ctx.program_id(ix)?;
+            if program_id != token_program && program_id != token_2022_program {
+                return None;
+            }
+            let instruction = TokenInstruction::try_from(ix).ok()?;
+            Some(ResolvedTokenInstruction {
+                program_id,
+                accounts: resolve_accounts(ctx, ix, &instruction),
+                instruction,
+                instruction_path: path,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use solana_sdk::signature::Signature;
+
+    use crate::decoder::{TokenBalance, compiled_instruction};
+
+    #[test]
+    fn test_decode_token_instructions() {
+        let token_program = constants::TOKEN_PROGRAM;
+        let token_2022_program = constants::TOKEN_2022_PROGRAM;
+        let (source, destination, owner, mint) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        let mut transfer = vec![TRANSFER_IX];
+        transfer.extend(500u64.to_le_bytes());
+        let mut with_fee = vec![TRANSFER_FEE_EXTENSION_IX, TRANSFER_CHECKED_WITH_FEE_IX];
+        with_fee.extend(1_000u64.to_le_bytes());
+        with_fee.push(6);
+        with_fee.extend(10u64.to_le_bytes());
+
+        let ctx = TransactionContext {
+            slot: 1,
+            signature: Signature::default(),
+            account_keys: vec![
+                owner,
+                source,
+                destination,
+                mint,
+                token_program,
+                token_2022_program,
+            ],
+            instructions: vec![compiled_instruction(4, vec![1, 2, 0], transfer)],
+            inner_instructions: Some(vec![vec![
+                compiled_instruction(5, vec![1, 3, 2, 0], with_fee),
+                compiled_instruction(4, vec![1, 2, 0], vec![CLOSE_ACCOUNT_IX]),
+            ]]),
+            pre_token_balances: HashMap::new(),
+            post_token_balances: HashMap::from([(
+                1,
+                TokenBalance {
+                    mint,
+                    owner: Some(owner),
+                    amount: 0,
+                },
+            )]),
+            log_messages: vec![],
+            compute_units_consumed: None,
+            error: None,
+            writable: vec![],
+        };
+
+        let decoded = decode_token_instructions(&ctx);
+        assert_eq!(decoded.len(), 3);
+
+        assert_eq!(
+            decoded[0].instruction,
+            TokenInstruction::Transfer(AmountArgs { amount: 500 })
+        );
+        assert_eq!(
+            decoded[0].accounts,
+            TokenAccounts {
+                source: Some(source),
+                destination: Some(destination),
+                mint: Some(mint),
+                authority: Some(owner),
+            }
+        );
+
+        assert!(decoded[1].is_token_2022());
+        assert_eq!(decoded[1].instruction.amount(), Some(1_000));
+        assert_eq!(decoded[1].instruction.fee(), Some(10));
+        assert_eq!(decoded[1].accounts.mint, Some(mint));
+        assert_eq!(
+            decoded[1].instruction_path,
+            InstructionPath {
+                outer: 0,
+                inner: Some(0)
+            }
+        );
+
+        assert_eq!(decoded[2].instruction, TokenInstruction::CloseAccount);
+        assert_eq!(decoded[2].accounts.destination, Some(destination));
+    }
+
+    #[test]
+    fn test_token_account_state() {
+        let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![0u8; TOKEN_ACCOUNT_LEN];
+        data[..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        data[64..72].copy_from_slice(&1_000u64.to_le_bytes());
+        let expected = TokenAccountState {
+            mint,
+            owner,
+            amount: 1_000,
+        };
+        assert_eq!(
+            TokenAccountState::try_from_account_data(&data).unwrap(),
+            expected
+        );
+        assert!(TokenAccountState::try_from_account_data(&data[..82]).is_err());
+
+        // Token-2022 带扩展的账户: 账户类型之后是 TLV 扩展
+        data.push(ACCOUNT_TYPE_ACCOUNT);
+        data.extend([0u8; 8]);
+        assert_eq!(
+            TokenAccountState::try_from_account_data(&data).unwrap(),
+            expected
+        );
+        // 带扩展的 mint 同样超过 165 字节，账户类型为 1
+        data[TOKEN_ACCOUNT_LEN] = 1;
+        assert!(TokenAccountState::try_from_account_data(&data).is_err());
+    }
+}
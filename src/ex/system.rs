@@ -0,0 +1,226 @@
+use anyhow::{Result, anyhow};
+use solana_program::system_instruction::SystemInstruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_program;
+use solana_transaction_status::UiCompiledInstruction;
+
+use crate::decoder::{InstructionPath, TransactionContext};
+
+// https://github.com/anza-xyz/solana-sdk/blob/master/system-interface/src/instruction.rs
+// System 程序指令使用 bincode 编码（u32 下标 + 参数），直接复用官方的 SystemInstruction
+pub fn decode_system_instruction(ui_ix: &UiCompiledInstruction) -> Result<SystemInstruction> {
+    let data = bs58::decode(&ui_ix.data).into_vec()?;
+    bincode::deserialize(&data)
+        .map_err(|_| anyhow!("failed to convert to target SystemInstruction"))
+}
+
+/// Accounts of a System instruction, resolved to addresses.
+///
+/// `from` is the account paying lamports (the funding account for `CreateAccount*`),
+/// `to` the account receiving them or being created. `nonce` is set for nonce
+/// instructions, with `authority` the signing nonce authority (or the new one
+/// for `InitializeNonceAccount` / `AuthorizeNonceAccount`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SystemAccounts {
+    pub from: Option<Pubkey>,
+    pub to: Option<Pubkey>,
+    pub nonce: Option<Pubkey>,
+    pub authority: Option<Pubkey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedSystemInstruction {
+    pub instruction: SystemInstruction,
+    pub accounts: SystemAccounts,
+    pub instruction_path: InstructionPath,
+}
+
+impl ResolvedSystemInstruction {
+    /// Lamports moved from `from` to `to`.
+    pub fn lamports(&self) -> Option<u64> {
+        match self.instruction {
+            SystemInstruction::Transfer { lamports }
+            | SystemInstruction::TransferWithSeed { lamports, .. }
+            | SystemInstruction::CreateAccount { lamports, .. }
+            | SystemInstruction::CreateAccountWithSeed { lamports, .. }
+            | SystemInstruction::WithdrawNonceAccount(lamports) => Some(lamports),
+            _ => None,
+        }
+    }
+}
+
+// 只保留资金流向与 nonce 相关的指令，其余返回 None
+fn resolve_accounts(
+    ctx: &TransactionContext,
+    ix: &UiCompiledInstruction,
+    instruction: &SystemInstruction,
+) -> Option<SystemAccounts> {
+    let key = |position: usize| ctx.account(ix, position);
+
+    let accounts = match instruction {
+        //   0. `[writable, signer]` Funding account
+        //   1. `[writable, signer]` New account
+        SystemInstruction::CreateAccount { .. }
+        | SystemInstruction::CreateAccountWithSeed { .. } => SystemAccounts {
+            from: key(0),
+            to: key(1),
+            ..Default::default()
+        },
+        //   0. `[writable, signer]` Funding account
+        //   1. `[writable]` Recipient account
+        SystemInstruction::Transfer { .. } => SystemAccounts {
+            from: key(0),
+            to: key(1),
+            ..Default::default()
+        },
+        //   0. `[writable]` Funding account, derived from the base
+        //   1. `[signer]` Base account
+        //   2. `[writable]` Recipient account
+        SystemInstruction::TransferWithSeed { .. } => SystemAccounts {
+            from: key(0),
+            to: key(2),
+            authority: key(1),
+            ..Default::default()
+        },
+        //   0. `[writable]` Nonce account
+        //   1. `[]` RecentBlockhashes sysvar
+        //   2. `[signer]` Nonce authority
+        SystemInstruction::AdvanceNonceAccount => SystemAccounts {
+            nonce: key(0),
+            authority: key(2),
+            ..Default::default()
+        },
+        //   0. `[writable]` Nonce account
+        //   1. `[writable]` Recipient account
+        //   2. `[]` RecentBlockhashes sysvar
+        //   3. `[]` Rent sysvar
+        //   4. `[signer]` Nonce authority
+        SystemInstruction::WithdrawNonceAccount(_) => SystemAccounts {
+            from: key(0),
+            to: key(1),
+            nonce: key(0),
+            authority: key(4),
+        },
+        SystemInstruction::InitializeNonceAccount(authority)
+        | SystemInstruction::AuthorizeNonceAccount(authority) => SystemAccounts {
+            nonce: key(0),
+            authority: Some(*authority),
+            ..Default::default()
+        },
+        SystemInstruction::UpgradeNonceAccount => SystemAccounts {
+            nonce: key(0),
+            ..Default::default()
+        },
+        _ => return None,
+    };
+    Some(accounts)
+}
+
+/// Decodes the transfers, account creations and nonce instructions of the
+/// System program, inner ones included.
+pub fn decode_system_instructions(ctx: &TransactionContext) -> Vec<ResolvedSystemInstruction> {
+    ctx.instructions_with_path()
+        .filter_map(|(path, ix)| {
+            if ctx.program_id(ix)? != system_program::id() {
+                return None;
+            }
+            let instruction = decode_system_instruction(ix).ok()?;
+            Some(ResolvedSystemInstruction {
+                accounts: resolve_accounts(ctx, ix, &instruction)?,
+                instruction,
+                instruction_path: path,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use solana_program::system_instruction;
+    use solana_sdk::signature::Signature;
+
+    use crate::decoder::compiled_instruction;
+
+    fn ui_ix(ix: solana_sdk::instruction::Instruction, keys: &[Pubkey]) -> UiCompiledInstruction {
+        let index = |key: &Pubkey| keys.iter().position(|k| k == key).unwrap() as u8;
+        compiled_instruction(
+            index(&ix.program_id),
+            ix.accounts.iter().map(|meta| index(&meta.pubkey)).collect(),
+            &ix.data,
+        )
+    }
+
+    #[test]
+    fn test_decode_system_instructions() {
+        let (payer, wallet, nonce, base) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let derived = Pubkey::create_with_seed(&base, "seed", &system_program::id()).unwrap();
+        let keys = vec![
+            payer,
+            wallet,
+            nonce,
+            base,
+            derived,
+            system_program::id(),
+            solana_sdk::sysvar::recent_blockhashes::id(),
+        ];
+
+        let ctx = TransactionContext {
+            slot: 1,
+            signature: Signature::default(),
+            instructions: vec![
+                ui_ix(
+                    system_instruction::advance_nonce_account(&nonce, &payer),
+                    &keys,
+                ),
+                ui_ix(system_instruction::transfer(&payer, &wallet, 1_000), &keys),
+                ui_ix(
+                    system_instruction::transfer_with_seed(
+                        &derived,
+                        &base,
+                        "seed".to_string(),
+                        &system_program::id(),
+                        &wallet,
+                        500,
+                    ),
+                    &keys,
+                ),
+                ui_ix(system_instruction::assign(&wallet, &base), &keys),
+            ],
+            account_keys: keys,
+            inner_instructions: Some(vec![vec![]; 4]),
+            pre_token_balances: HashMap::new(),
+            post_token_balances: HashMap::new(),
+            log_messages: vec![],
+            compute_units_consumed: None,
+            error: None,
+            writable: vec![],
+        };
+
+        let decoded = decode_system_instructions(&ctx);
+        // Assign 不是资金相关的指令，被过滤掉
+        assert_eq!(decoded.len(), 3);
+
+        assert_eq!(
+            decoded[0].instruction,
+            SystemInstruction::AdvanceNonceAccount
+        );
+        assert_eq!(decoded[0].accounts.nonce, Some(nonce));
+        assert_eq!(decoded[0].accounts.authority, Some(payer));
+
+        assert_eq!(decoded[1].lamports(), Some(1_000));
+        assert_eq!(decoded[1].accounts.from, Some(payer));
+        assert_eq!(decoded[1].accounts.to, Some(wallet));
+
+        assert_eq!(decoded[2].lamports(), Some(500));
+        assert_eq!(decoded[2].accounts.from, Some(derived));
+        assert_eq!(decoded[2].accounts.authority, Some(base));
+    }
+}
@@ -0,0 +1,583 @@
+use anyhow::{Result, anyhow};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{UiCompiledInstruction, UiInstruction};
+
+use crate::constants;
+use crate::decoder::{DexDecoder, InstructionPath, SwapEvent, TransactionContext, Venue};
+
+// Anchor 指令 discriminator: sha256("global:<name>")[..8]
+const SWAP_IX: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+const SWAP_V2_IX: [u8; 8] = [43, 4, 237, 11, 26, 201, 30, 98];
+const TWO_HOP_SWAP_IX: [u8; 8] = [195, 96, 237, 108, 68, 162, 219, 230];
+const TWO_HOP_SWAP_V2_IX: [u8; 8] = [186, 143, 209, 29, 254, 2, 194, 117];
+const INCREASE_LIQUIDITY_IX: [u8; 8] = [46, 156, 243, 118, 13, 205, 251, 178];
+const INCREASE_LIQUIDITY_V2_IX: [u8; 8] = [133, 29, 89, 223, 69, 238, 176, 10];
+const DECREASE_LIQUIDITY_IX: [u8; 8] = [160, 38, 208, 111, 104, 91, 44, 1];
+const DECREASE_LIQUIDITY_V2_IX: [u8; 8] = [58, 127, 188, 62, 79, 82, 196, 96];
+
+// sha256("account:Whirlpool")[..8]
+const WHIRLPOOL_ACCOUNT: [u8; 8] = [63, 149, 209, 12, 225, 128, 99, 9];
+
+// https://github.com/orca-so/whirlpools/tree/main/programs/whirlpool/src/instructions
+// 这里监听的是指令
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+pub enum WhirlpoolInstruction {
+    ///   Swap on a single Whirlpool.
+    ///
+    ///   0. `[]` Token program id
+    ///   1. `[signer]` Token authority
+    ///   2. `[writable]` Whirlpool Account
+    ///   3. `[writable]` Token owner account A
+    ///   4. `[writable]` Token vault A
+    ///   5. `[writable]` Token owner account B
+    ///   6. `[writable]` Token vault B
+    ///   7. `[writable]` Tick array 0
+    ///   8. `[writable]` Tick array 1
+    ///   9. `[writable]` Tick array 2
+    ///   10. `[]` Oracle
+    Swap(SwapInstruction),
+
+    ///   Swap on a single Whirlpool, Token-2022 aware.
+    ///
+    ///   0. `[]` Token program A
+    ///   1. `[]` Token program B
+    ///   2. `[]` Memo program
+    ///   3. `[signer]` Token authority
+    ///   4. `[writable]` Whirlpool Account
+    ///   5. `[]` Token mint A
+    ///   6. `[]` Token mint B
+    ///   7. `[writable]` Token owner account A
+    ///   8. `[writable]` Token vault A
+    ///   9. `[writable]` Token owner account B
+    ///   10. `[writable]` Token vault B
+    ///   11. `[writable]` Tick array 0
+    ///   12. `[writable]` Tick array 1
+    ///   13. `[writable]` Tick array 2
+    ///   14. `[writable]` Oracle
+    SwapV2(SwapV2Instruction),
+
+    ///   Swap through two Whirlpools sharing an intermediate token.
+    ///
+    ///   0. `[]` Token program id
+    ///   1. `[signer]` Token authority
+    ///   2. `[writable]` Whirlpool one Account
+    ///   3. `[writable]` Whirlpool two Account
+    ///   4. `[writable]` Token owner account one A
+    ///   5. `[writable]` Token vault one A
+    ///   6. `[writable]` Token owner account one B
+    ///   7. `[writable]` Token vault one B
+    ///   8. `[writable]` Token owner account two A
+    ///   9. `[writable]` Token vault two A
+    ///   10. `[writable]` Token owner account two B
+    ///   11. `[writable]` Token vault two B
+    ///   12. `[writable]` Tick array one 0
+    ///   13. `[writable]` Tick array one 1
+    ///   14. `[writable]` Tick array one 2
+    ///   15. `[writable]` Tick array two 0
+    ///   16. `[writable]` Tick array two 1
+    ///   17. `[writable]` Tick array two 2
+    ///   18. `[]` Oracle one
+    ///   19. `[]` Oracle two
+    TwoHopSwap(TwoHopSwapInstruction),
+
+    ///   Swap through two Whirlpools, Token-2022 aware.
+    ///
+    ///   0. `[writable]` Whirlpool one Account
+    ///   1. `[writable]` Whirlpool two Account
+    ///   2. `[]` Token mint input
+    ///   3. `[]` Token mint intermediate
+    ///   4. `[]` Token mint output
+    ///   5. `[]` Token program input
+    ///   6. `[]` Token program intermediate
+    ///   7. `[]` Token program output
+    ///   8. `[writable]` Token owner account input
+    ///   9. `[writable]` Token vault one input
+    ///   10. `[writable]` Token vault one intermediate
+    ///   11. `[writable]` Token vault two intermediate
+    ///   12. `[writable]` Token vault two output
+    ///   13. `[writable]` Token owner account output
+    ///   14. `[signer]` Token authority
+    ///   15. `[writable]` Tick array one 0
+    ///   16. `[writable]` Tick array one 1
+    ///   17. `[writable]` Tick array one 2
+    ///   18. `[writable]` Tick array two 0
+    ///   19. `[writable]` Tick array two 1
+    ///   20. `[writable]` Tick array two 2
+    ///   21. `[writable]` Oracle one
+    ///   22. `[writable]` Oracle two
+    ///   23. `[]` Memo program
+    TwoHopSwapV2(TwoHopSwapV2Instruction),
+
+    ///   Add liquidity to a position.
+    ///
+    ///   0. `[writable]` Whirlpool Account
+    ///   1. `[]` Token program id
+    ///   2. `[signer]` Position authority
+    ///   3. `[writable]` Position Account
+    ///   4. `[]` Position token account
+    ///   5. `[writable]` Token owner account A
+    ///   6. `[writable]` Token owner account B
+    ///   7. `[writable]` Token vault A
+    ///   8. `[writable]` Token vault B
+    ///   9. `[writable]` Tick array lower
+    ///   10. `[writable]` Tick array upper
+    IncreaseLiquidity(IncreaseLiquidityInstruction),
+
+    ///   Add liquidity to a position, Token-2022 aware.
+    IncreaseLiquidityV2(IncreaseLiquidityV2Instruction),
+
+    ///   Remove liquidity from a position. Same accounts as `IncreaseLiquidity`.
+    DecreaseLiquidity(DecreaseLiquidityInstruction),
+
+    ///   Remove liquidity from a position, Token-2022 aware.
+    DecreaseLiquidityV2(DecreaseLiquidityV2Instruction),
+}
+
+impl TryFrom<UiInstruction> for WhirlpoolInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ix: UiInstruction) -> Result<WhirlpoolInstruction> {
+        match ix {
+            UiInstruction::Compiled(ui_ix) => WhirlpoolInstruction::try_from(&ui_ix),
+            _ => Err(anyhow!("failed to convert to target WhirlpoolInstruction")),
+        }
+    }
+}
+
+impl TryFrom<&UiCompiledInstruction> for WhirlpoolInstruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ui_ix: &UiCompiledInstruction) -> Result<WhirlpoolInstruction> {
+        let data = bs58::decode(&ui_ix.data).into_vec()?;
+        if data.len() < 8 {
+            return Err(anyhow!("failed to convert to target WhirlpoolInstruction"));
+        }
+
+        let (discriminator, args) = data.split_at(8);
+        let ix = match discriminator {
+            d if d == SWAP_IX => Self::Swap(SwapInstruction::try_from_slice(args)?),
+            d if d == SWAP_V2_IX => Self::SwapV2(SwapV2Instruction::try_from_slice(args)?),
+            d if d == TWO_HOP_SWAP_IX => {
+                Self::TwoHopSwap(TwoHopSwapInstruction::try_from_slice(args)?)
+            }
+            d if d == TWO_HOP_SWAP_V2_IX => {
+                Self::TwoHopSwapV2(TwoHopSwapV2Instruction::try_from_slice(args)?)
+            }
+            d if d == INCREASE_LIQUIDITY_IX => {
+                Self::IncreaseLiquidity(IncreaseLiquidityInstruction::try_from_slice(args)?)
+            }
+            d if d == INCREASE_LIQUIDITY_V2_IX => {
+                Self::IncreaseLiquidityV2(IncreaseLiquidityV2Instruction::try_from_slice(args)?)
+            }
+            d if d == DECREASE_LIQUIDITY_IX => {
+                Self::DecreaseLiquidity(DecreaseLiquidityInstruction::try_from_slice(args)?)
+            }
+            d if d == DECREASE_LIQUIDITY_V2_IX => {
+                Self::DecreaseLiquidityV2(DecreaseLiquidityV2Instruction::try_from_slice(args)?)
+            }
+            _ => return Err(anyhow!("failed to convert to target WhirlpoolInstruction")),
+        };
+        Ok(ix)
+    }
+}
+
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/instructions/swap.rs
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct SwapInstruction {
+    /// Input amount when `amount_specified_is_input`, otherwise output amount
+    pub amount: u64,
+    /// Minimum output (exact in) or maximum input (exact out), prevents excessive slippage
+    pub other_amount_threshold: u64,
+    /// Q64.64 sqrt price the swap must not cross
+    pub sqrt_price_limit: u128,
+    pub amount_specified_is_input: bool,
+    /// Direction: token A -> token B when true
+    pub a_to_b: bool,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Eq)]
+pub enum AccountsType {
+    TransferHookA,
+    TransferHookB,
+    TransferHookReward,
+    TransferHookInput,
+    TransferHookIntermediate,
+    TransferHookOutput,
+    SupplementalTickArrays,
+    SupplementalTickArraysOne,
+    SupplementalTickArraysTwo,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct RemainingAccountsSlice {
+    pub accounts_type: AccountsType,
+    pub length: u8,
+}
+
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/util/remaining_accounts_utils.rs
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct RemainingAccountsInfo {
+    pub slices: Vec<RemainingAccountsSlice>,
+}
+
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/instructions/v2/swap.rs
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct SwapV2Instruction {
+    pub amount: u64,
+    pub other_amount_threshold: u64,
+    pub sqrt_price_limit: u128,
+    pub amount_specified_is_input: bool,
+    pub a_to_b: bool,
+    pub remaining_accounts_info: Option<RemainingAccountsInfo>,
+}
+
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/instructions/two_hop_swap.rs
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct TwoHopSwapInstruction {
+    pub amount: u64,
+    pub other_amount_threshold: u64,
+    pub amount_specified_is_input: bool,
+    pub a_to_b_one: bool,
+    pub a_to_b_two: bool,
+    pub sqrt_price_limit_one: u128,
+    pub sqrt_price_limit_two: u128,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct TwoHopSwapV2Instruction {
+    pub amount: u64,
+    pub other_amount_threshold: u64,
+    pub amount_specified_is_input: bool,
+    pub a_to_b_one: bool,
+    pub a_to_b_two: bool,
+    pub sqrt_price_limit_one: u128,
+    pub sqrt_price_limit_two: u128,
+    pub remaining_accounts_info: Option<RemainingAccountsInfo>,
+}
+
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/instructions/increase_liquidity.rs
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct IncreaseLiquidityInstruction {
+    pub liquidity_amount: u128,
+    /// Maximum token A deposited, prevents excessive slippage
+    pub token_max_a: u64,
+    /// Maximum token B deposited, prevents excessive slippage
+    pub token_max_b: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct IncreaseLiquidityV2Instruction {
+    pub liquidity_amount: u128,
+    pub token_max_a: u64,
+    pub token_max_b: u64,
+    pub remaining_accounts_info: Option<RemainingAccountsInfo>,
+}
+
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/instructions/decrease_liquidity.rs
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct DecreaseLiquidityInstruction {
+    pub liquidity_amount: u128,
+    /// Minimum token A withdrawn, prevents excessive slippage
+    pub token_min_a: u64,
+    /// Minimum token B withdrawn, prevents excessive slippage
+    pub token_min_b: u64,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct DecreaseLiquidityV2Instruction {
+    pub liquidity_amount: u128,
+    pub token_min_a: u64,
+    pub token_min_b: u64,
+    pub remaining_accounts_info: Option<RemainingAccountsInfo>,
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct WhirlpoolRewardInfo {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub emissions_per_second_x64: u128,
+    pub growth_global_x64: u128,
+}
+
+// https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/state/whirlpool.rs
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct Whirlpool {
+    pub whirlpools_config: Pubkey,
+    pub whirlpool_bump: [u8; 1],
+    pub tick_spacing: u16,
+    pub fee_tier_index_seed: [u8; 2],
+    /// Fee rate in hundredths of a basis point, e.g. 3000 = 0.3%
+    pub fee_rate: u16,
+    pub protocol_fee_rate: u16,
+    /// Liquidity currently in range
+    pub liquidity: u128,
+    /// Q64.64 square root of the price of token A in token B
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub protocol_fee_owed_a: u64,
+    pub protocol_fee_owed_b: u64,
+    pub token_mint_a: Pubkey,
+    pub token_vault_a: Pubkey,
+    pub fee_growth_global_a: u128,
+    pub token_mint_b: Pubkey,
+    pub token_vault_b: Pubkey,
+    pub fee_growth_global_b: u128,
+    pub reward_last_updated_timestamp: u64,
+    pub reward_infos: [WhirlpoolRewardInfo; 3],
+}
+
+impl Whirlpool {
+    /// Decodes the raw `Whirlpool` account data, including the 8 byte account discriminator.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 || data[..8] != WHIRLPOOL_ACCOUNT {
+            return Err(anyhow!("failed to convert to target Whirlpool account"));
+        }
+        Ok(Whirlpool::deserialize(&mut &data[8..])?)
+    }
+
+    /// Price of token A denominated in token B, adjusted for mint decimals.
+    pub fn price(&self, decimals_a: u8, decimals_b: u8) -> f64 {
+        let sqrt_price = self.sqrt_price as f64 / (1u128 << 64) as f64;
+        sqrt_price * sqrt_price * 10f64.powi(decimals_a as i32 - decimals_b as i32)
+    }
+
+    /// Fee rate as a fraction, e.g. 0.003 for a 0.3% pool.
+    pub fn fee(&self) -> f64 {
+        self.fee_rate as f64 / 1_000_000.0
+    }
+}
+
+/// Emits a `SwapEvent` for every `Swap` / `SwapV2`, and one per hop for
+/// `TwoHopSwap` / `TwoHopSwapV2`.
+pub struct WhirlpoolDecoder;
+
+impl DexDecoder for WhirlpoolDecoder {
+    fn program_id(&self) -> Pubkey {
+        constants::ORCA_WHIRLPOOL_PROGRAM
+    }
+
+    fn venue(&self) -> Venue {
+        Venue::OrcaWhirlpool
+    }
+
+    fn decode_instruction(
+        &self,
+        ctx: &TransactionContext,
+        path: InstructionPath,
+        ix: &UiCompiledInstruction,
+    ) -> Option<SwapEvent> {
+        match WhirlpoolInstruction::try_from(ix).ok()? {
+            WhirlpoolInstruction::Swap(_) => {
+                ctx.swap_from_vaults(self.venue(), path, ix, 2, 1, [4, 6])
+            }
+            WhirlpoolInstruction::SwapV2(_) => {
+                ctx.swap_from_vaults(self.venue(), path, ix, 4, 3, [8, 10])
+            }
+            _ => None,
+        }
+    }
+
+    fn decode_instruction_swaps(
+        &self,
+        ctx: &TransactionContext,
+        path: InstructionPath,
+        ix: &UiCompiledInstruction,
+    ) -> Vec<SwapEvent> {
+        // 每一跳的 (pool, trader, [vault A, vault B])，两个池子的 vault 各自独立变化
+        let hops = match WhirlpoolInstruction::try_from(ix) {
+            Ok(WhirlpoolInstruction::TwoHopSwap(_)) => [(2, 1, [5, 7]), (3, 1, [9, 11])],
+            Ok(WhirlpoolInstruction::TwoHopSwapV2(_)) => [(0, 14, [9, 10]), (1, 14, [11, 12])],
+            _ => return self.decode_instruction(ctx, path, ix).into_iter().collect(),
+        };
+        hops.into_iter()
+            .filter_map(|(pool, trader, vaults)| {
+                ctx.swap_from_vaults(self.venue(), path, ix, pool, trader, vaults)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::anchor_instruction;
+    use crate::utils::calculate_discriminator;
+
+    #[test]
+    fn test_discriminators() {
+        assert_eq!(SWAP_IX, calculate_discriminator("swap"));
+        assert_eq!(SWAP_V2_IX, calculate_discriminator("swap_v2"));
+        assert_eq!(TWO_HOP_SWAP_IX, calculate_discriminator("two_hop_swap"));
+        assert_eq!(
+            TWO_HOP_SWAP_V2_IX,
+            calculate_discriminator("two_hop_swap_v2")
+        );
+        assert_eq!(
+            INCREASE_LIQUIDITY_IX,
+            calculate_discriminator("increase_liquidity")
+        );
+        assert_eq!(
+            INCREASE_LIQUIDITY_V2_IX,
+            calculate_discriminator("increase_liquidity_v2")
+        );
+        assert_eq!(
+            DECREASE_LIQUIDITY_IX,
+            calculate_discriminator("decrease_liquidity")
+        );
+        assert_eq!(
+            DECREASE_LIQUIDITY_V2_IX,
+            calculate_discriminator("decrease_liquidity_v2")
+        );
+    }
+
+    #[test]
+    fn test_swap_v2() {
+        let ui_ix = anchor_instruction(
+            SWAP_V2_IX,
+            SwapV2Instruction {
+                amount: 1_000_000,
+                other_amount_threshold: 990,
+                sqrt_price_limit: 4295048016,
+                amount_specified_is_input: true,
+                a_to_b: true,
+                remaining_accounts_info: Some(RemainingAccountsInfo {
+                    slices: vec![RemainingAccountsSlice {
+                        accounts_type: AccountsType::SupplementalTickArrays,
+                        length: 2,
+                    }],
+                }),
+            },
+        );
+
+        match WhirlpoolInstruction::try_from(UiInstruction::Compiled(ui_ix)).unwrap() {
+            WhirlpoolInstruction::SwapV2(swap) => {
+                assert_eq!(swap.amount, 1_000_000);
+                assert_eq!(swap.other_amount_threshold, 990);
+                assert!(swap.a_to_b);
+                assert_eq!(swap.remaining_accounts_info.unwrap().slices[0].length, 2);
+            }
+            x => panic!("unexpected instruction {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_whirlpool_account() {
+        let pool = Whirlpool {
+            whirlpools_config: Pubkey::new_unique(),
+            whirlpool_bump: [255],
+            tick_spacing: 64,
+            fee_tier_index_seed: [64, 0],
+            fee_rate: 3000,
+            protocol_fee_rate: 1300,
+            liquidity: 1_000_000,
+            // sqrt price 0.25 => raw price 0.0625, scaled by 10^(9 - 6)
+            sqrt_price: 1u128 << 62,
+            tick_current_index: -27728,
+            protocol_fee_owed_a: 0,
+            protocol_fee_owed_b: 0,
+            token_mint_a: Pubkey::new_unique(),
+            token_vault_a: Pubkey::new_unique(),
+            fee_growth_global_a: 0,
+            token_mint_b: Pubkey::new_unique(),
+            token_vault_b: Pubkey::new_unique(),
+            fee_growth_global_b: 0,
+            reward_last_updated_timestamp: 0,
+            reward_infos: [WhirlpoolRewardInfo {
+                mint: Pubkey::default(),
+                vault: Pubkey::default(),
+                authority: Pubkey::default(),
+                emissions_per_second_x64: 0,
+                growth_global_x64: 0,
+            }; 3],
+        };
+        let mut data = WHIRLPOOL_ACCOUNT.to_vec();
+        data.extend(borsh::to_vec(&pool).unwrap());
+        assert_eq!(data.len(), 653);
+
+        let decoded = Whirlpool::try_from_account_data(&data).unwrap();
+        assert_eq!(decoded.token_mint_a, pool.token_mint_a);
+        assert_eq!(decoded.tick_current_index, -27728);
+        assert!((decoded.price(9, 6) - 62.5).abs() < 1e-9);
+        assert!((decoded.fee() - 0.003).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_two_hop_swap() {
+        use std::collections::HashMap;
+
+        use solana_sdk::signature::Signature;
+
+        use crate::decoder::{TokenBalance, compiled_instruction};
+
+        let mut account_keys: Vec<Pubkey> = (0..20).map(|_| Pubkey::new_unique()).collect();
+        account_keys.push(constants::ORCA_WHIRLPOOL_PROGRAM);
+        let ix = compiled_instruction(
+            20,
+            (0..20).collect(),
+            [
+                TWO_HOP_SWAP_IX.to_vec(),
+                borsh::to_vec(&TwoHopSwapInstruction {
+                    amount: 100,
+                    other_amount_threshold: 15,
+                    amount_specified_is_input: true,
+                    a_to_b_one: true,
+                    a_to_b_two: false,
+                    sqrt_price_limit_one: 0,
+                    sqrt_price_limit_two: 0,
+                })
+                .unwrap(),
+            ]
+            .concat(),
+        );
+
+        // 第一跳 A -> B，第二跳 B -> C，中间 token B 流经两个池子
+        let (mint_a, mint_b, mint_c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let balance = |mint, amount| TokenBalance {
+            mint,
+            owner: None,
+            amount,
+        };
+        let pre = HashMap::from([
+            (5, balance(mint_a, 1_000)),
+            (7, balance(mint_b, 1_000)),
+            (9, balance(mint_c, 1_000)),
+            (11, balance(mint_b, 1_000)),
+        ]);
+        let post = HashMap::from([
+            (5, balance(mint_a, 1_100)),
+            (7, balance(mint_b, 950)),
+            (9, balance(mint_c, 980)),
+            (11, balance(mint_b, 1_050)),
+        ]);
+        let ctx = TransactionContext {
+            slot: 1,
+            signature: Signature::default(),
+            writable: vec![true; account_keys.len()],
+            account_keys: account_keys.clone(),
+            instructions: vec![ix],
+            inner_instructions: Some(vec![vec![]]),
+            pre_token_balances: pre,
+            post_token_balances: post,
+            log_messages: vec![],
+            compute_units_consumed: None,
+            error: None,
+        };
+
+        let decoders: Vec<Box<dyn DexDecoder>> = vec![Box::new(WhirlpoolDecoder)];
+        let swaps = ctx.decode_swaps(&decoders);
+        assert_eq!(swaps.len(), 2);
+        assert_eq!(swaps[0].pool, account_keys[2]);
+        assert_eq!((swaps[0].mint_in, swaps[0].mint_out), (mint_a, mint_b));
+        assert_eq!((swaps[0].amount_in, swaps[0].amount_out), (100, 50));
+        assert_eq!(swaps[1].pool, account_keys[3]);
+        assert_eq!((swaps[1].mint_in, swaps[1].mint_out), (mint_b, mint_c));
+        assert_eq!((swaps[1].amount_in, swaps[1].amount_out), (50, 20));
+        assert_eq!(swaps[1].trader, account_keys[1]);
+    }
+}
@@ -0,0 +1,654 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use serde_json::{Map, Number, Value, json};
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::UiCompiledInstruction;
+
+use crate::decoder::{InstructionPath, TransactionContext};
+use crate::utils::calculate_namespaced_discriminator;
+
+// emit_cpi! 事件: EVENT_IX_TAG(8 字节) + 事件 discriminator + 数据
+const EVENT_IX_TAG: [u8; 8] = [228, 69, 165, 46, 81, 203, 154, 29];
+
+// IDL 格式: https://github.com/coral-xyz/anchor/blob/master/idl/spec/src/lib.rs
+// 同时兼容 0.30 之前的旧格式（没有 discriminator，类型内联在 accounts / events 中）
+#[derive(Debug, Clone, Deserialize)]
+pub struct Idl {
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub metadata: Option<IdlMetadata>,
+    pub instructions: Vec<IdlInstruction>,
+    #[serde(default)]
+    pub accounts: Vec<IdlAccount>,
+    #[serde(default)]
+    pub events: Vec<IdlEvent>,
+    #[serde(default)]
+    pub types: Vec<IdlTypeDef>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlMetadata {
+    #[serde(default)]
+    pub address: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlInstruction {
+    pub name: String,
+    #[serde(default)]
+    pub discriminator: Option<Vec<u8>>,
+    #[serde(default)]
+    pub args: Vec<IdlField>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlAccount {
+    pub name: String,
+    #[serde(default)]
+    pub discriminator: Option<Vec<u8>>,
+    /// Inline layout of legacy IDLs, newer ones reference `types`
+    #[serde(default, rename = "type")]
+    pub ty: Option<IdlTypeDefTy>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlEvent {
+    pub name: String,
+    #[serde(default)]
+    pub discriminator: Option<Vec<u8>>,
+    /// Inline fields of legacy IDLs, newer ones reference `types`
+    #[serde(default)]
+    pub fields: Option<Vec<IdlField>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: IdlType,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlTypeDef {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: IdlTypeDefTy,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum IdlTypeDefTy {
+    Struct {
+        #[serde(default)]
+        fields: Option<IdlFields>,
+    },
+    Enum {
+        variants: Vec<IdlEnumVariant>,
+    },
+    Type {
+        alias: IdlType,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlEnumVariant {
+    pub name: String,
+    #[serde(default)]
+    pub fields: Option<IdlFields>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum IdlFields {
+    Named(Vec<IdlField>),
+    Tuple(Vec<IdlType>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum IdlType {
+    Primitive(String),
+    Defined { defined: IdlDefined },
+    Option { option: Box<IdlType> },
+    COption { coption: Box<IdlType> },
+    Vec { vec: Box<IdlType> },
+    Array { array: (Box<IdlType>, usize) },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum IdlDefined {
+    Name(String),
+    Named { name: String },
+}
+
+impl IdlDefined {
+    fn name(&self) -> &str {
+        match self {
+            Self::Name(name) | Self::Named { name } => name,
+        }
+    }
+}
+
+/// An instruction, event or account decoded through an IDL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoded {
+    pub name: String,
+    pub value: Value,
+}
+
+impl Idl {
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let json = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| anyhow!("failed to read IDL {:?}: {}", path.as_ref(), e))?;
+        Self::from_json(&json)
+    }
+
+    /// Program id from `address`, or `metadata.address` for legacy IDLs.
+    pub fn program_id(&self) -> Option<Pubkey> {
+        let address = self
+            .address
+            .as_ref()
+            .or_else(|| self.metadata.as_ref()?.address.as_ref())?;
+        Pubkey::from_str(address).ok()
+    }
+
+    /// Decodes instruction data (discriminator included).
+    pub fn decode_instruction(&self, data: &[u8]) -> Result<Decoded> {
+        let (discriminator, mut args) = split_discriminator(data)?;
+        let ix = self
+            .instructions
+            .iter()
+            .find(|ix| instruction_discriminator(ix) == discriminator)
+            .ok_or_else(|| anyhow!("unknown instruction discriminator {:?}", discriminator))?;
+
+        let value = self.decode_named_fields(&ix.args, &mut args)?;
+        Ok(Decoded {
+            name: ix.name.clone(),
+            value,
+        })
+    }
+
+    /// Decodes event data (discriminator included), as found in `emit!` logs.
+    pub fn decode_event(&self, data: &[u8]) -> Result<Decoded> {
+        let (discriminator, mut rest) = split_discriminator(data)?;
+        let event = self
+            .events
+            .iter()
+            .find(|event| event_discriminator(event) == discriminator)
+            .ok_or_else(|| anyhow!("unknown event discriminator {:?}", discriminator))?;
+
+        let value = match &event.fields {
+            Some(fields) => self.decode_named_fields(fields, &mut rest)?,
+            None => self.decode_defined(&event.name, &mut rest)?,
+        };
+        Ok(Decoded {
+            name: event.name.clone(),
+            value,
+        })
+    }
+
+    /// Decodes account data (discriminator included). Trailing bytes are ignored,
+    /// accounts are often allocated larger than their layout.
+    pub fn decode_account(&self, data: &[u8]) -> Result<Decoded> {
+        let (discriminator, mut rest) = split_discriminator(data)?;
+        let account = self
+            .accounts
+            .iter()
+            .find(|account| account_discriminator(account) == discriminator)
+            .ok_or_else(|| anyhow!("unknown account discriminator {:?}", discriminator))?;
+
+        let value = match &account.ty {
+            Some(ty) => self.decode_type_def(ty, &mut rest)?,
+            None => self.decode_defined(&account.name, &mut rest)?,
+        };
+        Ok(Decoded {
+            name: account.name.clone(),
+            value,
+        })
+    }
+
+    /// Decodes a compiled instruction, either a regular instruction or an `emit_cpi!` event.
+    pub fn decode_compiled_instruction(&self, ui_ix: &UiCompiledInstruction) -> Result<Decoded> {
+        let data = bs58::decode(&ui_ix.data).into_vec()?;
+        if data.len() >= 16 && data[..8] == EVENT_IX_TAG {
+            return self.decode_event(&data[8..]);
+        }
+        self.decode_instruction(&data)
+    }
+
+    fn decode_named_fields(&self, fields: &[IdlField], data: &mut &[u8]) -> Result<Value> {
+        let mut object = Map::new();
+        for field in fields {
+            object.insert(field.name.clone(), self.decode_type(&field.ty, data)?);
+        }
+        Ok(Value::Object(object))
+    }
+
+    fn decode_fields(&self, fields: &Option<IdlFields>, data: &mut &[u8]) -> Result<Value> {
+        match fields {
+            None => Ok(Value::Null),
+            Some(IdlFields::Named(fields)) => self.decode_named_fields(fields, data),
+            Some(IdlFields::Tuple(types)) => types
+                .iter()
+                .map(|ty| self.decode_type(ty, data))
+                .collect::<Result<Vec<_>>>()
+                .map(Value::Array),
+        }
+    }
+
+    fn decode_defined(&self, name: &str, data: &mut &[u8]) -> Result<Value> {
+        let def = self
+            .types
+            .iter()
+            .find(|def| def.name == name)
+            .ok_or_else(|| anyhow!("type {} not found in IDL", name))?;
+        self.decode_type_def(&def.ty, data)
+    }
+
+    fn decode_type_def(&self, ty: &IdlTypeDefTy, data: &mut &[u8]) -> Result<Value> {
+        match ty {
+            IdlTypeDefTy::Struct { fields } => self.decode_fields(fields, data),
+            IdlTypeDefTy::Enum { variants } => {
+                let index = take(data, 1)?[0] as usize;
+                let variant = variants
+                    .get(index)
+                    .ok_or_else(|| anyhow!("enum variant {} out of range", index))?;
+                match &variant.fields {
+                    None => Ok(Value::String(variant.name.clone())),
+                    fields => {
+                        Ok(json!({ variant.name.clone(): self.decode_fields(fields, data)? }))
+                    }
+                }
+            }
+            IdlTypeDefTy::Type { alias } => self.decode_type(alias, data),
+        }
+    }
+
+    fn decode_type(&self, ty: &IdlType, data: &mut &[u8]) -> Result<Value> {
+        let value = match ty {
+            IdlType::Primitive(name) => decode_primitive(name, data)?,
+            IdlType::Defined { defined } => self.decode_defined(defined.name(), data)?,
+            IdlType::Option { option } => match take(data, 1)?[0] {
+                0 => Value::Null,
+                _ => self.decode_type(option, data)?,
+            },
+            // COption 使用 4 字节标记
+            IdlType::COption { coption } => match u32::from_le_bytes(array(data)?) {
+                0 => Value::Null,
+                _ => self.decode_type(coption, data)?,
+            },
+            IdlType::Vec { vec } => {
+                let len = u32::from_le_bytes(array(data)?) as usize;
+                (0..len)
+                    .map(|_| self.decode_type(vec, data))
+                    .collect::<Result<Vec<_>>>()
+                    .map(Value::Array)?
+            }
+            IdlType::Array { array: (ty, len) } => (0..*len)
+                .map(|_| self.decode_type(ty, data))
+                .collect::<Result<Vec<_>>>()
+                .map(Value::Array)?,
+        };
+        Ok(value)
+    }
+}
+
+fn decode_primitive(name: &str, data: &mut &[u8]) -> Result<Value> {
+    let value = match name {
+        "bool" => Value::Bool(take(data, 1)?[0] != 0),
+        "u8" => json!(take(data, 1)?[0]),
+        "i8" => json!(take(data, 1)?[0] as i8),
+        "u16" => json!(u16::from_le_bytes(array(data)?)),
+        "i16" => json!(i16::from_le_bytes(array(data)?)),
+        "u32" => json!(u32::from_le_bytes(array(data)?)),
+        "i32" => json!(i32::from_le_bytes(array(data)?)),
+        "u64" => json!(u64::from_le_bytes(array(data)?)),
+        "i64" => json!(i64::from_le_bytes(array(data)?)),
+        // JSON 数字无法无损表示 128 位整数，使用字符串
+        "u128" => Value::String(u128::from_le_bytes(array(data)?).to_string()),
+        "i128" => Value::String(i128::from_le_bytes(array(data)?).to_string()),
+        "f32" => float(f32::from_le_bytes(array(data)?) as f64),
+        "f64" => float(f64::from_le_bytes(array(data)?)),
+        "publicKey" | "pubkey" => Value::String(Pubkey::new_from_array(array(data)?).to_string()),
+        "string" => {
+            let len = u32::from_le_bytes(array(data)?) as usize;
+            Value::String(String::from_utf8(take(data, len)?.to_vec())?)
+        }
+        "bytes" => {
+            let len = u32::from_le_bytes(array(data)?) as usize;
+            json!(take(data, len)?)
+        }
+        x => return Err(anyhow!("unsupported IDL type {}", x)),
+    };
+    Ok(value)
+}
+
+fn float(value: f64) -> Value {
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(anyhow!("unexpected end of data"));
+    }
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Ok(head)
+}
+
+fn array<const N: usize>(data: &mut &[u8]) -> Result<[u8; N]> {
+    Ok(take(data, N)?.try_into()?)
+}
+
+fn split_discriminator(data: &[u8]) -> Result<([u8; 8], &[u8])> {
+    if data.len() < 8 {
+        return Err(anyhow!("data shorter than a discriminator"));
+    }
+    let (discriminator, rest) = data.split_at(8);
+    Ok((discriminator.try_into()?, rest))
+}
+
+fn discriminator_or(explicit: &Option<Vec<u8>>, namespace: &str, name: &str) -> [u8; 8] {
+    explicit
+        .as_deref()
+        .and_then(|d| d.try_into().ok())
+        .unwrap_or_else(|| calculate_namespaced_discriminator(namespace, name))
+}
+
+fn instruction_discriminator(ix: &IdlInstruction) -> [u8; 8] {
+    // 旧格式的指令名为 camelCase，哈希时使用 snake_case
+    discriminator_or(&ix.discriminator, "global", &to_snake_case(&ix.name))
+}
+
+fn account_discriminator(account: &IdlAccount) -> [u8; 8] {
+    discriminator_or(&account.discriminator, "account", &account.name)
+}
+
+fn event_discriminator(event: &IdlEvent) -> [u8; 8] {
+    discriminator_or(&event.discriminator, "event", &event.name)
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// An instruction of a program without a `DexDecoder`, decoded through its IDL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdlDecodedInstruction {
+    pub program_id: Pubkey,
+    pub path: InstructionPath,
+    pub decoded: Decoded,
+}
+
+/// IDLs keyed by program id, so new programs can be added by dropping an IDL file.
+#[derive(Debug, Clone, Default)]
+pub struct IdlRegistry {
+    pub idls: HashMap<Pubkey, Idl>,
+}
+
+impl IdlRegistry {
+    /// Loads every `*.json` IDL in `dir`. IDLs without a program address are skipped.
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let mut registry = IdlRegistry::default();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let idl = Idl::from_file(&path)?;
+                match idl.program_id() {
+                    Some(program_id) => {
+                        registry.idls.insert(program_id, idl);
+                    }
+                    None => log::warn!("IDL {:?} has no program address, skipped", path),
+                }
+            }
+        }
+        Ok(registry)
+    }
+
+    pub fn get(&self, program_id: &Pubkey) -> Option<&Idl> {
+        self.idls.get(program_id)
+    }
+
+    /// Decodes `ui_ix` with the IDL of `program_id`, if one is registered.
+    pub fn decode_compiled_instruction(
+        &self,
+        program_id: &Pubkey,
+        ui_ix: &UiCompiledInstruction,
+    ) -> Option<Result<Decoded>> {
+        self.get(program_id)
+            .map(|idl| idl.decode_compiled_instruction(ui_ix))
+    }
+
+    /// Decodes every instruction of the transaction invoking a registered
+    /// program, except the programs in `known` which have their own decoder.
+    pub fn decode_transaction(
+        &self,
+        ctx: &TransactionContext,
+        known: &[Pubkey],
+    ) -> Vec<IdlDecodedInstruction> {
+        if self.idls.is_empty() {
+            return Vec::new();
+        }
+        ctx.instructions_with_path()
+            .filter_map(|(path, ix)| {
+                let program_id = ctx.program_id(ix)?;
+                if known.contains(&program_id) {
+                    return None;
+                }
+                match self.decode_compiled_instruction(&program_id, ix)? {
+                    Ok(decoded) => Some(IdlDecodedInstruction {
+                        program_id,
+                        path,
+                        decoded,
+                    }),
+                    Err(err) => {
+                        log::debug!("failed to decode {} instruction: {:?}", program_id, err);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshDeserialize;
+
+    use crate::pumpfun::TradeEvent;
+    use crate::utils::calculate_discriminator;
+
+    // pump.fun 旧格式 IDL 的一部分
+    const PUMPFUN_IDL: &str = r#"{
+        "version": "0.1.0",
+        "name": "pump",
+        "instructions": [
+            {
+                "name": "buy",
+                "accounts": [],
+                "args": [
+                    { "name": "amount", "type": "u64" },
+                    { "name": "maxSolCost", "type": "u64" }
+                ]
+            }
+        ],
+        "events": [
+            {
+                "name": "TradeEvent",
+                "fields": [
+                    { "name": "mint", "type": "publicKey", "index": false },
+                    { "name": "solAmount", "type": "u64", "index": false },
+                    { "name": "tokenAmount", "type": "u64", "index": false },
+                    { "name": "isBuy", "type": "bool", "index": false },
+                    { "name": "user", "type": "publicKey", "index": false },
+                    { "name": "timestamp", "type": "i64", "index": false },
+                    { "name": "virtualSolReserves", "type": "u64", "index": false },
+                    { "name": "virtualTokenReserves", "type": "u64", "index": false },
+                    { "name": "realSolReserves", "type": "u64", "index": false },
+                    { "name": "realTokenReserves", "type": "u64", "index": false }
+                ]
+            }
+        ],
+        "metadata": { "address": "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P" }
+    }"#;
+
+    #[test]
+    fn test_legacy_idl() {
+        let idl = Idl::from_json(PUMPFUN_IDL).unwrap();
+        assert_eq!(
+            idl.program_id().unwrap().to_string(),
+            crate::constants::PUMP_FUN_ID
+        );
+
+        let mut data = calculate_discriminator("buy").to_vec();
+        data.extend(1_000u64.to_le_bytes());
+        data.extend(2_000u64.to_le_bytes());
+        let ix = idl.decode_instruction(&data).unwrap();
+        assert_eq!(ix.name, "buy");
+        assert_eq!(ix.value, json!({ "amount": 1_000, "maxSolCost": 2_000 }));
+
+        // 与 pumpfun.rs 中手写结构的解码结果一致
+        let data = "2K7nL28PxCW8ejnyCeuMpbYAmP2pnuyvkxEQgp79nsKJzbKfMq82LAVFjwFY1xYhKmuaA8H3M5xLfFnF85Xbai9s9aaCyDETZgWMQJayFp8t1HM9ihUxb1TCcsXYVsNKDqaGANFoxSEAPLvpAXJVQHTNyAMxFcgM9s3knpLcDTYtGe7Ufq3WZ9kvAGdd";
+        let ui_ix = UiCompiledInstruction {
+            program_id_index: 0,
+            accounts: vec![],
+            data: data.to_string(),
+            stack_height: None,
+        };
+        let event = idl.decode_compiled_instruction(&ui_ix).unwrap();
+        let raw = bs58::decode(data).into_vec().unwrap();
+        let trade = TradeEvent::try_from_slice(&raw[16..]).unwrap();
+        assert_eq!(event.name, "TradeEvent");
+        assert_eq!(event.value["mint"], trade.mint.to_string());
+        assert_eq!(event.value["solAmount"], trade.sol_amount);
+        assert_eq!(event.value["isBuy"], trade.is_buy);
+        assert_eq!(event.value["realTokenReserves"], trade.real_token_reserves);
+    }
+
+    #[test]
+    fn test_registry_decode_transaction() {
+        let idl = Idl::from_json(PUMPFUN_IDL).unwrap();
+        let pumpfun = idl.program_id().unwrap();
+        let mut registry = IdlRegistry::default();
+        registry.idls.insert(pumpfun, idl);
+
+        let mut data = calculate_discriminator("buy").to_vec();
+        data.extend(1_000u64.to_le_bytes());
+        data.extend(2_000u64.to_le_bytes());
+        let ui_ix = |program_id_index, data: &[u8]| UiCompiledInstruction {
+            program_id_index,
+            accounts: vec![],
+            data: bs58::encode(data).into_string(),
+            stack_height: None,
+        };
+        let ctx = TransactionContext {
+            slot: 1,
+            signature: Default::default(),
+            account_keys: vec![pumpfun, Pubkey::new_unique()],
+            writable: vec![],
+            instructions: vec![ui_ix(0, &data), ui_ix(1, &data), ui_ix(0, &[0; 8])],
+            inner_instructions: None,
+            pre_token_balances: HashMap::new(),
+            post_token_balances: HashMap::new(),
+            log_messages: vec![],
+            compute_units_consumed: None,
+            error: None,
+        };
+
+        // 未注册的程序和无法解码的指令被跳过
+        let decoded = registry.decode_transaction(&ctx, &[]);
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].program_id, pumpfun);
+        assert_eq!(decoded[0].path.outer, 0);
+        assert_eq!(decoded[0].decoded.name, "buy");
+
+        // 已有 DexDecoder 的程序不再用 IDL 解码
+        assert!(registry.decode_transaction(&ctx, &[pumpfun]).is_empty());
+    }
+
+    #[test]
+    fn test_idl_types() {
+        let idl = Idl::from_json(
+            r#"{
+                "address": "11111111111111111111111111111111",
+                "instructions": [
+                    {
+                        "name": "place",
+                        "discriminator": [1, 2, 3, 4, 5, 6, 7, 8],
+                        "accounts": [],
+                        "args": [
+                            { "name": "side", "type": { "defined": { "name": "Side" } } },
+                            { "name": "limit", "type": { "option": "u128" } },
+                            { "name": "tags", "type": { "vec": "string" } }
+                        ]
+                    }
+                ],
+                "accounts": [{ "name": "Book", "discriminator": [9, 9, 9, 9, 9, 9, 9, 9] }],
+                "types": [
+                    {
+                        "name": "Side",
+                        "type": { "kind": "enum", "variants": [{ "name": "Bid" }, { "name": "Ask" }] }
+                    },
+                    {
+                        "name": "Book",
+                        "type": {
+                            "kind": "struct",
+                            "fields": [
+                                { "name": "levels", "type": { "array": ["u16", 2] } },
+                                { "name": "owner", "type": "pubkey" }
+                            ]
+                        }
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let mut data = vec![1, 2, 3, 4, 5, 6, 7, 8, 1, 1];
+        data.extend(7u128.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data.extend(3u32.to_le_bytes());
+        data.extend(b"abc");
+        let ix = idl.decode_instruction(&data).unwrap();
+        assert_eq!(
+            ix.value,
+            json!({ "side": "Ask", "limit": "7", "tags": ["abc"] })
+        );
+        assert!(idl.decode_instruction(&data[..20]).is_err());
+
+        let owner = Pubkey::new_unique();
+        let mut data = vec![9; 8];
+        data.extend(3u16.to_le_bytes());
+        data.extend(4u16.to_le_bytes());
+        data.extend(owner.to_bytes());
+        data.extend([0; 16]);
+        let account = idl.decode_account(&data).unwrap();
+        assert_eq!(account.name, "Book");
+        assert_eq!(
+            account.value,
+            json!({ "levels": [3, 4], "owner": owner.to_string() })
+        );
+    }
+}
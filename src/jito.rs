@@ -269,7 +269,8 @@ mod tests {
         let recent_blockhash = solana_rpc.get_latest_blockhash().unwrap();
 
         // println!("{:?}", recent_blockhash);
-        jito_request(recent_blockhash).await.unwrap();
+        let sender = crate::utils::read_keypair_file(None).unwrap();
+        jito_request(recent_blockhash, &sender).await.unwrap();
         //
     }
 }
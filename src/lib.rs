@@ -1,9 +1,29 @@
+pub mod commitment;
 pub mod common;
 pub mod constants;
 pub mod engine;
+pub mod events;
 pub mod ex;
+pub mod idl;
 pub mod jito;
+pub mod pipeline;
+pub mod priority_fee;
+pub mod source;
+pub mod state;
+pub mod strategy;
+pub mod tip;
+pub mod transaction;
 pub mod utils;
 
+pub use ex::compute_budget;
+pub use ex::decoder;
+pub use ex::jupiter;
+pub use ex::launchlab;
+pub use ex::meteora;
+pub use ex::openbook;
+pub use ex::phoenix;
 pub use ex::pumpfun;
 pub use ex::raydium;
+pub use ex::spl_token;
+pub use ex::system;
+pub use ex::whirlpool;
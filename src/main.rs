@@ -1,11 +1,93 @@
+use std::io::BufRead;
+use std::str::FromStr;
+use std::sync::Arc;
+
 use dotenv;
-use mybot::engine::Engine;
+use mybot::constants;
+use mybot::engine::{self, Engine};
+use mybot::source::geyser::WatchedAccounts;
+use mybot::source::recording::RecordingReplaySource;
+use mybot::source::replay::ReplaySource;
+use mybot::source::shredstream::ShredstreamSource;
+use mybot::source::websocket::WebsocketSource;
+use solana_sdk::pubkey::Pubkey;
 
 #[tokio::main]
 async fn main() {
     dotenv::dotenv().ok();
     env_logger::init();
 
-    let engine = Engine::new().await;
-    engine.run().await.unwrap();
+    let engine = Arc::new(Engine::new().await.unwrap());
+    spawn_admin_commands(engine.watched.clone());
+    match constants::TRANSACTION_SOURCE.as_str() {
+        "websocket" => {
+            let mentions = engine::watched_accounts()
+                .iter()
+                .map(|account| Pubkey::from_str(account).unwrap())
+                .collect();
+            let source = WebsocketSource::new(
+                constants::RPC_ENDPOINT.clone(),
+                constants::WS_ENDPOINT.clone(),
+                mentions,
+            );
+            engine.run_with_source(source).await.unwrap();
+        }
+        "shredstream" => {
+            let mentions = engine::watched_accounts()
+                .iter()
+                .map(|account| Pubkey::from_str(account).unwrap())
+                .collect();
+            let source = ShredstreamSource::new(constants::SHREDSTREAM_ENDPOINT.clone(), mentions)
+                .with_blockhash_rpc(constants::RPC_ENDPOINT.clone());
+            engine.run_with_source(source).await.unwrap();
+        }
+        "replay" => {
+            let source = ReplaySource::new(constants::REPLAY_FILE.as_str());
+            engine.run_with_source(source).await.unwrap();
+        }
+        "recording" => {
+            let source = RecordingReplaySource::new(
+                constants::REPLAY_FILE.as_str(),
+                *constants::REPLAY_SPEED,
+            );
+            engine.run_with_source(source).await.unwrap();
+        }
+        _ => engine.run().await.unwrap(),
+    }
+}
+
+// 从标准输入读取管理命令，运行中增删关注的钱包、池子和程序：
+// watch <pubkey>... / unwatch <pubkey>...
+fn spawn_admin_commands(watched: WatchedAccounts) {
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let mut args = line.split_whitespace();
+            let command = args.next();
+            let accounts: Result<Vec<_>, _> = args.map(Pubkey::from_str).collect();
+            let accounts = match accounts {
+                Ok(accounts) => accounts,
+                Err(err) => {
+                    println!("invalid pubkey: {}", err);
+                    continue;
+                }
+            };
+            let changed = match command {
+                Some("watch") => watched.add(&accounts),
+                Some("unwatch") => watched.remove(&accounts),
+                Some(other) => {
+                    println!("unknown command: {}", other);
+                    continue;
+                }
+                None => continue,
+            };
+            println!(
+                "watched accounts (changed: {}): {:?}",
+                changed,
+                watched.accounts()
+            );
+        }
+    });
 }
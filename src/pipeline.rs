@@ -0,0 +1,201 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use anyhow::{Result, anyhow};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+/// What a stage does when the next stage's queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueuePolicy {
+    /// Wait for room, slowing down the producing stage
+    Block,
+    /// Drop the new item and count it, the producer never waits
+    DropNewest,
+}
+
+impl QueuePolicy {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "block" => Ok(QueuePolicy::Block),
+            "drop" => Ok(QueuePolicy::DropNewest),
+            _ => Err(anyhow!("unknown queue policy: {}", name)),
+        }
+    }
+}
+
+/// Counters of one queue, shared by its sender and receiver.
+#[derive(Debug)]
+pub struct QueueStats {
+    pub name: &'static str,
+    pub capacity: usize,
+    depth: AtomicUsize,
+    max_depth: AtomicUsize,
+    enqueued: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl QueueStats {
+    /// Items waiting in the queue.
+    pub fn depth(&self) -> usize {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth.load(Ordering::Relaxed)
+    }
+
+    pub fn enqueued(&self) -> u64 {
+        self.enqueued.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Bounded queue between two pipeline stages.
+pub fn queue<T>(
+    name: &'static str,
+    capacity: usize,
+    policy: QueuePolicy,
+) -> (QueueSender<T>, QueueReceiver<T>) {
+    let capacity = capacity.max(1);
+    let (sender, receiver) = mpsc::channel(capacity);
+    let stats = Arc::new(QueueStats {
+        name,
+        capacity,
+        depth: AtomicUsize::new(0),
+        max_depth: AtomicUsize::new(0),
+        enqueued: AtomicU64::new(0),
+        dropped: AtomicU64::new(0),
+    });
+    (
+        QueueSender {
+            sender,
+            policy,
+            stats: stats.clone(),
+        },
+        QueueReceiver { receiver, stats },
+    )
+}
+
+#[derive(Debug)]
+pub struct QueueSender<T> {
+    sender: mpsc::Sender<T>,
+    policy: QueuePolicy,
+    stats: Arc<QueueStats>,
+}
+
+impl<T> Clone for QueueSender<T> {
+    fn clone(&self) -> Self {
+        QueueSender {
+            sender: self.sender.clone(),
+            policy: self.policy,
+            stats: self.stats.clone(),
+        }
+    }
+}
+
+impl<T> QueueSender<T> {
+    /// Queues `item` according to the policy, returning whether it was queued
+    /// rather than dropped. `Err` once the receiving stage is gone.
+    pub async fn push(&self, item: T) -> Result<bool> {
+        // 先计数再发送，避免接收方先于计数减到负数
+        let depth = self.stats.depth.fetch_add(1, Ordering::Relaxed) + 1;
+        let sent = match self.policy {
+            QueuePolicy::Block => self.sender.send(item).await.map_err(|_| true),
+            QueuePolicy::DropNewest => match self.sender.try_send(item) {
+                Ok(()) => Ok(()),
+                Err(TrySendError::Full(_)) => Err(false),
+                Err(TrySendError::Closed(_)) => Err(true),
+            },
+        };
+        match sent {
+            Ok(()) => {
+                self.stats.enqueued.fetch_add(1, Ordering::Relaxed);
+                self.stats.max_depth.fetch_max(depth, Ordering::Relaxed);
+                Ok(true)
+            }
+            Err(closed) => {
+                self.stats.depth.fetch_sub(1, Ordering::Relaxed);
+                if closed {
+                    return Err(anyhow!("{} queue closed", self.stats.name));
+                }
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Whether the receiving stage is gone.
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    pub fn stats(&self) -> Arc<QueueStats> {
+        self.stats.clone()
+    }
+}
+
+#[derive(Debug)]
+pub struct QueueReceiver<T> {
+    receiver: mpsc::Receiver<T>,
+    stats: Arc<QueueStats>,
+}
+
+impl<T> QueueReceiver<T> {
+    /// Next item, `None` once every sender is dropped and the queue is drained.
+    pub async fn recv(&mut self) -> Option<T> {
+        let item = self.receiver.recv().await?;
+        self.stats.depth.fetch_sub(1, Ordering::Relaxed);
+        Some(item)
+    }
+
+    pub fn stats(&self) -> Arc<QueueStats> {
+        self.stats.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_queue_policies() {
+        let (sender, mut receiver) = queue("test", 2, QueuePolicy::DropNewest);
+        for item in 0..5 {
+            assert_eq!(sender.push(item).await.unwrap(), item < 2);
+        }
+        let stats = sender.stats();
+        assert_eq!((stats.enqueued(), stats.dropped()), (2, 3));
+        assert_eq!((stats.depth(), stats.max_depth()), (2, 2));
+
+        assert_eq!(receiver.recv().await, Some(0));
+        assert_eq!(stats.depth(), 1);
+        sender.push(5).await.unwrap();
+        assert_eq!(receiver.recv().await, Some(1));
+        assert_eq!(receiver.recv().await, Some(5));
+        assert_eq!(stats.depth(), 0);
+
+        drop(receiver);
+        assert!(sender.push(6).await.is_err());
+        assert_eq!(stats.dropped(), 3);
+
+        // Block 等待接收方腾出空间，不丢弃
+        let (sender, mut receiver) = queue("test", 1, QueuePolicy::Block);
+        let producer = tokio::spawn(async move {
+            for item in 0..3 {
+                sender.push(item).await.unwrap();
+            }
+            sender.stats()
+        });
+        let mut received = Vec::new();
+        while let Some(item) = receiver.recv().await {
+            received.push(item);
+        }
+        let stats = producer.await.unwrap();
+        assert_eq!(received, vec![0, 1, 2]);
+        assert_eq!((stats.enqueued(), stats.dropped()), (3, 0));
+    }
+}
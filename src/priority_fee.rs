@@ -0,0 +1,186 @@
+use std::collections::{BTreeMap, HashMap};
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::compute_budget;
+use crate::transaction::DecodedTransaction;
+
+// 约 1 分钟的 slot 数
+pub const DEFAULT_WINDOW_SLOTS: u64 = 150;
+
+/// Compute unit prices, in micro-lamports, paid by transactions locking an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeePercentiles {
+    pub p50: u64,
+    pub p75: u64,
+    pub p90: u64,
+    pub p99: u64,
+    /// Number of transactions the percentiles are computed from
+    pub samples: usize,
+}
+
+/// Rolling per-account priority fee estimator over the last `window_slots` slots.
+///
+/// Every observed transaction adds its compute unit price to each account it
+/// write-locks, since those are the accounts it competes for with other transactions.
+/// Transactions without `SetComputeUnitPrice` count as a price of zero.
+#[derive(Debug, Clone)]
+pub struct PriorityFeeEstimator {
+    window_slots: u64,
+    // slot -> 写锁账户 -> 该 slot 内观察到的 unit price
+    slots: BTreeMap<u64, HashMap<Pubkey, Vec<u64>>>,
+}
+
+impl Default for PriorityFeeEstimator {
+    fn default() -> Self {
+        PriorityFeeEstimator::new(DEFAULT_WINDOW_SLOTS)
+    }
+}
+
+impl PriorityFeeEstimator {
+    pub fn new(window_slots: u64) -> Self {
+        PriorityFeeEstimator {
+            window_slots: window_slots.max(1),
+            slots: BTreeMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, tx: &DecodedTransaction) {
+        let unit_price = tx.compute_budget.unit_price.unwrap_or_default();
+        let accounts = self.slots.entry(tx.slot).or_default();
+        for account in &tx.writable_accounts {
+            accounts.entry(*account).or_default().push(unit_price);
+        }
+        self.prune();
+    }
+
+    // 丢弃窗口之外的 slot，窗口以观察到的最新 slot 为准
+    fn prune(&mut self) {
+        let Some(&latest) = self.slots.keys().next_back() else {
+            return;
+        };
+        let oldest = latest.saturating_sub(self.window_slots - 1);
+        self.slots = self.slots.split_off(&oldest);
+    }
+
+    fn samples(&self, account: &Pubkey) -> Vec<u64> {
+        let mut samples: Vec<u64> = self
+            .slots
+            .values()
+            .filter_map(|accounts| accounts.get(account))
+            .flatten()
+            .copied()
+            .collect();
+        samples.sort_unstable();
+        samples
+    }
+
+    /// Unit price at `percentile` (0-100) for `account`, `None` without observations.
+    pub fn percentile(&self, account: &Pubkey, percentile: u8) -> Option<u64> {
+        nearest_rank(&self.samples(account), percentile)
+    }
+
+    pub fn percentiles(&self, account: &Pubkey) -> Option<FeePercentiles> {
+        let samples = self.samples(account);
+        Some(FeePercentiles {
+            p50: nearest_rank(&samples, 50)?,
+            p75: nearest_rank(&samples, 75)?,
+            p90: nearest_rank(&samples, 90)?,
+            p99: nearest_rank(&samples, 99)?,
+            samples: samples.len(),
+        })
+    }
+
+    /// Unit price in micro-lamports that beats `percentile` on every account in
+    /// `accounts`, the write locks of the transaction being built. Zero if none
+    /// of them has been observed.
+    pub fn outbid_unit_price(&self, accounts: &[Pubkey], percentile: u8) -> u64 {
+        accounts
+            .iter()
+            .filter_map(|account| self.percentile(account, percentile))
+            .max()
+            .map(|price| price.saturating_add(1))
+            .unwrap_or_default()
+    }
+
+    /// Priority fee in lamports for `outbid_unit_price` at `unit_limit` compute units.
+    pub fn outbid_priority_fee(&self, accounts: &[Pubkey], percentile: u8, unit_limit: u32) -> u64 {
+        compute_budget::priority_fee(self.outbid_unit_price(accounts, percentile), unit_limit)
+    }
+}
+
+// nearest-rank 百分位，samples 需已排序
+fn nearest_rank(samples: &[u64], percentile: u8) -> Option<u64> {
+    if samples.is_empty() {
+        return None;
+    }
+    let percentile = percentile.min(100) as usize;
+    let rank = (percentile * samples.len()).div_ceil(100).max(1);
+    Some(samples[rank - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use solana_sdk::signature::Signature;
+
+    use crate::compute_budget::ComputeBudget;
+
+    fn tx(
+        slot: u64,
+        writable_accounts: Vec<Pubkey>,
+        unit_price: Option<u64>,
+    ) -> DecodedTransaction {
+        DecodedTransaction {
+            slot,
+            signature: Signature::default(),
+            signer: writable_accounts.first().copied(),
+            writable_accounts,
+            swaps: vec![],
+            jupiter_routes: vec![],
+            pools_created: vec![],
+            idl_instructions: vec![],
+            token_instructions: vec![],
+            system_instructions: vec![],
+            compute_budget: ComputeBudget {
+                unit_price,
+                ..Default::default()
+            },
+            jito_tip: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_priority_fee_estimator() {
+        let (pool, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut estimator = PriorityFeeEstimator::new(10);
+
+        // 窗口外的高价会被丢弃
+        estimator.record(&tx(1, vec![pool], Some(1_000_000)));
+        for price in 1..=100 {
+            estimator.record(&tx(100 + price % 5, vec![pool], Some(price)));
+        }
+        estimator.record(&tx(104, vec![other], None));
+
+        let percentiles = estimator.percentiles(&pool).unwrap();
+        assert_eq!(percentiles.samples, 100);
+        assert_eq!(
+            (
+                percentiles.p50,
+                percentiles.p75,
+                percentiles.p90,
+                percentiles.p99
+            ),
+            (50, 75, 90, 99)
+        );
+        assert_eq!(estimator.percentile(&other, 50), Some(0));
+        assert_eq!(estimator.percentiles(&Pubkey::new_unique()), None);
+
+        assert_eq!(estimator.outbid_unit_price(&[pool, other], 90), 91);
+        assert_eq!(estimator.outbid_unit_price(&[Pubkey::new_unique()], 90), 0);
+        // 91 * 200_000 / 1_000_000 = 18.2，向上取整
+        assert_eq!(estimator.outbid_priority_fee(&[pool], 90, 200_000), 19);
+    }
+}
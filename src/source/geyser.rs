@@ -0,0 +1,561 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::TransactionWithStatusMeta;
+use tokio::sync::watch;
+use yellowstone_grpc_client::{ClientTlsConfig, GeyserGrpcClient, Interceptor};
+use yellowstone_grpc_proto::convert_from;
+use yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof;
+use yellowstone_grpc_proto::geyser::{
+    CommitmentLevel, SlotStatus as GeyserSlotStatus, SubscribeRequest,
+    SubscribeRequestFilterAccounts, SubscribeRequestFilterSlots,
+    SubscribeRequestFilterTransactions, SubscribeRequestPing, SubscribeUpdateAccount,
+    SubscribeUpdateSlot, SubscribeUpdateTransaction,
+};
+use yellowstone_grpc_proto::tonic::codec::CompressionEncoding;
+
+use crate::commitment::{SlotStatus, SlotUpdate};
+use crate::constants;
+use crate::source::recording::UpdateRecorder;
+use crate::source::{
+    AccountStream, ObservedTransaction, SlotStream, TransactionSource, TransactionStream,
+};
+use crate::state::AccountUpdate;
+
+/// Accounts a `GeyserSource` subscribes to, changeable while the stream runs.
+///
+/// Clones share the same list. Every running subscription following it
+/// re-sends its `SubscribeRequest` on the existing stream when it changes.
+#[derive(Clone)]
+pub struct WatchedAccounts {
+    sender: Arc<watch::Sender<Vec<String>>>,
+}
+
+impl WatchedAccounts {
+    pub fn new(accounts: Vec<String>) -> Self {
+        WatchedAccounts {
+            sender: Arc::new(watch::Sender::new(accounts)),
+        }
+    }
+
+    pub fn accounts(&self) -> Vec<String> {
+        self.sender.borrow().clone()
+    }
+
+    /// Adds wallets, pools or programs, returns whether the list changed.
+    pub fn add(&self, accounts: &[Pubkey]) -> bool {
+        self.sender.send_if_modified(|watched| {
+            let before = watched.len();
+            for account in accounts {
+                let account = account.to_string();
+                if !watched.contains(&account) {
+                    watched.push(account);
+                }
+            }
+            watched.len() != before
+        })
+    }
+
+    /// Removes accounts, returns whether the list changed.
+    pub fn remove(&self, accounts: &[Pubkey]) -> bool {
+        self.sender.send_if_modified(|watched| {
+            let before = watched.len();
+            watched.retain(|account| {
+                !accounts
+                    .iter()
+                    .any(|removed| removed.to_string() == *account)
+            });
+            watched.len() != before
+        })
+    }
+
+    fn subscribe(&self) -> watch::Receiver<Vec<String>> {
+        self.sender.subscribe()
+    }
+}
+
+// 默认 4MB 放不下大的区块和账户推送
+const DEFAULT_MAX_DECODING_MESSAGE_SIZE: usize = 1024 * 1024 * 1024;
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Authentication and transport settings of the gRPC client.
+#[derive(Debug, Clone)]
+pub struct GeyserClientConfig {
+    /// `x-token` header required by most paid providers
+    pub x_token: Option<String>,
+    pub connect_timeout: Duration,
+    /// Timeout of each request, for `subscribe` only until the stream is open
+    pub timeout: Duration,
+    pub max_decoding_message_size: usize,
+    /// HTTP/2 keepalive ping interval, disabled when `None`
+    pub keep_alive_interval: Option<Duration>,
+    pub keep_alive_timeout: Option<Duration>,
+    /// Compression both sent and accepted
+    pub compression: Option<CompressionEncoding>,
+}
+
+impl Default for GeyserClientConfig {
+    fn default() -> Self {
+        GeyserClientConfig {
+            x_token: None,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            timeout: DEFAULT_TIMEOUT,
+            max_decoding_message_size: DEFAULT_MAX_DECODING_MESSAGE_SIZE,
+            keep_alive_interval: None,
+            keep_alive_timeout: None,
+            compression: None,
+        }
+    }
+}
+
+impl GeyserClientConfig {
+    /// Reads the `GRPC_*` settings, falling back to the defaults for unset ones.
+    pub fn from_env() -> Result<Self> {
+        let default = GeyserClientConfig::default();
+        let compression = match constants::GRPC_COMPRESSION.as_deref() {
+            None => None,
+            Some("gzip") => Some(CompressionEncoding::Gzip),
+            Some("zstd") => Some(CompressionEncoding::Zstd),
+            Some(other) => return Err(anyhow!("unsupported grpc compression: {}", other)),
+        };
+        Ok(GeyserClientConfig {
+            x_token: constants::GRPC_X_TOKEN.clone(),
+            connect_timeout: parse_duration(
+                "GRPC_CONNECT_TIMEOUT",
+                constants::GRPC_CONNECT_TIMEOUT.as_deref(),
+            )?
+            .unwrap_or(default.connect_timeout),
+            timeout: parse_duration("GRPC_TIMEOUT", constants::GRPC_TIMEOUT.as_deref())?
+                .unwrap_or(default.timeout),
+            max_decoding_message_size: constants::GRPC_MAX_DECODING_MESSAGE_SIZE
+                .as_deref()
+                .map(|value| {
+                    value.parse().map_err(|err| {
+                        anyhow!(
+                            "invalid GRPC_MAX_DECODING_MESSAGE_SIZE {:?}: {}",
+                            value,
+                            err
+                        )
+                    })
+                })
+                .transpose()?
+                .unwrap_or(default.max_decoding_message_size),
+            keep_alive_interval: parse_duration(
+                "GRPC_KEEPALIVE_INTERVAL",
+                constants::GRPC_KEEPALIVE_INTERVAL.as_deref(),
+            )?,
+            keep_alive_timeout: parse_duration(
+                "GRPC_KEEPALIVE_TIMEOUT",
+                constants::GRPC_KEEPALIVE_TIMEOUT.as_deref(),
+            )?,
+            compression,
+        })
+    }
+}
+
+// humantime 格式，如 10s、500ms；不带单位的 10 视为无效而不是忽略
+fn parse_duration(name: &str, value: Option<&str>) -> Result<Option<Duration>> {
+    value
+        .map(|value| {
+            humantime::parse_duration(value)
+                .map_err(|err| anyhow!("invalid {} {:?}: {}", name, value, err))
+        })
+        .transpose()
+}
+
+/// Yellowstone gRPC transaction subscription, optionally with slot and account updates.
+pub struct GeyserSource {
+    endpoint: String,
+    client_config: GeyserClientConfig,
+    watched: WatchedAccounts,
+    /// Accounts whose own updates are subscribed, see `account_updates`
+    state_accounts: WatchedAccounts,
+    include_failed: bool,
+    commitment: CommitmentLevel,
+    recorder: Option<UpdateRecorder>,
+    slot_sender: Option<mpsc::UnboundedSender<SlotUpdate>>,
+    account_sender: Option<mpsc::UnboundedSender<AccountUpdate>>,
+}
+
+impl GeyserSource {
+    /// Subscribes at `Processed` to transactions touching any of `account_include`.
+    pub fn new(endpoint: String, account_include: Vec<String>) -> Self {
+        GeyserSource {
+            endpoint,
+            client_config: GeyserClientConfig::default(),
+            watched: WatchedAccounts::new(account_include),
+            state_accounts: WatchedAccounts::new(Vec::new()),
+            include_failed: *constants::INCLUDE_FAILED_TRANSACTIONS,
+            commitment: CommitmentLevel::Processed,
+            recorder: None,
+            slot_sender: None,
+            account_sender: None,
+        }
+    }
+
+    pub fn with_client_config(mut self, client_config: GeyserClientConfig) -> Self {
+        self.client_config = client_config;
+        self
+    }
+
+    /// Follows a shared account list instead of the one given to `new`.
+    pub fn with_watched(mut self, watched: WatchedAccounts) -> Self {
+        self.watched = watched;
+        self
+    }
+
+    /// Handle for changing the subscribed accounts while running.
+    pub fn watched(&self) -> WatchedAccounts {
+        self.watched.clone()
+    }
+
+    /// Subscribes to updates of these accounts themselves, delivered by `account_updates`.
+    pub fn with_state_accounts(mut self, state_accounts: WatchedAccounts) -> Self {
+        self.state_accounts = state_accounts;
+        self
+    }
+
+    /// Records every received `SubscribeUpdate` to `path`, see `RecordingReplaySource`.
+    pub fn record_to(mut self, path: impl AsRef<Path>) -> Result<Self> {
+        self.recorder = Some(UpdateRecorder::create(path)?);
+        Ok(self)
+    }
+
+    async fn connect(&self) -> Result<GeyserGrpcClient<impl Interceptor + use<>>> {
+        info!("GRPC_ENDPOINT = {}", self.endpoint);
+        let config = &self.client_config;
+        let mut builder = GeyserGrpcClient::build_from_shared(self.endpoint.clone())?
+            .x_token(config.x_token.clone())?
+            .tls_config(ClientTlsConfig::new().with_native_roots())?
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.timeout)
+            .max_decoding_message_size(config.max_decoding_message_size);
+        if let Some(interval) = config.keep_alive_interval {
+            builder = builder
+                .http2_keep_alive_interval(interval)
+                .keep_alive_while_idle(true);
+        }
+        if let Some(timeout) = config.keep_alive_timeout {
+            builder = builder.keep_alive_timeout(timeout);
+        }
+        if let Some(encoding) = config.compression {
+            builder = builder
+                .send_compressed(encoding)
+                .accept_compressed(encoding);
+        }
+        Ok(builder.connect().await?)
+    }
+}
+
+// 构造 SubscribeRequest 时不随账户变化的部分
+#[derive(Debug, Clone, Copy)]
+struct RequestOptions {
+    include_failed: bool,
+    commitment: CommitmentLevel,
+    slots: bool,
+}
+
+fn subscribe_request(
+    options: RequestOptions,
+    account_include: Vec<String>,
+    state_accounts: Vec<String>,
+) -> SubscribeRequest {
+    info!("account_include = {:?}", account_include);
+    // 空的 account_include 会订阅全部交易，unwatch 掉最后一个账户时不再订阅交易
+    let mut transactions: HashMap<String, SubscribeRequestFilterTransactions> = HashMap::new();
+    if !account_include.is_empty() {
+        transactions.insert(
+            "client".to_string(),
+            SubscribeRequestFilterTransactions {
+                vote: None,
+                // None 表示成功和失败的交易都推送
+                failed: if options.include_failed {
+                    None
+                } else {
+                    Some(false)
+                },
+                signature: None,
+                account_include,
+                account_exclude: Vec::new(),
+                account_required: Vec::new(),
+            },
+        );
+    }
+    let mut slot_filters = HashMap::new();
+    if options.slots {
+        // 所有状态都要推送，不只是订阅的 commitment；Dead 属于 interslot 更新
+        slot_filters.insert(
+            "client".to_string(),
+            SubscribeRequestFilterSlots {
+                filter_by_commitment: Some(false),
+                interslot_updates: Some(true),
+            },
+        );
+    }
+    // 空的账户过滤条件会订阅全部账户
+    let mut account_filters = HashMap::new();
+    if !state_accounts.is_empty() {
+        account_filters.insert(
+            "client".to_string(),
+            SubscribeRequestFilterAccounts {
+                account: state_accounts,
+                ..Default::default()
+            },
+        );
+    }
+    SubscribeRequest {
+        accounts: account_filters,
+        transactions,
+        slots: slot_filters,
+        commitment: Some(options.commitment.into()),
+        ..Default::default()
+    }
+}
+
+// 订阅流的状态，在 unfold 的每次迭代之间传递
+struct Subscription<Tx, Rx> {
+    sink: Tx,
+    stream: Rx,
+    recorder: Option<UpdateRecorder>,
+    // 持有 WatchedAccounts 保证 receiver 不会因为 sender 被释放而失效
+    _watched: [WatchedAccounts; 2],
+    accounts: watch::Receiver<Vec<String>>,
+    state_accounts: watch::Receiver<Vec<String>>,
+    slot_sender: Option<mpsc::UnboundedSender<SlotUpdate>>,
+    account_sender: Option<mpsc::UnboundedSender<AccountUpdate>>,
+}
+
+impl<Tx, Rx> Subscription<Tx, Rx> {
+    fn request(&mut self, options: RequestOptions) -> SubscribeRequest {
+        subscribe_request(
+            options,
+            self.accounts.borrow_and_update().clone(),
+            self.state_accounts.borrow_and_update().clone(),
+        )
+    }
+}
+
+impl TransactionSource for GeyserSource {
+    async fn subscribe(&mut self) -> Result<TransactionStream> {
+        let options = RequestOptions {
+            include_failed: self.include_failed,
+            commitment: self.commitment,
+            slots: self.slot_sender.is_some(),
+        };
+        let mut client = self.connect().await?;
+        let (sink, stream) = client.subscribe().await?;
+
+        // sink 用来响应 ping 以及在关注的账户变化时重新发送 SubscribeRequest，
+        // 直到收到下一笔交易才返回
+        let mut state = Subscription {
+            sink,
+            stream,
+            recorder: self.recorder.clone(),
+            _watched: [self.watched.clone(), self.state_accounts.clone()],
+            accounts: self.watched.subscribe(),
+            state_accounts: self.state_accounts.subscribe(),
+            slot_sender: self.slot_sender.clone(),
+            account_sender: self.account_sender.clone(),
+        };
+        let request = state.request(options);
+        state.sink.send(request).await?;
+        let transactions = futures::stream::unfold(state, move |mut state| async move {
+            loop {
+                let update = tokio::select! {
+                    update = state.stream.next() => Some(update?),
+                    Ok(()) = state.accounts.changed() => None,
+                    Ok(()) = state.state_accounts.changed() => None,
+                };
+                let Some(update) = update else {
+                    // 同一个流上发送新的 SubscribeRequest 会替换原来的过滤条件
+                    let request = state.request(options);
+                    info!("subscription filters changed: {:?}", request);
+                    if let Err(err) = state.sink.send(request).await {
+                        warn!("failed to update subscription: {:?}", err);
+                    }
+                    continue;
+                };
+                let update = match update {
+                    Ok(update) => update,
+                    Err(status) => return Some((Err(status.into()), state)),
+                };
+                if let Some(recorder) = state.recorder.as_ref()
+                    && let Err(err) = recorder.record(&update)
+                {
+                    warn!("failed to record update: {:?}", err);
+                }
+                match update.update_oneof {
+                    Some(UpdateOneof::Transaction(tx)) => {
+                        if let Some(observed) = observed_transaction(tx) {
+                            return Some((Ok(observed), state));
+                        }
+                    }
+                    Some(UpdateOneof::Account(account)) => {
+                        if let (Some(sender), Some(update)) =
+                            (state.account_sender.as_ref(), account_update(account))
+                        {
+                            let _ = sender.unbounded_send(update);
+                        }
+                    }
+                    Some(UpdateOneof::Slot(slot)) => {
+                        if let (Some(sender), Some(update)) =
+                            (state.slot_sender.as_ref(), slot_update(slot))
+                        {
+                            let _ = sender.unbounded_send(update);
+                        }
+                    }
+                    Some(UpdateOneof::BlockMeta(meta)) => {
+                        debug!("BlockMeta: {:?}", meta);
+                    }
+                    Some(UpdateOneof::Ping(v)) => {
+                        debug!("Ping received; {:?}", v);
+                        let _ = state
+                            .sink
+                            .send(SubscribeRequest {
+                                ping: Some(SubscribeRequestPing { id: 1 }),
+                                ..Default::default()
+                            })
+                            .await;
+                    }
+                    o => {
+                        debug!("OTHER: {:?}", o);
+                    }
+                }
+            }
+        });
+        Ok(Box::pin(transactions))
+    }
+
+    async fn latest_blockhash(&mut self) -> Result<Option<Hash>> {
+        let mut client = self.connect().await?;
+        let response = client.get_latest_blockhash(Some(self.commitment)).await?;
+        Ok(Some(Hash::from_str(&response.blockhash)?))
+    }
+
+    fn slot_updates(&mut self) -> Option<SlotStream> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.slot_sender = Some(sender);
+        Some(Box::pin(receiver))
+    }
+
+    fn account_updates(&mut self) -> Option<AccountStream> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.account_sender = Some(sender);
+        Some(Box::pin(receiver))
+    }
+}
+
+pub(crate) fn account_update(account: SubscribeUpdateAccount) -> Option<AccountUpdate> {
+    let info = account.account?;
+    Some(AccountUpdate {
+        pubkey: Pubkey::try_from(info.pubkey.as_slice()).ok()?,
+        owner: Pubkey::try_from(info.owner.as_slice()).ok()?,
+        slot: account.slot,
+        data: info.data,
+    })
+}
+
+// 只保留 CommitmentTracker 关心的状态
+pub(crate) fn slot_update(slot: SubscribeUpdateSlot) -> Option<SlotUpdate> {
+    let status = match GeyserSlotStatus::try_from(slot.status).ok()? {
+        GeyserSlotStatus::SlotProcessed => SlotStatus::Processed,
+        GeyserSlotStatus::SlotConfirmed => SlotStatus::Confirmed,
+        GeyserSlotStatus::SlotFinalized => SlotStatus::Finalized,
+        GeyserSlotStatus::SlotDead => SlotStatus::Dead,
+        _ => return None,
+    };
+    Some(SlotUpdate {
+        slot: slot.slot,
+        parent: slot.parent,
+        status,
+    })
+}
+
+// grpc 推送的交易转换失败时直接跳过
+pub(crate) fn observed_transaction(tx: SubscribeUpdateTransaction) -> Option<ObservedTransaction> {
+    let tx_info = tx.transaction?;
+    let signature = Signature::try_from(tx_info.signature.as_slice()).ok()?;
+    let (transaction, meta) = match convert_from::create_tx_with_meta(tx_info).ok()? {
+        TransactionWithStatusMeta::Complete(tx) => (tx.transaction, Some(tx.meta)),
+        TransactionWithStatusMeta::MissingMetadata(tx) => (VersionedTransaction::from(tx), None),
+    };
+    Some(ObservedTransaction {
+        slot: tx.slot,
+        signature,
+        transaction,
+        meta,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(
+            parse_duration("GRPC_TIMEOUT", Some("500ms")).unwrap(),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(parse_duration("GRPC_TIMEOUT", None).unwrap(), None);
+        let err = parse_duration("GRPC_TIMEOUT", Some("10")).unwrap_err();
+        assert!(err.to_string().contains("GRPC_TIMEOUT"));
+    }
+
+    #[test]
+    fn test_watched_accounts() {
+        let pool = Pubkey::new_unique();
+        let wallet = Pubkey::new_unique();
+        let watched = WatchedAccounts::new(vec![pool.to_string()]);
+        let mut accounts = watched.clone().subscribe();
+
+        assert!(watched.add(&[pool, wallet]));
+        assert!(accounts.has_changed().unwrap());
+        assert_eq!(
+            accounts.borrow_and_update().clone(),
+            vec![pool.to_string(), wallet.to_string()]
+        );
+
+        // 没有变化时不通知订阅方
+        assert!(!watched.add(&[wallet]));
+        assert!(!watched.remove(&[Pubkey::new_unique()]));
+        assert!(!accounts.has_changed().unwrap());
+
+        assert!(watched.remove(&[pool]));
+        assert_eq!(watched.accounts(), vec![wallet.to_string()]);
+    }
+
+    #[test]
+    fn test_subscribe_request_without_accounts() {
+        let options = RequestOptions {
+            include_failed: true,
+            commitment: CommitmentLevel::Processed,
+            slots: true,
+        };
+        let wallet = Pubkey::new_unique().to_string();
+        let request = subscribe_request(options, vec![wallet.clone()], Vec::new());
+        assert_eq!(
+            request.transactions["client"].account_include,
+            vec![wallet.clone()]
+        );
+        assert!(request.accounts.is_empty());
+
+        // 没有关注的账户时不能发送空的交易过滤条件，否则会收到全部交易
+        let request = subscribe_request(options, Vec::new(), vec![wallet]);
+        assert!(request.transactions.is_empty());
+        assert_eq!(request.accounts.len(), 1);
+        assert_eq!(request.slots.len(), 1);
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::time::Instant;
+
+use anyhow::{Result, anyhow};
+use base64::Engine as _;
+use futures::Stream;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::message::v0::LoadedAddresses;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_sdk::transaction_context::TransactionReturnData;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, InnerInstruction, InnerInstructions,
+    TransactionStatusMeta, TransactionTokenBalance, UiInstruction, UiLoadedAddresses,
+    UiTransactionReturnData, UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
+
+use crate::commitment::SlotUpdate;
+use crate::decoder::TransactionContext;
+use crate::state::AccountUpdate;
+
+pub mod geyser;
+pub mod recording;
+pub mod redundant;
+pub mod replay;
+pub mod shredstream;
+pub mod websocket;
+
+/// A transaction as delivered by a `TransactionSource`, independent of the transport.
+#[derive(Debug, Clone)]
+pub struct ObservedTransaction {
+    pub slot: u64,
+    pub signature: Signature,
+    pub transaction: VersionedTransaction,
+    /// `None` when the source only delivers the transaction itself
+    pub meta: Option<TransactionStatusMeta>,
+}
+
+impl ObservedTransaction {
+    pub fn context(&self) -> TransactionContext {
+        TransactionContext::new(self.slot, &self.transaction, self.meta.as_ref())
+    }
+
+    /// Converts a `getTransaction` response, which must use a binary encoding.
+    pub fn from_encoded(encoded: EncodedConfirmedTransactionWithStatusMeta) -> Result<Self> {
+        let transaction = encoded
+            .transaction
+            .transaction
+            .decode()
+            .ok_or_else(|| anyhow!("failed to decode transaction, binary encoding required"))?;
+        let meta = encoded
+            .transaction
+            .meta
+            .map(status_meta_from_ui)
+            .transpose()?;
+
+        Ok(ObservedTransaction {
+            slot: encoded.slot,
+            signature: transaction.signatures.first().copied().unwrap_or_default(),
+            transaction,
+            meta,
+        })
+    }
+}
+
+pub type TransactionStream = Pin<Box<dyn Stream<Item = Result<ObservedTransaction>> + Send>>;
+
+pub type SlotStream = Pin<Box<dyn Stream<Item = SlotUpdate> + Send>>;
+
+pub type AccountStream = Pin<Box<dyn Stream<Item = AccountUpdate> + Send>>;
+
+/// Where `Engine` gets its transactions from.
+pub trait TransactionSource {
+    /// Starts the subscription. The stream ends when the source is exhausted
+    /// and yields an error when the connection fails.
+    fn subscribe(&mut self) -> impl Future<Output = Result<TransactionStream>> + Send;
+
+    /// Blockhash for the transactions we send, `None` for sources that are not
+    /// connected to a live cluster.
+    fn latest_blockhash(&mut self) -> impl Future<Output = Result<Option<Hash>>> + Send;
+
+    /// Slot status updates for following observed transactions to finality,
+    /// `None` for sources that don't have them. Must be called before `subscribe`.
+    fn slot_updates(&mut self) -> Option<SlotStream> {
+        None
+    }
+
+    /// Updates of the accounts whose state is followed, `None` for sources that
+    /// don't have them. Must be called before `subscribe`.
+    fn account_updates(&mut self) -> Option<AccountStream> {
+        None
+    }
+}
+
+/// Bounded set of recently seen signatures with when each was first seen,
+/// evicting the least recently seen once full.
+pub(crate) struct RecentSignatures {
+    capacity: usize,
+    // 签名 -> (首次出现的时间, 最近一次出现的序号)
+    seen: HashMap<Signature, (Instant, u64)>,
+    // 按出现顺序排列，序号与 seen 中不一致的是已经再次出现过的旧记录
+    order: VecDeque<(Signature, u64)>,
+    next: u64,
+}
+
+impl RecentSignatures {
+    pub(crate) fn new(capacity: usize) -> Self {
+        RecentSignatures {
+            capacity: capacity.max(1),
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+            next: 0,
+        }
+    }
+
+    /// Remembers `signature`, returning when it was first seen if it is a duplicate.
+    pub(crate) fn insert(&mut self, signature: Signature) -> Option<Instant> {
+        self.insert_at(signature, Instant::now())
+    }
+
+    /// Like `insert`, with `seen_at` as the time the signature was seen.
+    pub(crate) fn insert_at(&mut self, signature: Signature, seen_at: Instant) -> Option<Instant> {
+        let sequence = self.next;
+        self.next += 1;
+        self.order.push_back((signature, sequence));
+        if let Some((first_seen, last_seen)) = self.seen.get_mut(&signature) {
+            *last_seen = sequence;
+            let first_seen = *first_seen;
+            self.compact();
+            return Some(first_seen);
+        }
+        self.seen.insert(signature, (seen_at, sequence));
+        while self.seen.len() > self.capacity
+            && let Some((oldest, sequence)) = self.order.pop_front()
+        {
+            if self
+                .seen
+                .get(&oldest)
+                .is_some_and(|(_, last)| *last == sequence)
+            {
+                self.seen.remove(&oldest);
+            }
+        }
+        None
+    }
+
+    // 重复出现的签名会在 order 中留下旧记录，超过容量的两倍时清理
+    fn compact(&mut self) {
+        if self.order.len() > self.capacity * 2 {
+            let seen = &self.seen;
+            self.order.retain(|(signature, sequence)| {
+                seen.get(signature)
+                    .is_some_and(|(_, last)| last == sequence)
+            });
+        }
+    }
+}
+
+// RPC 返回的 meta 是 UI 格式，转换回 TransactionStatusMeta 以复用 TransactionContext::new
+fn status_meta_from_ui(meta: UiTransactionStatusMeta) -> Result<TransactionStatusMeta> {
+    let inner_instructions = Option::from(meta.inner_instructions)
+        .map(|inner: Vec<_>| {
+            inner
+                .into_iter()
+                .map(|ixs| {
+                    let instructions = ixs
+                        .instructions
+                        .into_iter()
+                        .map(inner_instruction_from_ui)
+                        .collect::<Result<_>>()?;
+                    Ok(InnerInstructions {
+                        index: ixs.index,
+                        instructions,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()?;
+
+    let token_balances = |balances: Option<Vec<_>>| {
+        balances.map(|balances| {
+            balances
+                .into_iter()
+                .map(|b: UiTransactionTokenBalance| TransactionTokenBalance {
+                    account_index: b.account_index,
+                    mint: b.mint,
+                    ui_token_amount: b.ui_token_amount,
+                    owner: Option::from(b.owner).unwrap_or_default(),
+                    program_id: Option::from(b.program_id).unwrap_or_default(),
+                })
+                .collect()
+        })
+    };
+
+    let loaded_addresses: Option<UiLoadedAddresses> = meta.loaded_addresses.into();
+    let loaded_addresses = match loaded_addresses {
+        Some(addresses) => {
+            let parse = |keys: Vec<String>| {
+                keys.iter()
+                    .map(|key| Ok(Pubkey::from_str(key)?))
+                    .collect::<Result<Vec<_>>>()
+            };
+            LoadedAddresses {
+                writable: parse(addresses.writable)?,
+                readonly: parse(addresses.readonly)?,
+            }
+        }
+        None => LoadedAddresses::default(),
+    };
+
+    let return_data = Option::from(meta.return_data)
+        .map(|data: UiTransactionReturnData| {
+            Ok::<_, anyhow::Error>(TransactionReturnData {
+                program_id: Pubkey::from_str(&data.program_id)?,
+                data: base64::engine::general_purpose::STANDARD.decode(data.data.0)?,
+            })
+        })
+        .transpose()?;
+
+    Ok(TransactionStatusMeta {
+        status: meta.status,
+        fee: meta.fee,
+        pre_balances: meta.pre_balances,
+        post_balances: meta.post_balances,
+        inner_instructions,
+        log_messages: meta.log_messages.into(),
+        pre_token_balances: token_balances(meta.pre_token_balances.into()),
+        post_token_balances: token_balances(meta.post_token_balances.into()),
+        rewards: meta.rewards.into(),
+        loaded_addresses,
+        return_data,
+        compute_units_consumed: meta.compute_units_consumed.into(),
+    })
+}
+
+fn inner_instruction_from_ui(ix: UiInstruction) -> Result<InnerInstruction> {
+    match ix {
+        UiInstruction::Compiled(ix) => Ok(InnerInstruction {
+            instruction: CompiledInstruction {
+                program_id_index: ix.program_id_index,
+                accounts: ix.accounts,
+                data: bs58::decode(&ix.data).into_vec()?,
+            },
+            stack_height: ix.stack_height,
+        }),
+        UiInstruction::Parsed(_) => Err(anyhow!(
+            "parsed inner instruction, binary encoding required"
+        )),
+    }
+}
@@ -0,0 +1,312 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc as std_mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Result, anyhow};
+use futures::StreamExt;
+use futures::channel::mpsc;
+use log::warn;
+use prost::Message;
+use solana_sdk::hash::Hash;
+use yellowstone_grpc_proto::geyser::SubscribeUpdate;
+use yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof;
+
+use crate::commitment::SlotUpdate;
+use crate::source::geyser::{account_update, observed_transaction, slot_update};
+use crate::source::{AccountStream, SlotStream, TransactionSource, TransactionStream};
+use crate::state::AccountUpdate;
+
+/// One frame of a recording: a `SubscribeUpdate` and when we received it.
+#[derive(Clone, PartialEq, Message)]
+pub struct RecordedUpdate {
+    /// Microseconds since the unix epoch
+    #[prost(uint64, tag = "1")]
+    pub received_at_micros: u64,
+    #[prost(message, optional, tag = "2")]
+    pub update: Option<SubscribeUpdate>,
+}
+
+/// Writes every received `SubscribeUpdate` to a file of length-delimited
+/// `RecordedUpdate` frames.
+///
+/// Frames are written by a dedicated thread so recording never blocks the
+/// subscription stream. Clones write to the same file, so every subscription
+/// of a source, resubscriptions included, ends up in one recording. Dropping
+/// the last clone waits for pending frames to be written.
+#[derive(Clone)]
+pub struct UpdateRecorder {
+    // 在 _writer 之前 drop：先关闭 channel，最后一个 clone 再等待写线程
+    sender: std_mpsc::Sender<RecordedUpdate>,
+    _writer: Arc<RecordingWriter>,
+}
+
+struct RecordingWriter(Option<JoinHandle<()>>);
+
+impl Drop for RecordingWriter {
+    fn drop(&mut self) {
+        // 所有 sender 都已关闭，等待写线程把剩余的帧写完
+        if let Some(writer) = self.0.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+impl UpdateRecorder {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let (sender, receiver) = std_mpsc::channel::<RecordedUpdate>();
+        let writer = thread::spawn(move || {
+            for frame in receiver {
+                // 每帧都 flush，进程崩溃时录制文件依然完整，便于复现问题
+                let written = writer
+                    .write_all(&frame.encode_length_delimited_to_vec())
+                    .and_then(|()| writer.flush());
+                if let Err(err) = written {
+                    warn!("failed to write recording, stop recording: {:?}", err);
+                    return;
+                }
+            }
+        });
+        Ok(UpdateRecorder {
+            sender,
+            _writer: Arc::new(RecordingWriter(Some(writer))),
+        })
+    }
+
+    /// Queues `update` for writing, fails once the writer thread has stopped.
+    pub fn record(&self, update: &SubscribeUpdate) -> Result<()> {
+        let received_at = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        let frame = RecordedUpdate {
+            received_at_micros: received_at.as_micros() as u64,
+            update: Some(update.clone()),
+        };
+        self.sender
+            .send(frame)
+            .map_err(|_| anyhow!("recording stopped"))
+    }
+}
+
+/// Reads all frames of a recording.
+pub fn read_recording(path: impl AsRef<Path>) -> Result<Vec<RecordedUpdate>> {
+    let data = std::fs::read(path)?;
+    let mut buf = data.as_slice();
+    let mut frames = Vec::new();
+    while !buf.is_empty() {
+        frames.push(RecordedUpdate::decode_length_delimited(&mut buf)?);
+    }
+    Ok(frames)
+}
+
+/// Feeds a recording made by `UpdateRecorder` back through the engine.
+///
+/// With a `speed` the original gaps between updates are replayed, divided by
+/// `speed` (1.0 is real time, 10.0 ten times faster). Without one the
+/// updates are replayed as fast as they are consumed.
+///
+/// Recorded slot and account updates are delivered by `slot_updates` and
+/// `account_updates` as the transaction stream reaches them.
+pub struct RecordingReplaySource {
+    path: PathBuf,
+    speed: Option<f64>,
+    slot_sender: Option<mpsc::UnboundedSender<SlotUpdate>>,
+    account_sender: Option<mpsc::UnboundedSender<AccountUpdate>>,
+}
+
+impl RecordingReplaySource {
+    pub fn new(path: impl Into<PathBuf>, speed: Option<f64>) -> Self {
+        RecordingReplaySource {
+            path: path.into(),
+            speed: speed.filter(|speed| *speed > 0.0),
+            slot_sender: None,
+            account_sender: None,
+        }
+    }
+}
+
+impl TransactionSource for RecordingReplaySource {
+    async fn subscribe(&mut self) -> Result<TransactionStream> {
+        let frames = read_recording(&self.path)?;
+        let speed = self.speed;
+        let first = frames.first().map(|frame| frame.received_at_micros);
+        let started = tokio::time::Instant::now();
+        let slot_sender = self.slot_sender.clone();
+        let account_sender = self.account_sender.clone();
+
+        let transactions = futures::stream::iter(frames).filter_map(move |frame| {
+            let slot_sender = slot_sender.clone();
+            let account_sender = account_sender.clone();
+            async move {
+                // 按录制时的时间间隔回放
+                if let (Some(speed), Some(first)) = (speed, first) {
+                    let offset = frame.received_at_micros.saturating_sub(first) as f64 / speed;
+                    tokio::time::sleep_until(started + Duration::from_micros(offset as u64)).await;
+                }
+                match frame.update?.update_oneof? {
+                    UpdateOneof::Transaction(tx) => observed_transaction(tx).map(Ok),
+                    UpdateOneof::Slot(slot) => {
+                        if let (Some(sender), Some(update)) = (slot_sender, slot_update(slot)) {
+                            let _ = sender.unbounded_send(update);
+                        }
+                        None
+                    }
+                    UpdateOneof::Account(account) => {
+                        if let (Some(sender), Some(update)) =
+                            (account_sender, account_update(account))
+                        {
+                            let _ = sender.unbounded_send(update);
+                        }
+                        None
+                    }
+                    _ => None,
+                }
+            }
+        });
+        Ok(Box::pin(transactions))
+    }
+
+    // 回放不连接集群，没有可用的 blockhash
+    async fn latest_blockhash(&mut self) -> Result<Option<Hash>> {
+        Ok(None)
+    }
+
+    fn slot_updates(&mut self) -> Option<SlotStream> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.slot_sender = Some(sender);
+        Some(Box::pin(receiver))
+    }
+
+    fn account_updates(&mut self) -> Option<AccountStream> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.account_sender = Some(sender);
+        Some(Box::pin(receiver))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::system_instruction;
+    use solana_sdk::transaction::{SanitizedTransaction, Transaction};
+    use solana_transaction_status::TransactionStatusMeta;
+    use yellowstone_grpc_proto::convert_to;
+    use yellowstone_grpc_proto::geyser::{
+        SlotStatus as GeyserSlotStatus, SubscribeUpdateAccount, SubscribeUpdateAccountInfo,
+        SubscribeUpdatePing, SubscribeUpdateSlot, SubscribeUpdateTransaction,
+        SubscribeUpdateTransactionInfo,
+    };
+
+    use crate::commitment::SlotStatus;
+
+    fn transaction_update(slot: u64) -> SubscribeUpdate {
+        let payer = Keypair::new();
+        let ix = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        let sanitized = SanitizedTransaction::from_transaction_for_tests(tx);
+        SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+                transaction: Some(SubscribeUpdateTransactionInfo {
+                    signature: sanitized.signature().as_ref().to_vec(),
+                    is_vote: false,
+                    transaction: Some(convert_to::create_transaction(&sanitized)),
+                    meta: Some(convert_to::create_transaction_meta(
+                        &TransactionStatusMeta::default(),
+                    )),
+                    index: 0,
+                }),
+                slot,
+            })),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let path = std::env::temp_dir().join(format!("recording-{}.bin", Pubkey::new_unique()));
+        let recorder = UpdateRecorder::create(&path).unwrap();
+        // 重新订阅时用的 clone 写入同一个文件
+        let resubscribed = recorder.clone();
+
+        let ping = SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Ping(SubscribeUpdatePing {})),
+            ..Default::default()
+        };
+        // 没有交易内容的推送会在回放时被跳过
+        let empty_transaction = SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+                transaction: None,
+                slot: 7,
+            })),
+            ..Default::default()
+        };
+        let slot = SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Slot(SubscribeUpdateSlot {
+                slot: 8,
+                parent: Some(7),
+                status: GeyserSlotStatus::SlotConfirmed as i32,
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        let (pool, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let account = SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Account(SubscribeUpdateAccount {
+                account: Some(SubscribeUpdateAccountInfo {
+                    pubkey: pool.to_bytes().to_vec(),
+                    owner: owner.to_bytes().to_vec(),
+                    data: vec![1, 2, 3],
+                    ..Default::default()
+                }),
+                slot: 8,
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        recorder.record(&ping).unwrap();
+        recorder.record(&empty_transaction).unwrap();
+        recorder.record(&transaction_update(8)).unwrap();
+        resubscribed.record(&slot).unwrap();
+        resubscribed.record(&account).unwrap();
+        // drop 最后一个 clone 时等待写线程写完
+        drop(recorder);
+        drop(resubscribed);
+
+        let frames = read_recording(&path).unwrap();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[0].update, Some(ping));
+        assert_eq!(frames[1].update, Some(empty_transaction));
+        assert!(frames[0].received_at_micros <= frames[2].received_at_micros);
+
+        let mut source = RecordingReplaySource::new(&path, Some(100.0));
+        let mut slots = source.slot_updates().unwrap();
+        let mut accounts = source.account_updates().unwrap();
+        let replayed: Vec<_> = source.subscribe().await.unwrap().collect().await;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replayed.len(), 1);
+        let observed = replayed[0].as_ref().unwrap();
+        assert_eq!(observed.slot, 8);
+        assert!(observed.meta.is_some());
+        assert_eq!(observed.context().instructions.len(), 1);
+
+        let slot = slots.next().await.unwrap();
+        assert_eq!((slot.slot, slot.parent), (8, Some(7)));
+        assert_eq!(slot.status, SlotStatus::Confirmed);
+        let account = accounts.next().await.unwrap();
+        assert_eq!(
+            (account.pubkey, account.owner, account.slot),
+            (pool, owner, 8)
+        );
+        assert_eq!(account.data, vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,379 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, anyhow};
+use futures::StreamExt;
+use log::warn;
+use solana_sdk::hash::Hash;
+use tokio::sync::Mutex as AsyncMutex;
+
+use crate::pipeline::{self, QueuePolicy, QueueReceiver, QueueSender};
+use crate::source::{
+    AccountStream, ObservedTransaction, RecentSignatures, SlotStream, TransactionSource,
+    TransactionStream,
+};
+
+// 足够覆盖各个端点之间的延迟差
+const DEFAULT_DEDUP_CAPACITY: usize = 100_000;
+
+const RESUBSCRIBE_BACKOFF: Duration = Duration::from_secs(1);
+
+const MAX_RESUBSCRIBE_BACKOFF: Duration = Duration::from_secs(30);
+
+// 各端点汇合队列的默认长度，满了以后按 QueuePolicy 等待或丢弃
+const DEFAULT_QUEUE_CAPACITY: usize = 1024;
+
+/// How one endpoint of a `RedundantSource` performs against the others.
+#[derive(Debug, Clone, Default)]
+pub struct EndpointStats {
+    pub endpoint: String,
+    /// Transactions received, duplicates included
+    pub received: u64,
+    /// Transactions this endpoint delivered first
+    pub wins: u64,
+    pub errors: u64,
+    /// Transactions dropped because the merge queue was full, see `with_queue`
+    pub dropped: u64,
+    /// Summed delay behind the first copy, over the copies that lost
+    pub total_lag: Duration,
+    pub max_lag: Duration,
+}
+
+impl EndpointStats {
+    pub fn win_rate(&self) -> f64 {
+        if self.received == 0 {
+            return 0.0;
+        }
+        self.wins as f64 / self.received as f64
+    }
+
+    /// Average delay behind the fastest endpoint when this one lost.
+    pub fn average_lag(&self) -> Option<Duration> {
+        let lost = self.received - self.wins;
+        (lost > 0).then(|| self.total_lag / lost as u32)
+    }
+}
+
+/// Subscribes to several sources at once and forwards whichever copy of a
+/// transaction arrives first, de-duplicated by signature.
+///
+/// Every endpoint is read by its own task, which stamps when each transaction
+/// arrived. An endpoint that errors or ends is logged and resubscribed after a
+/// backoff, doubling up to `MAX_RESUBSCRIBE_BACKOFF` while it keeps failing.
+/// The tasks feed a bounded queue, so an endpoint waits or drops transactions
+/// when the consumer falls behind.
+pub struct RedundantSource<S> {
+    sources: Vec<(String, Arc<AsyncMutex<S>>)>,
+    dedup_capacity: usize,
+    queue_capacity: usize,
+    queue_policy: QueuePolicy,
+    resubscribe_backoff: Duration,
+    stats: Arc<Mutex<Vec<EndpointStats>>>,
+}
+
+impl<S: TransactionSource> RedundantSource<S> {
+    /// `sources` pairs each source with the endpoint name used in its stats.
+    pub fn new(sources: Vec<(String, S)>) -> Self {
+        let stats = sources
+            .iter()
+            .map(|(endpoint, _)| EndpointStats {
+                endpoint: endpoint.clone(),
+                ..Default::default()
+            })
+            .collect();
+        RedundantSource {
+            sources: sources
+                .into_iter()
+                .map(|(endpoint, source)| (endpoint, Arc::new(AsyncMutex::new(source))))
+                .collect(),
+            dedup_capacity: DEFAULT_DEDUP_CAPACITY,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            queue_policy: QueuePolicy::Block,
+            resubscribe_backoff: RESUBSCRIBE_BACKOFF,
+            stats: Arc::new(Mutex::new(stats)),
+        }
+    }
+
+    pub fn with_dedup_capacity(mut self, capacity: usize) -> Self {
+        self.dedup_capacity = capacity;
+        self
+    }
+
+    /// Length of the queue the endpoints feed and what they do when it is full.
+    pub fn with_queue(mut self, capacity: usize, policy: QueuePolicy) -> Self {
+        self.queue_capacity = capacity;
+        self.queue_policy = policy;
+        self
+    }
+
+    /// First delay before resubscribing to an endpoint that errored or ended.
+    pub fn with_resubscribe_backoff(mut self, backoff: Duration) -> Self {
+        self.resubscribe_backoff = backoff;
+        self
+    }
+
+    /// Per endpoint stats, in the order of `sources`, updated while the stream runs.
+    pub fn stats(&self) -> Arc<Mutex<Vec<EndpointStats>>> {
+        self.stats.clone()
+    }
+}
+
+impl<S: TransactionSource + Send + 'static> TransactionSource for RedundantSource<S> {
+    async fn subscribe(&mut self) -> Result<TransactionStream> {
+        let mut streams = Vec::new();
+        for (index, (endpoint, source)) in self.sources.iter().enumerate() {
+            let stream = source.lock().await.subscribe().await;
+            if let Err(err) = &stream {
+                warn!("failed to subscribe to {}: {:?}", endpoint, err);
+                self.stats.lock().unwrap()[index].errors += 1;
+            }
+            streams.push(stream.ok());
+        }
+        if streams.iter().all(Option::is_none) {
+            return Err(anyhow!("failed to subscribe to any endpoint"));
+        }
+
+        // 每个端点一个任务，收到交易时就记录到达时间，不受下游消费速度影响
+        let (sender, receiver) =
+            pipeline::queue("redundant", self.queue_capacity, self.queue_policy);
+        for (index, stream) in streams.into_iter().enumerate() {
+            let (endpoint, source) = &self.sources[index];
+            tokio::spawn(forward_endpoint(
+                index,
+                endpoint.clone(),
+                source.clone(),
+                stream,
+                self.resubscribe_backoff,
+                self.stats.clone(),
+                sender.clone(),
+            ));
+        }
+
+        let stats = self.stats.clone();
+        let mut recent = RecentSignatures::new(self.dedup_capacity);
+        let arrivals = futures::stream::unfold(
+            receiver,
+            |mut receiver: QueueReceiver<Arrival>| async move {
+                let arrival = receiver.recv().await?;
+                Some((arrival, receiver))
+            },
+        );
+        let transactions = arrivals.filter_map(move |(index, arrived, observed)| {
+            let mut stats = stats.lock().unwrap();
+            let stats = &mut stats[index];
+            stats.received += 1;
+            let forward = match recent.insert_at(observed.signature, arrived) {
+                Some(first_seen) => {
+                    let lag = arrived.saturating_duration_since(first_seen);
+                    stats.total_lag += lag;
+                    stats.max_lag = stats.max_lag.max(lag);
+                    None
+                }
+                None => {
+                    stats.wins += 1;
+                    Some(Ok(observed))
+                }
+            };
+            futures::future::ready(forward)
+        });
+        Ok(Box::pin(transactions))
+    }
+
+    // 取第一个能返回 blockhash 的端点
+    async fn latest_blockhash(&mut self) -> Result<Option<Hash>> {
+        let mut last_err = None;
+        for (endpoint, source) in self.sources.iter() {
+            match source.lock().await.latest_blockhash().await {
+                Ok(hash) => return Ok(hash),
+                Err(err) => {
+                    warn!("failed to get blockhash from {}: {:?}", endpoint, err);
+                    last_err = Some(err);
+                }
+            }
+        }
+        Err(last_err.unwrap_or_else(|| anyhow!("no endpoint configured")))
+    }
+
+    // 各端点的 slot 更新合并在一起，重复的更新对 CommitmentTracker 没有影响
+    fn slot_updates(&mut self) -> Option<SlotStream> {
+        let streams: Vec<_> = self
+            .sources
+            .iter()
+            .filter_map(|(_, source)| source.try_lock().ok()?.slot_updates())
+            .collect();
+        if streams.is_empty() {
+            return None;
+        }
+        Some(Box::pin(futures::stream::select_all(streams)))
+    }
+
+    // 重复或较旧的账户更新由 StateStore 按 slot 丢弃
+    fn account_updates(&mut self) -> Option<AccountStream> {
+        let streams: Vec<_> = self
+            .sources
+            .iter()
+            .filter_map(|(_, source)| source.try_lock().ok()?.account_updates())
+            .collect();
+        if streams.is_empty() {
+            return None;
+        }
+        Some(Box::pin(futures::stream::select_all(streams)))
+    }
+}
+
+type Arrival = (usize, Instant, ObservedTransaction);
+
+// 转发一个端点的交易，出错或结束后退避重连，直到下游不再接收
+async fn forward_endpoint<S: TransactionSource>(
+    index: usize,
+    endpoint: String,
+    source: Arc<AsyncMutex<S>>,
+    mut stream: Option<TransactionStream>,
+    initial_backoff: Duration,
+    stats: Arc<Mutex<Vec<EndpointStats>>>,
+    sender: QueueSender<Arrival>,
+) {
+    let mut backoff = initial_backoff;
+    loop {
+        if let Some(mut transactions) = stream.take() {
+            while let Some(item) = transactions.next().await {
+                match item {
+                    Ok(observed) => {
+                        backoff = initial_backoff;
+                        match sender.push((index, Instant::now(), observed)).await {
+                            Ok(true) => {}
+                            Ok(false) => stats.lock().unwrap()[index].dropped += 1,
+                            Err(_) => return,
+                        }
+                    }
+                    Err(err) => {
+                        warn!("{} failed: {:?}", endpoint, err);
+                        stats.lock().unwrap()[index].errors += 1;
+                        break;
+                    }
+                }
+            }
+            warn!("{} disconnected, resubscribing in {:?}", endpoint, backoff);
+        }
+        if sender.is_closed() {
+            return;
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_RESUBSCRIBE_BACKOFF);
+        match source.lock().await.subscribe().await {
+            Ok(transactions) => stream = Some(transactions),
+            Err(err) => {
+                warn!("failed to resubscribe to {}: {:?}", endpoint, err);
+                stats.lock().unwrap()[index].errors += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use solana_sdk::message::{Message, VersionedMessage};
+    use solana_sdk::signature::Signature;
+    use solana_sdk::transaction::VersionedTransaction;
+
+    // 按顺序推送交易，每笔之前等待 delay
+    struct FixedSource(Vec<u8>, Duration);
+
+    impl TransactionSource for FixedSource {
+        async fn subscribe(&mut self) -> Result<TransactionStream> {
+            let delay = self.1;
+            let transactions = self.0.iter().map(|id| {
+                let signature = Signature::from([*id; 64]);
+                Ok(ObservedTransaction {
+                    slot: 1,
+                    signature,
+                    transaction: VersionedTransaction {
+                        signatures: vec![signature],
+                        message: VersionedMessage::Legacy(Message::default()),
+                    },
+                    meta: None,
+                })
+            });
+            Ok(Box::pin(
+                futures::stream::iter(transactions.collect::<Vec<_>>()).then(
+                    move |observed| async move {
+                        tokio::time::sleep(delay).await;
+                        observed
+                    },
+                ),
+            ))
+        }
+
+        async fn latest_blockhash(&mut self) -> Result<Option<Hash>> {
+            Ok(None)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_redundant_source() {
+        let delay = Duration::from_millis(50);
+        let mut source = RedundantSource::new(vec![
+            ("a".to_string(), FixedSource(vec![1, 2, 3], Duration::ZERO)),
+            ("b".to_string(), FixedSource(vec![2, 3, 4], delay)),
+        ])
+        .with_resubscribe_backoff(Duration::from_secs(60));
+        let stats = source.stats();
+
+        // 端点结束后会重连，流不会结束
+        let mut signatures: Vec<_> = source
+            .subscribe()
+            .await
+            .unwrap()
+            .map(|observed| observed.unwrap().signature)
+            .take(4)
+            .collect()
+            .await;
+        signatures.sort();
+        let expected: Vec<_> = (1..=4).map(|id| Signature::from([id; 64])).collect();
+        assert_eq!(signatures, expected);
+
+        let stats = stats.lock().unwrap();
+        assert_eq!(stats[0].endpoint, "a");
+        assert_eq!((stats[0].received, stats[1].received), (3, 3));
+        assert_eq!((stats[0].wins, stats[1].wins), (3, 1));
+        assert!(stats[0].win_rate() > 0.0);
+        // b 的 2 和 3 都晚于 a 到达
+        assert_eq!(stats[0].average_lag(), None);
+        assert!(stats[1].max_lag >= delay / 2);
+        assert!(stats[1].average_lag().unwrap() >= delay / 2);
+    }
+
+    #[tokio::test]
+    async fn test_redundant_source_full_queue() {
+        let mut source = RedundantSource::new(vec![(
+            "a".to_string(),
+            FixedSource(vec![1, 2, 3], Duration::ZERO),
+        )])
+        .with_queue(1, QueuePolicy::DropNewest)
+        .with_resubscribe_backoff(Duration::from_secs(60));
+        let stats = source.stats();
+
+        // 消费之前端点已经推送完，队列只放得下第一笔
+        let mut transactions = source.subscribe().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let observed = transactions.next().await.unwrap().unwrap();
+        assert_eq!(observed.signature, Signature::from([1; 64]));
+        let stats = stats.lock().unwrap();
+        assert_eq!((stats[0].received, stats[0].dropped), (1, 2));
+    }
+
+    #[test]
+    fn test_recent_signatures() {
+        let signature = |id| Signature::from([id; 64]);
+        let mut recent = RecentSignatures::new(2);
+        assert_eq!(recent.insert(signature(1)), None);
+        assert_eq!(recent.insert(signature(2)), None);
+        // 再次出现的签名变成最近使用的，被淘汰的是 2
+        assert!(recent.insert(signature(1)).is_some());
+        assert_eq!(recent.insert(signature(3)), None);
+        assert!(recent.insert(signature(1)).is_some());
+        assert_eq!(recent.insert(signature(2)), None);
+    }
+}
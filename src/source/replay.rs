@@ -0,0 +1,117 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use solana_sdk::hash::Hash;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+use crate::source::{ObservedTransaction, TransactionSource, TransactionStream};
+
+/// Replays transactions from a file, one `getTransaction` result per line as
+/// JSON, base64 encoded. Handy for fixtures: dump the RPC responses of the
+/// transactions of interest and run the engine over them.
+pub struct ReplaySource {
+    path: PathBuf,
+}
+
+impl ReplaySource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        ReplaySource { path: path.into() }
+    }
+}
+
+impl TransactionSource for ReplaySource {
+    async fn subscribe(&mut self) -> Result<TransactionStream> {
+        let lines = BufReader::new(File::open(&self.path)?).lines();
+        let transactions = lines
+            .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .map(|line| {
+                let encoded: EncodedConfirmedTransactionWithStatusMeta =
+                    serde_json::from_str(&line?)?;
+                ObservedTransaction::from_encoded(encoded)
+            });
+        Ok(Box::pin(futures::stream::iter(transactions)))
+    }
+
+    // 回放不连接集群，没有可用的 blockhash
+    async fn latest_blockhash(&mut self) -> Result<Option<Hash>> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    use futures::StreamExt;
+    use solana_sdk::instruction::{AccountMeta, CompiledInstruction, Instruction};
+    use solana_sdk::message::{Message, VersionedMessage};
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+    use solana_sdk::transaction::VersionedTransaction;
+    use solana_transaction_status::{
+        InnerInstruction, InnerInstructions, TransactionStatusMeta, TransactionWithStatusMeta,
+        UiTransactionEncoding, VersionedTransactionWithStatusMeta,
+    };
+
+    #[tokio::test]
+    async fn test_replay_source() {
+        let (payer, program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = Instruction::new_with_bytes(program, &[1, 2], vec![AccountMeta::new(payer, true)]);
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::from([7; 64])],
+            message: VersionedMessage::Legacy(Message::new(&[ix], Some(&payer))),
+        };
+        let meta = TransactionStatusMeta {
+            inner_instructions: Some(vec![InnerInstructions {
+                index: 0,
+                instructions: vec![InnerInstruction {
+                    instruction: CompiledInstruction::new_from_raw_parts(1, vec![3], vec![0]),
+                    stack_height: Some(2),
+                }],
+            }]),
+            log_messages: Some(vec![format!("Program {} invoke [1]", program)]),
+            compute_units_consumed: Some(1_234),
+            ..Default::default()
+        };
+        let encoded = TransactionWithStatusMeta::Complete(VersionedTransactionWithStatusMeta {
+            transaction: transaction.clone(),
+            meta,
+        })
+        .encode(UiTransactionEncoding::Base64, Some(0), true)
+        .unwrap();
+        let encoded = EncodedConfirmedTransactionWithStatusMeta {
+            slot: 42,
+            transaction: encoded,
+            block_time: None,
+        };
+
+        let path = std::env::temp_dir().join(format!("replay-{}.jsonl", Pubkey::new_unique()));
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "{}", serde_json::to_string(&encoded).unwrap()).unwrap();
+        writeln!(file).unwrap();
+
+        let mut source = ReplaySource::new(&path);
+        assert_eq!(source.latest_blockhash().await.unwrap(), None);
+        let observed: Vec<_> = source.subscribe().await.unwrap().collect().await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(observed.len(), 1);
+        let observed = observed[0].as_ref().unwrap();
+        assert_eq!(observed.slot, 42);
+        assert_eq!(observed.signature, Signature::from([7; 64]));
+        assert_eq!(observed.transaction, transaction);
+
+        let ctx = observed.context();
+        assert_eq!(ctx.compute_units_consumed, Some(1_234));
+        let inner = &ctx.inner_instructions.unwrap()[0];
+        assert_eq!(inner.len(), 1);
+        assert_eq!(
+            ctx.account_keys[inner[0].program_id_index as usize],
+            program
+        );
+        assert_eq!(inner[0].stack_height, Some(2));
+    }
+}
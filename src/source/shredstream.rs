@@ -0,0 +1,271 @@
+use anyhow::Result;
+use futures::StreamExt;
+use log::{info, warn};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+use tonic::transport::Endpoint;
+
+use crate::source::{ObservedTransaction, TransactionSource, TransactionStream};
+
+pub mod proto {
+    #![allow(clippy::all)]
+    include!(concat!(env!("OUT_DIR"), "/shredstream.ShredstreamProxy.rs"));
+}
+
+use proto::shredstream_proxy_client::ShredstreamProxyClient;
+
+/// Request of `SubscribeEntries`, the proxy has no filters yet.
+#[derive(Clone, PartialEq, Message)]
+pub struct SubscribeEntriesRequest {}
+
+/// Entries of one slot as sent by the ShredStream proxy.
+#[derive(Clone, PartialEq, Message)]
+pub struct Entry {
+    #[prost(uint64, tag = "1")]
+    pub slot: u64,
+    /// bincode serialized `Vec<solana_entry::entry::Entry>`
+    #[prost(bytes = "vec", tag = "2")]
+    pub entries: Vec<u8>,
+}
+
+// 与 solana_entry::entry::Entry 的 bincode 布局一致，避免引入 solana-entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolanaEntry {
+    pub num_hashes: u64,
+    pub hash: Hash,
+    pub transactions: Vec<VersionedTransaction>,
+}
+
+impl Entry {
+    pub fn transactions(&self) -> Result<Vec<VersionedTransaction>> {
+        let entries: Vec<SolanaEntry> = bincode::deserialize(&self.entries)?;
+        Ok(entries
+            .into_iter()
+            .flat_map(|entry| entry.transactions)
+            .collect())
+    }
+}
+
+/// Transactions from a Jito ShredStream proxy, rebuilt from shreds before the
+/// slot is replayed.
+///
+/// There is no meta: no inner instructions, logs or balances, and no error since
+/// the transactions have not been executed yet. Address lookup tables are not
+/// resolved, so `mentions` only matches static account keys.
+pub struct ShredstreamSource {
+    endpoint: String,
+    mentions: Vec<Pubkey>,
+    rpc_url: Option<String>,
+}
+
+impl ShredstreamSource {
+    /// Subscribes to the proxy at `endpoint`, keeping the transactions that
+    /// mention any of `mentions`, or all of them when it is empty.
+    pub fn new(endpoint: String, mentions: Vec<Pubkey>) -> Self {
+        ShredstreamSource {
+            endpoint,
+            mentions,
+            rpc_url: None,
+        }
+    }
+
+    /// The proxy has no blockhash, fetch it from this RPC to allow sniping.
+    pub fn with_blockhash_rpc(mut self, rpc_url: String) -> Self {
+        self.rpc_url = Some(rpc_url);
+        self
+    }
+}
+
+impl TransactionSource for ShredstreamSource {
+    async fn subscribe(&mut self) -> Result<TransactionStream> {
+        info!("SHREDSTREAM_ENDPOINT = {}", self.endpoint);
+        let channel = Endpoint::from_shared(self.endpoint.clone())?
+            .connect()
+            .await?;
+        let entries = ShredstreamProxyClient::new(channel)
+            .subscribe_entries(SubscribeEntriesRequest {})
+            .await?
+            .into_inner();
+
+        let mentions = self.mentions.clone();
+        let transactions = entries.flat_map(move |entry| {
+            let observed = match entry {
+                Ok(entry) => observed_transactions(&entry, &mentions),
+                Err(status) => vec![Err(status.into())],
+            };
+            futures::stream::iter(observed)
+        });
+        Ok(Box::pin(transactions))
+    }
+
+    async fn latest_blockhash(&mut self) -> Result<Option<Hash>> {
+        let Some(rpc_url) = &self.rpc_url else {
+            return Ok(None);
+        };
+        let rpc = RpcClient::new_with_commitment(rpc_url.clone(), CommitmentConfig::confirmed());
+        Ok(Some(rpc.get_latest_blockhash().await?))
+    }
+}
+
+// 无法反序列化的 entry 只跳过，不中断订阅
+fn observed_transactions(entry: &Entry, mentions: &[Pubkey]) -> Vec<Result<ObservedTransaction>> {
+    let transactions = match entry.transactions() {
+        Ok(transactions) => transactions,
+        Err(err) => {
+            warn!(
+                "failed to deserialize entries of slot {}: {:?}",
+                entry.slot, err
+            );
+            return Vec::new();
+        }
+    };
+    transactions
+        .into_iter()
+        .filter(|tx| {
+            let keys = tx.message.static_account_keys();
+            mentions.is_empty() || mentions.iter().any(|account| keys.contains(account))
+        })
+        .map(|transaction| {
+            Ok(ObservedTransaction {
+                slot: entry.slot,
+                signature: transaction.signatures.first().copied().unwrap_or_default(),
+                transaction,
+                meta: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::system_instruction;
+    use solana_sdk::transaction::Transaction;
+    use tonic::transport::Server;
+    use tonic::{Request, Response, Status};
+
+    use crate::constants;
+    use crate::decoder::{DexDecoder, Venue};
+    use crate::raydium::RaydiumAmmDecoder;
+    use crate::source::shredstream::proto::shredstream_proxy_server::{
+        ShredstreamProxy, ShredstreamProxyServer,
+    };
+
+    // 按录制好的 entries 逐条推送的 ShredStream 代理
+    struct MockProxy(Vec<Entry>);
+
+    #[tonic::async_trait]
+    impl ShredstreamProxy for MockProxy {
+        type SubscribeEntriesStream =
+            futures::stream::Iter<std::vec::IntoIter<Result<Entry, Status>>>;
+
+        async fn subscribe_entries(
+            &self,
+            _request: Request<SubscribeEntriesRequest>,
+        ) -> Result<Response<Self::SubscribeEntriesStream>, Status> {
+            let entries: Vec<_> = self.0.iter().cloned().map(Ok).collect();
+            Ok(Response::new(futures::stream::iter(entries)))
+        }
+    }
+
+    fn transfer(to: &Pubkey) -> VersionedTransaction {
+        let payer = Keypair::new();
+        let ix = system_instruction::transfer(&payer.pubkey(), to, 1);
+        Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], Hash::default())
+            .into()
+    }
+
+    // Raydium SwapBaseIn，18 个账户，最后一个是签名的 user owner
+    fn swap(program_id: &Pubkey, amount_in: u64, minimum_amount_out: u64) -> VersionedTransaction {
+        let payer = Keypair::new();
+        let mut accounts: Vec<_> = (0..17)
+            .map(|_| AccountMeta::new(Pubkey::new_unique(), false))
+            .collect();
+        accounts.push(AccountMeta::new(payer.pubkey(), true));
+        let mut data = vec![9];
+        data.extend(amount_in.to_le_bytes());
+        data.extend(minimum_amount_out.to_le_bytes());
+        let ix = Instruction::new_with_bytes(*program_id, &data, accounts);
+        Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], Hash::default())
+            .into()
+    }
+
+    fn entry(slot: u64, transactions: Vec<VersionedTransaction>) -> Entry {
+        let entries = vec![
+            SolanaEntry {
+                num_hashes: 1,
+                hash: Hash::new_unique(),
+                transactions: Vec::new(),
+            },
+            SolanaEntry {
+                num_hashes: 1,
+                hash: Hash::new_unique(),
+                transactions,
+            },
+        ];
+        Entry {
+            slot,
+            entries: bincode::serialize(&entries).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shredstream_source() {
+        let watched = constants::RAYDIUM_AAM_PROGRAM;
+        let matching = swap(&watched, 1_000, 900);
+        let entries = vec![
+            entry(10, vec![transfer(&Pubkey::new_unique()), matching.clone()]),
+            Entry {
+                slot: 11,
+                entries: vec![1, 2, 3],
+            },
+            entry(12, vec![transfer(&watched)]),
+        ];
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = futures::stream::unfold(listener, |listener| async {
+            let stream = listener.accept().await.map(|(stream, _)| stream);
+            Some((stream, listener))
+        });
+        tokio::spawn(
+            Server::builder()
+                .add_service(ShredstreamProxyServer::new(MockProxy(entries)))
+                .serve_with_incoming(incoming),
+        );
+
+        let mut source = ShredstreamSource::new(format!("http://{}", addr), vec![watched]);
+        assert_eq!(source.latest_blockhash().await.unwrap(), None);
+        let observed: Vec<_> = source
+            .subscribe()
+            .await
+            .unwrap()
+            .map(|observed| observed.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(observed.len(), 2);
+        assert_eq!(observed[0].slot, 10);
+        assert_eq!(observed[0].signature, matching.signatures[0]);
+        assert!(observed[0].meta.is_none());
+        assert_eq!(observed[0].context().instructions.len(), 1);
+
+        // 没有 meta 和日志，从顶层指令的参数解码 swap
+        let decoders: Vec<Box<dyn DexDecoder>> = vec![Box::new(RaydiumAmmDecoder)];
+        let swaps = observed[0].context().decode_swaps(&decoders);
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].venue, Venue::RaydiumAmm);
+        assert_eq!((swaps[0].amount_in, swaps[0].amount_out), (1_000, 900));
+        assert_eq!(swaps[0].trader, matching.message.static_account_keys()[0]);
+        assert_eq!(swaps[0].mint_in, Pubkey::default());
+        assert_eq!(observed[1].slot, 12);
+    }
+}
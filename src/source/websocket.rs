@@ -0,0 +1,133 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use log::warn;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcTransactionConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::constants;
+use crate::source::{ObservedTransaction, RecentSignatures, TransactionSource, TransactionStream};
+
+// 同一笔交易可能同时命中多个 mentions 订阅，记住最近的签名用于去重
+const RECENT_SIGNATURES: usize = 4096;
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Solana RPC websocket source: `logsSubscribe` for every watched account,
+/// then `getTransaction` for each notified signature.
+///
+/// `getTransaction` does not serve processed transactions, so both run at
+/// `Confirmed` and this source lags a Geyser subscription by a few slots.
+pub struct WebsocketSource {
+    rpc_url: String,
+    ws_url: String,
+    mentions: Vec<Pubkey>,
+    include_failed: bool,
+    commitment: CommitmentConfig,
+}
+
+impl WebsocketSource {
+    pub fn new(rpc_url: String, ws_url: String, mentions: Vec<Pubkey>) -> Self {
+        WebsocketSource {
+            rpc_url,
+            ws_url,
+            mentions,
+            include_failed: *constants::INCLUDE_FAILED_TRANSACTIONS,
+            commitment: CommitmentConfig::confirmed(),
+        }
+    }
+}
+
+impl TransactionSource for WebsocketSource {
+    async fn subscribe(&mut self) -> Result<TransactionStream> {
+        let (mut sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let (rpc_url, ws_url) = (self.rpc_url.clone(), self.ws_url.clone());
+        let (mentions, include_failed, commitment) =
+            (self.mentions.clone(), self.include_failed, self.commitment);
+
+        // PubsubClient 的订阅流借用 client，放在单独的任务中持有
+        tokio::spawn(async move {
+            let result = async {
+                let pubsub = PubsubClient::new(&ws_url).await?;
+                let rpc = RpcClient::new_with_commitment(rpc_url, commitment);
+
+                let mut subscriptions = Vec::new();
+                for account in &mentions {
+                    let (logs, _unsubscribe) = pubsub
+                        .logs_subscribe(
+                            RpcTransactionLogsFilter::Mentions(vec![account.to_string()]),
+                            RpcTransactionLogsConfig {
+                                commitment: Some(commitment),
+                            },
+                        )
+                        .await?;
+                    subscriptions.push(logs);
+                }
+                let mut logs = futures::stream::select_all(subscriptions);
+
+                let mut recent = RecentSignatures::new(RECENT_SIGNATURES);
+                while let Some(response) = logs.next().await {
+                    let logs = response.value;
+                    if logs.err.is_some() && !include_failed {
+                        continue;
+                    }
+                    let Ok(signature) = Signature::from_str(&logs.signature) else {
+                        continue;
+                    };
+                    if recent.insert(signature).is_some() {
+                        continue;
+                    }
+                    // 单笔交易拉取失败不影响订阅
+                    match get_transaction(&rpc, &signature, commitment).await {
+                        Ok(observed) => {
+                            if sender.send(Ok(observed)).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err(err) => warn!("getTransaction {} failed: {:?}", signature, err),
+                    }
+                }
+                Ok::<_, anyhow::Error>(())
+            }
+            .await;
+
+            if let Err(err) = result {
+                let _ = sender.send(Err(err)).await;
+            }
+        });
+
+        Ok(Box::pin(receiver))
+    }
+
+    async fn latest_blockhash(&mut self) -> Result<Option<Hash>> {
+        let rpc = RpcClient::new_with_commitment(self.rpc_url.clone(), self.commitment);
+        Ok(Some(rpc.get_latest_blockhash().await?))
+    }
+}
+
+async fn get_transaction(
+    rpc: &RpcClient,
+    signature: &Signature,
+    commitment: CommitmentConfig,
+) -> Result<ObservedTransaction> {
+    let encoded = rpc
+        .get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(commitment),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await?;
+    ObservedTransaction::from_encoded(encoded)
+}
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::{Result, anyhow};
+use log::warn;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::constants;
+use crate::openbook::{MarketAccounts, MarketState};
+use crate::pumpfun::BondingCurve;
+use crate::raydium::AmmInfo;
+use crate::spl_token::TokenAccountState;
+
+/// Raw account update delivered by a `TransactionSource`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountUpdate {
+    pub pubkey: Pubkey,
+    pub owner: Pubkey,
+    pub slot: u64,
+    pub data: Vec<u8>,
+}
+
+/// Decoded state of an account we follow.
+#[derive(Debug, Clone)]
+pub enum AccountState {
+    RaydiumAmm(Box<AmmInfo>),
+    /// AMM coin/pc vault
+    TokenAccount(TokenAccountState),
+    PumpfunBondingCurve(BondingCurve),
+    /// OpenBook market of an AMM, for the market accounts of its swaps
+    OpenbookMarket(Box<MarketState>),
+}
+
+impl AccountState {
+    /// Decodes the account by its owner program.
+    pub fn decode(owner: &Pubkey, data: &[u8]) -> Result<Self> {
+        if *owner == constants::RAYDIUM_AAM_PROGRAM {
+            let amm = AmmInfo::try_from_account_data(data)?;
+            Ok(AccountState::RaydiumAmm(Box::new(amm)))
+        } else if *owner == constants::TOKEN_PROGRAM || *owner == constants::TOKEN_2022_PROGRAM {
+            let account = TokenAccountState::try_from_account_data(data)?;
+            Ok(AccountState::TokenAccount(account))
+        } else if *owner == constants::PUMP_FUN_PROGRAM {
+            let curve = BondingCurve::try_from_account_data(data)?;
+            Ok(AccountState::PumpfunBondingCurve(curve))
+        } else if *owner == constants::OPENBOOK_PROGRAM {
+            let market = MarketState::try_from_account_data(data)?;
+            Ok(AccountState::OpenbookMarket(Box::new(market)))
+        } else {
+            Err(anyhow!("unsupported account owner {}", owner))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountSnapshot {
+    /// Slot of the update this state was decoded from
+    pub slot: u64,
+    pub state: AccountState,
+}
+
+/// Latest decoded state of pools, vaults, markets and bonding curves, keyed by pubkey.
+#[derive(Debug, Default)]
+pub struct StateStore {
+    accounts: HashMap<Pubkey, AccountSnapshot>,
+}
+
+impl StateStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `state` unless a newer slot is already stored, returns whether it was stored.
+    pub fn insert(&mut self, pubkey: Pubkey, slot: u64, state: AccountState) -> bool {
+        // 多个端点或重连时可能收到旧的更新
+        if self
+            .accounts
+            .get(&pubkey)
+            .is_some_and(|snapshot| snapshot.slot > slot)
+        {
+            return false;
+        }
+        self.accounts
+            .insert(pubkey, AccountSnapshot { slot, state });
+        true
+    }
+
+    /// Decodes and stores an account update.
+    pub fn apply(&mut self, update: &AccountUpdate) -> Result<bool> {
+        let state = AccountState::decode(&update.owner, &update.data)?;
+        Ok(self.insert(update.pubkey, update.slot, state))
+    }
+
+    pub fn get(&self, pubkey: &Pubkey) -> Option<&AccountSnapshot> {
+        self.accounts.get(pubkey)
+    }
+
+    pub fn amm(&self, pubkey: &Pubkey) -> Option<&AmmInfo> {
+        match &self.get(pubkey)?.state {
+            AccountState::RaydiumAmm(amm) => Some(amm),
+            _ => None,
+        }
+    }
+
+    pub fn token_account(&self, pubkey: &Pubkey) -> Option<&TokenAccountState> {
+        match &self.get(pubkey)?.state {
+            AccountState::TokenAccount(account) => Some(account),
+            _ => None,
+        }
+    }
+
+    pub fn bonding_curve(&self, pubkey: &Pubkey) -> Option<&BondingCurve> {
+        match &self.get(pubkey)?.state {
+            AccountState::PumpfunBondingCurve(curve) => Some(curve),
+            _ => None,
+        }
+    }
+
+    pub fn market(&self, pubkey: &Pubkey) -> Option<&MarketState> {
+        match &self.get(pubkey)?.state {
+            AccountState::OpenbookMarket(market) => Some(market),
+            _ => None,
+        }
+    }
+
+    /// Market accounts a swap on the Raydium AMM `amm` passes, once its market has been seen.
+    pub fn amm_market_accounts(&self, amm: &Pubkey) -> Option<MarketAccounts> {
+        let info = self.amm(amm)?;
+        let market = self.market(&info.market)?;
+        MarketAccounts::from_market_state(&info.market_program, &info.market, market).ok()
+    }
+
+    /// Coin and pc vault balances of a Raydium AMM, once both vaults have been seen.
+    ///
+    /// These are the raw vault balances, the pnl the pool still owes is not deducted.
+    pub fn amm_reserves(&self, amm: &Pubkey) -> Option<(u64, u64)> {
+        let amm = self.amm(amm)?;
+        let coin = self.token_account(&amm.coin_vault)?;
+        let pc = self.token_account(&amm.pc_vault)?;
+        Some((coin.amount, pc.amount))
+    }
+
+    /// Quotes swapping `amount_in` of `mint_in` on a Raydium AMM at its latest reserves,
+    /// see `AmmInfo::quote_swap_base_in`.
+    pub fn quote_amm_swap_base_in(
+        &self,
+        amm: &Pubkey,
+        mint_in: &Pubkey,
+        amount_in: u64,
+    ) -> Option<u64> {
+        let (coin, pc) = self.amm_reserves(amm)?;
+        let info = self.amm(amm)?;
+        let (reserve_in, reserve_out) = if *mint_in == info.coin_vault_mint {
+            (coin, pc)
+        } else if *mint_in == info.pc_vault_mint {
+            (pc, coin)
+        } else {
+            return None;
+        };
+        info.quote_swap_base_in(amount_in, reserve_in, reserve_out)
+    }
+
+    /// Accounts to subscribe to besides the configured ones: the vaults and the market of
+    /// every stored AMM.
+    pub fn dependent_accounts(&self) -> Vec<Pubkey> {
+        self.accounts
+            .values()
+            .filter_map(|snapshot| match &snapshot.state {
+                AccountState::RaydiumAmm(amm) => Some([amm.coin_vault, amm.pc_vault, amm.market]),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+/// Configured accounts whose state is followed, see `constants::STATE_ACCOUNTS`.
+///
+/// Invalid pubkeys are logged and left out.
+pub fn state_accounts() -> Vec<String> {
+    constants::STATE_ACCOUNTS
+        .iter()
+        .filter(|account| match Pubkey::from_str(account) {
+            Ok(_) => true,
+            Err(err) => {
+                warn!(
+                    "ignoring invalid STATE_ACCOUNTS entry {:?}: {}",
+                    account, err
+                );
+                false
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use borsh::BorshSerialize;
+
+    use crate::openbook;
+    use crate::raydium::AMM_INFO_LEN;
+
+    fn token_account(mint: Pubkey, amount: u64) -> Vec<u8> {
+        let mut data = vec![0u8; 165];
+        data[..32].copy_from_slice(mint.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_state_store() {
+        let raydium = constants::RAYDIUM_AAM_PROGRAM;
+        let token_program = constants::TOKEN_PROGRAM;
+        let (amm, coin_vault, pc_vault) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        let mut data = vec![0u8; AMM_INFO_LEN];
+        let mut amm_info = AmmInfo::try_from_account_data(&data).unwrap();
+        amm_info.coin_vault = coin_vault;
+        amm_info.pc_vault = pc_vault;
+        let (coin_mint, pc_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        amm_info.coin_vault_mint = coin_mint;
+        amm_info.pc_vault_mint = pc_mint;
+        amm_info.fees.swap_fee_numerator = 25;
+        amm_info.fees.swap_fee_denominator = 10_000;
+        let market = Pubkey::new_unique();
+        amm_info.market = market;
+        amm_info.market_program = constants::OPENBOOK_PROGRAM;
+        data.clear();
+        amm_info.serialize(&mut data).unwrap();
+
+        let mut store = StateStore::new();
+        let update = |pubkey, owner, slot, data| AccountUpdate {
+            pubkey,
+            owner,
+            slot,
+            data,
+        };
+        assert!(store.apply(&update(amm, raydium, 5, data)).unwrap());
+        assert_eq!(
+            store.dependent_accounts(),
+            vec![coin_vault, pc_vault, market]
+        );
+        assert_eq!(store.amm_reserves(&amm), None);
+
+        let mint = Pubkey::new_unique();
+        let vault =
+            |slot, amount| update(coin_vault, token_program, slot, token_account(mint, amount));
+        store.apply(&vault(6, 1_000)).unwrap();
+        store
+            .apply(&update(
+                pc_vault,
+                token_program,
+                6,
+                token_account(mint, 2_000),
+            ))
+            .unwrap();
+        assert_eq!(store.amm_reserves(&amm), Some((1_000, 2_000)));
+
+        // 旧 slot 的更新被忽略
+        assert!(!store.apply(&vault(5, 10)).unwrap());
+        assert!(store.apply(&vault(7, 1_500)).unwrap());
+        assert_eq!(store.amm_reserves(&amm), Some((1_500, 2_000)));
+
+        // 手续费 1，2000 * 99 / (1500 + 99)
+        assert_eq!(
+            store.quote_amm_swap_base_in(&amm, &coin_mint, 100),
+            Some(123)
+        );
+        // 1500 * 99 / (2000 + 99)
+        assert_eq!(store.quote_amm_swap_base_in(&amm, &pc_mint, 100), Some(70));
+        assert_eq!(store.quote_amm_swap_base_in(&amm, &mint, 100), None);
+
+        assert_eq!(store.amm_market_accounts(&amm), None);
+        let openbook = constants::OPENBOOK_PROGRAM;
+        let nonce = (0u64..)
+            .find(|n| openbook::vault_signer(&market, *n, &openbook).is_ok())
+            .unwrap();
+        let market_state = openbook::market_state(market, nonce);
+        let mut data = b"serum".to_vec();
+        market_state.serialize(&mut data).unwrap();
+        data.extend(b"padding");
+        store.apply(&update(market, openbook, 8, data)).unwrap();
+        let accounts = store.amm_market_accounts(&amm).unwrap();
+        assert_eq!(
+            (accounts.market, accounts.bids),
+            (market, market_state.bids)
+        );
+
+        let curve = BondingCurve {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+        };
+        let mut data = vec![23, 183, 248, 55, 96, 216, 172, 96];
+        curve.serialize(&mut data).unwrap();
+        // 新版本账户末尾的 creator
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        let pumpfun = constants::PUMP_FUN_PROGRAM;
+        let curve_address = BondingCurve::address(&mint);
+        store
+            .apply(&update(curve_address, pumpfun, 8, data))
+            .unwrap();
+        assert_eq!(store.bonding_curve(&curve_address), Some(&curve));
+
+        assert!(
+            store
+                .apply(&update(amm, Pubkey::new_unique(), 9, vec![]))
+                .is_err()
+        );
+    }
+}
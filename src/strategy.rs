@@ -0,0 +1,266 @@
+use anyhow::{Result, anyhow};
+use log::debug;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+use crate::constants;
+use crate::decoder::Venue;
+use crate::events::Event;
+use crate::source::ObservedTransaction;
+use crate::state::StateStore;
+
+/// What a strategy can look at besides the event itself.
+pub struct Context<'a> {
+    /// Transaction the event was decoded from, `None` for slot and commitment events
+    pub observed: Option<&'a ObservedTransaction>,
+    /// Latest state of pools, vaults and bonding curves
+    pub state: &'a StateStore,
+}
+
+/// What a strategy wants the engine to do, turned into a bundle by the engine.
+///
+/// Swaps are built from the state store for Raydium AMM pools only, the
+/// bundle of a swap on another venue fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Buy `mint` in `pool` with `amount_in` of the pool's other token, accepting
+    /// up to `slippage_bps` less than quoted at the pool's latest state, tipping
+    /// `tip` lamports. Paid from the sender's associated token account, which
+    /// must hold `amount_in`, WSOL included
+    Buy {
+        venue: Venue,
+        pool: Pubkey,
+        mint: Pubkey,
+        amount_in: u64,
+        slippage_bps: u16,
+        tip: u64,
+    },
+    /// Sell `amount_in` of `mint` in `pool`, as `Buy`
+    Sell {
+        venue: Venue,
+        pool: Pubkey,
+        mint: Pubkey,
+        amount_in: u64,
+        slippage_bps: u16,
+        tip: u64,
+    },
+    /// Land right behind the observed transaction `target`, tipping `tip` lamports
+    Backrun {
+        target: Signature,
+        tip: u64,
+    },
+    Ignore,
+}
+
+/// Decision logic run by the engine on every transaction it decodes.
+///
+/// Strategies see `Event::Transaction` for every transaction, failed ones
+/// included, then `Event::Swap` for its swaps, with `error` set when the
+/// transaction failed, and `Event::PoolCreated` for successful ones.
+/// They also see `Event::Slot` and the `Event::Commitment` of every swap the
+/// engine followed, so a decision based on a swap that was dropped can be
+/// unwound.
+pub trait Strategy: Send {
+    fn name(&self) -> &'static str;
+
+    fn on_event(&mut self, ctx: &Context, event: &Event) -> Vec<Action>;
+}
+
+/// Strategies run side by side, each seeing every event.
+#[derive(Default)]
+pub struct StrategyRegistry {
+    strategies: Vec<Box<dyn Strategy>>,
+}
+
+impl StrategyRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds the built-in strategies named in `names`, see `constants::STRATEGIES`.
+    pub fn from_names(names: &[String]) -> Result<Self> {
+        let mut registry = Self::new();
+        for name in names {
+            let strategy: Box<dyn Strategy> = match name.as_str() {
+                "raydium_backrun" => {
+                    Box::new(RaydiumBackrun::new(*constants::BACKRUN_TIP_LAMPORTS))
+                }
+                _ => return Err(anyhow!("unknown strategy: {}", name)),
+            };
+            registry.register(strategy);
+        }
+        Ok(registry)
+    }
+
+    pub fn register(&mut self, strategy: Box<dyn Strategy>) {
+        self.strategies.push(strategy);
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.strategies.iter().map(|s| s.name()).collect()
+    }
+
+    /// Runs every strategy on `event`, returning the actions with the name of
+    /// the strategy that took them. `Action::Ignore` is left out.
+    pub fn on_event(&mut self, ctx: &Context, event: &Event) -> Vec<(&'static str, Action)> {
+        self.strategies
+            .iter_mut()
+            .flat_map(|strategy| {
+                let name = strategy.name();
+                strategy
+                    .on_event(ctx, event)
+                    .into_iter()
+                    .filter(|action| *action != Action::Ignore)
+                    .map(move |action| (name, action))
+            })
+            .collect()
+    }
+}
+
+/// Backruns every transaction swapping on a Raydium AMM pool.
+pub struct RaydiumBackrun {
+    tip: u64,
+    // 同一笔交易中的多个 swap 只跟一次
+    last_target: Option<Signature>,
+}
+
+impl RaydiumBackrun {
+    pub fn new(tip: u64) -> Self {
+        RaydiumBackrun {
+            tip,
+            last_target: None,
+        }
+    }
+}
+
+impl Strategy for RaydiumBackrun {
+    fn name(&self) -> &'static str {
+        "raydium_backrun"
+    }
+
+    fn on_event(&mut self, ctx: &Context, event: &Event) -> Vec<Action> {
+        let Event::Swap(swap) = event else {
+            return Vec::new();
+        };
+        // TODO 分析下单详情，考虑滑点，决定是否跟单
+        // 失败的交易不跟单
+        if swap.venue != Venue::RaydiumAmm
+            || swap.error.is_some()
+            || self.last_target == Some(swap.signature)
+        {
+            return Vec::new();
+        }
+        self.last_target = Some(swap.signature);
+        // 按池子最新的储备估算这笔交易应得的数量，与实际成交量比较得到滑点
+        if let Some(quoted) =
+            ctx.state
+                .quote_amm_swap_base_in(&swap.pool, &swap.mint_in, swap.amount_in)
+        {
+            debug!(
+                "{} on {}: quoted {}, received {}",
+                swap.signature, swap.pool, quoted, swap.amount_out
+            );
+        }
+        vec![Action::Backrun {
+            target: swap.signature,
+            tip: self.tip,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use solana_sdk::transaction::VersionedTransaction;
+
+    use crate::decoder::SwapEvent;
+
+    fn swap(venue: Venue, signature: Signature) -> Event {
+        Event::Swap(SwapEvent {
+            venue,
+            pool: Pubkey::new_unique(),
+            trader: Pubkey::new_unique(),
+            mint_in: Pubkey::new_unique(),
+            mint_out: Pubkey::new_unique(),
+            amount_in: 1,
+            amount_out: 1,
+            reserves_after: None,
+            slot: 1,
+            signature,
+            instruction_path: None,
+            error: None,
+        })
+    }
+
+    // 跟所有 swap，用来验证多个策略同时运行
+    struct BackrunEverything;
+
+    impl Strategy for BackrunEverything {
+        fn name(&self) -> &'static str {
+            "backrun_everything"
+        }
+
+        fn on_event(&mut self, _ctx: &Context, event: &Event) -> Vec<Action> {
+            match event {
+                Event::Swap(swap) => vec![Action::Backrun {
+                    target: swap.signature,
+                    tip: 1,
+                }],
+                _ => vec![Action::Ignore],
+            }
+        }
+    }
+
+    #[test]
+    fn test_strategy_registry() {
+        let mut registry = StrategyRegistry::from_names(&["raydium_backrun".to_string()]).unwrap();
+        registry.register(Box::new(BackrunEverything));
+        assert_eq!(
+            registry.names(),
+            vec!["raydium_backrun", "backrun_everything"]
+        );
+        assert!(StrategyRegistry::from_names(&["unknown".to_string()]).is_err());
+
+        let observed = ObservedTransaction {
+            slot: 1,
+            signature: Signature::default(),
+            transaction: VersionedTransaction::default(),
+            meta: None,
+        };
+        let state = StateStore::new();
+        let ctx = Context {
+            observed: Some(&observed),
+            state: &state,
+        };
+
+        let signature = Signature::from([1; 64]);
+        let actions = registry.on_event(&ctx, &swap(Venue::RaydiumAmm, signature));
+        assert_eq!(actions.len(), 2);
+        assert_eq!(
+            actions[0],
+            (
+                "raydium_backrun",
+                Action::Backrun {
+                    target: signature,
+                    tip: *constants::BACKRUN_TIP_LAMPORTS,
+                }
+            )
+        );
+        assert_eq!(actions[1].0, "backrun_everything");
+
+        // 同一笔交易的第二个 swap 不再跟单，其他池子的 swap 不跟单
+        let actions = registry.on_event(&ctx, &swap(Venue::RaydiumAmm, signature));
+        assert_eq!(actions.len(), 1);
+        let actions = registry.on_event(&ctx, &swap(Venue::Pumpfun, Signature::from([2; 64])));
+        assert_eq!(actions.len(), 1);
+
+        // Ignore 不返回
+        let slot = Event::Slot(crate::commitment::SlotUpdate {
+            slot: 1,
+            parent: None,
+            status: crate::commitment::SlotStatus::Processed,
+        });
+        assert!(registry.on_event(&ctx, &slot).is_empty());
+    }
+}
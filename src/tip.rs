@@ -0,0 +1,277 @@
+use std::collections::{HashMap, HashSet};
+
+use solana_program::system_instruction::SystemInstruction;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::constants;
+use crate::system::ResolvedSystemInstruction;
+use crate::transaction::DecodedTransaction;
+
+/// Jito tip paid by a transaction: a System transfer to one of the tip accounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JitoTip {
+    /// Tip account of the first tip transfer
+    pub account: Pubkey,
+    /// Total lamports sent to tip accounts, summed if the transaction tips more than once
+    pub lamports: u64,
+}
+
+const TIP_ACCOUNTS: [Pubkey; 8] = {
+    let mut accounts = [Pubkey::new_from_array([0; 32]); 8];
+    let mut i = 0;
+    while i < accounts.len() {
+        accounts[i] = Pubkey::from_str_const(constants::JITO_TIP_ACCOUNTS[i]);
+        i += 1;
+    }
+    accounts
+};
+
+// 每个统计表最多保留的池子或钱包数
+const DEFAULT_TIP_STATS_CAPACITY: usize = 10_000;
+
+pub fn is_tip_account(account: &Pubkey) -> bool {
+    TIP_ACCOUNTS.contains(account)
+}
+
+/// Finds the System transfers to a Jito tip account, inner ones included.
+pub fn detect_tip(system_instructions: &[ResolvedSystemInstruction]) -> Option<JitoTip> {
+    system_instructions
+        .iter()
+        .filter_map(|ix| match ix.instruction {
+            SystemInstruction::Transfer { lamports } => {
+                let to = ix.accounts.to?;
+                is_tip_account(&to).then_some((to, lamports))
+            }
+            _ => None,
+        })
+        .fold(None, |tip, (account, lamports)| match tip {
+            None => Some(JitoTip { account, lamports }),
+            Some(tip) => Some(JitoTip {
+                lamports: tip.lamports.saturating_add(lamports),
+                ..tip
+            }),
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TipSummary {
+    /// Transactions that paid a tip
+    pub tipped: u64,
+    pub total_lamports: u64,
+    pub max_lamports: u64,
+    /// Slot of the latest tip recorded
+    pub last_slot: u64,
+}
+
+impl TipSummary {
+    fn record(&mut self, tip: &JitoTip, slot: u64) {
+        self.tipped += 1;
+        self.total_lamports = self.total_lamports.saturating_add(tip.lamports);
+        self.max_lamports = self.max_lamports.max(tip.lamports);
+        self.last_slot = self.last_slot.max(slot);
+    }
+
+    /// Average tip over the transactions that paid one.
+    pub fn average_lamports(&self) -> Option<u64> {
+        (self.tipped > 0).then(|| self.total_lamports / self.tipped)
+    }
+}
+
+/// Tips aggregated per pool and per signer, to model what competitors bid
+/// for the same opportunities.
+///
+/// Only transactions that paid a tip are recorded. Each map keeps at most
+/// `capacity` entries, evicting the ones tipped least recently.
+#[derive(Debug, Clone)]
+pub struct TipStats {
+    pub by_pool: HashMap<Pubkey, TipSummary>,
+    pub by_signer: HashMap<Pubkey, TipSummary>,
+    capacity: usize,
+}
+
+impl Default for TipStats {
+    fn default() -> Self {
+        Self::new(DEFAULT_TIP_STATS_CAPACITY)
+    }
+}
+
+impl TipStats {
+    pub fn new(capacity: usize) -> Self {
+        TipStats {
+            by_pool: HashMap::new(),
+            by_signer: HashMap::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn record(&mut self, tx: &DecodedTransaction) {
+        let Some(tip) = tx.jito_tip.as_ref() else {
+            return;
+        };
+        if let Some(signer) = tx.signer {
+            self.by_signer
+                .entry(signer)
+                .or_default()
+                .record(tip, tx.slot);
+            evict(&mut self.by_signer, self.capacity);
+        }
+        // 同一笔交易多次经过同一个池子只计一次
+        let pools: HashSet<Pubkey> = tx.swaps.iter().map(|swap| swap.pool).collect();
+        for pool in pools {
+            self.by_pool.entry(pool).or_default().record(tip, tx.slot);
+        }
+        evict(&mut self.by_pool, self.capacity);
+    }
+
+    /// The `n` pools with the most lamports tipped.
+    pub fn top_pools(&self, n: usize) -> Vec<(Pubkey, TipSummary)> {
+        top(&self.by_pool, n)
+    }
+
+    /// The `n` signers with the most lamports tipped.
+    pub fn top_signers(&self, n: usize) -> Vec<(Pubkey, TipSummary)> {
+        top(&self.by_signer, n)
+    }
+}
+
+// 超出容量时一次淘汰到容量的 3/4，摊薄选择的开销
+fn evict(summaries: &mut HashMap<Pubkey, TipSummary>, capacity: usize) {
+    if summaries.len() <= capacity {
+        return;
+    }
+    let mut by_slot: Vec<_> = summaries
+        .iter()
+        .map(|(key, summary)| (summary.last_slot, *key))
+        .collect();
+    let evicted = by_slot.len() - capacity * 3 / 4;
+    by_slot.select_nth_unstable(evicted - 1);
+    for (_, key) in &by_slot[..evicted] {
+        summaries.remove(key);
+    }
+}
+
+fn top(summaries: &HashMap<Pubkey, TipSummary>, n: usize) -> Vec<(Pubkey, TipSummary)> {
+    let mut top: Vec<_> = summaries
+        .iter()
+        .map(|(key, summary)| (*key, *summary))
+        .collect();
+    top.sort_by_key(|(_, summary)| std::cmp::Reverse(summary.total_lamports));
+    top.truncate(n);
+    top
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use solana_sdk::signature::Signature;
+
+    use crate::compute_budget::ComputeBudget;
+    use crate::decoder::{InstructionPath, SwapEvent, Venue};
+    use crate::system::SystemAccounts;
+
+    fn transfer(to: Pubkey, lamports: u64) -> ResolvedSystemInstruction {
+        ResolvedSystemInstruction {
+            instruction: SystemInstruction::Transfer { lamports },
+            accounts: SystemAccounts {
+                from: Some(Pubkey::new_unique()),
+                to: Some(to),
+                ..Default::default()
+            },
+            instruction_path: InstructionPath {
+                outer: 0,
+                inner: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_tip_stats() {
+        let tip_account = TIP_ACCOUNTS[3];
+        assert!(is_tip_account(&tip_account));
+        let system_instructions = vec![
+            transfer(Pubkey::new_unique(), 5_000_000),
+            transfer(tip_account, 10_000),
+            transfer(tip_account, 2_000),
+        ];
+        let tip = detect_tip(&system_instructions).unwrap();
+        assert_eq!(tip.account, tip_account);
+        assert_eq!(tip.lamports, 12_000);
+        assert_eq!(detect_tip(&system_instructions[..1]), None);
+
+        let (pool, signer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let swap = SwapEvent {
+            venue: Venue::RaydiumAmm,
+            pool,
+            trader: signer,
+            mint_in: Pubkey::new_unique(),
+            mint_out: Pubkey::new_unique(),
+            amount_in: 1,
+            amount_out: 1,
+            reserves_after: None,
+            slot: 1,
+            signature: Signature::default(),
+            instruction_path: None,
+            error: None,
+        };
+        let tx = |jito_tip| DecodedTransaction {
+            slot: 1,
+            signature: Signature::default(),
+            signer: Some(signer),
+            writable_accounts: vec![pool],
+            swaps: vec![swap.clone(), swap.clone()],
+            jupiter_routes: vec![],
+            pools_created: vec![],
+            idl_instructions: vec![],
+            token_instructions: vec![],
+            system_instructions: vec![],
+            compute_budget: ComputeBudget::default(),
+            jito_tip,
+            error: None,
+        };
+
+        let mut stats = TipStats::default();
+        stats.record(&tx(Some(tip)));
+        stats.record(&tx(None));
+        // 没有小费的交易不记录
+        let summary = stats.by_pool[&pool];
+        assert_eq!(summary.tipped, 1);
+        assert_eq!(summary.max_lamports, 12_000);
+        assert_eq!(summary.average_lamports(), Some(12_000));
+        assert_eq!(stats.by_signer[&signer], summary);
+
+        // 按小费总额排序
+        let mut bigger = tx(Some(JitoTip {
+            account: tip_account,
+            lamports: 50_000,
+        }));
+        let other_pool = Pubkey::new_unique();
+        bigger.swaps[0].pool = other_pool;
+        bigger.swaps.truncate(1);
+        stats.record(&bigger);
+        let mut untipped = tx(None);
+        untipped.swaps[0].pool = Pubkey::new_unique();
+        stats.record(&untipped);
+        let top = stats.top_pools(5);
+        assert_eq!(top.len(), 2);
+        assert_eq!((top[0].0, top[0].1.total_lamports), (other_pool, 50_000));
+        assert_eq!(top[1].0, pool);
+        assert_eq!(stats.top_signers(1)[0].1.total_lamports, 62_000);
+        // 超出容量时淘汰最久没有付小费的池子
+        let mut stats = TipStats::new(4);
+        let pools: Vec<_> = (1..=5)
+            .map(|slot| {
+                let mut tipped = tx(Some(tip));
+                tipped.slot = slot;
+                tipped.swaps.truncate(1);
+                tipped.swaps[0].pool = Pubkey::new_unique();
+                stats.record(&tipped);
+                tipped.swaps[0].pool
+            })
+            .collect();
+        assert_eq!(stats.by_pool.len(), 3);
+        assert!(!stats.by_pool.contains_key(&pools[1]));
+        assert!(stats.by_pool.contains_key(&pools[2]));
+        assert_eq!(stats.by_signer[&signer].last_slot, 5);
+    }
+}
@@ -0,0 +1,349 @@
+use std::str::FromStr;
+
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+
+use crate::compute_budget::ComputeBudget;
+use crate::constants;
+use crate::decoder::{DexDecoder, PoolCreated, SwapEvent, TransactionContext};
+use crate::idl::{IdlDecodedInstruction, IdlRegistry};
+use crate::jupiter::{self, JupiterRoute};
+use crate::pumpfun::PumpfunError;
+use crate::raydium::RaydiumAmmError;
+use crate::spl_token::{self, ResolvedTokenInstruction};
+use crate::system::{self, ResolvedSystemInstruction};
+use crate::tip::{self, JitoTip};
+
+/// Everything the bot decodes from one transaction, handed to strategies and analytics.
+#[derive(Debug, Clone)]
+pub struct DecodedTransaction {
+    pub slot: u64,
+    pub signature: Signature,
+    /// Fee payer, the first account key
+    pub signer: Option<Pubkey>,
+    /// Accounts write-locked by the transaction
+    pub writable_accounts: Vec<Pubkey>,
+    /// Swaps of failed transactions are included, decoded from their args with `error` set
+    pub swaps: Vec<SwapEvent>,
+    /// Jupiter routes with the user's real input, output and slippage limit, their legs
+    /// linked to the DEX instructions behind `swaps`
+    pub jupiter_routes: Vec<JupiterRoute>,
+    /// Raydium AMM pools and pump.fun bonding curves created by the transaction
+    pub pools_created: Vec<PoolCreated>,
+    /// Instructions of programs without a `DexDecoder`, decoded through their IDL
+    pub idl_instructions: Vec<IdlDecodedInstruction>,
+    /// Token / Token-2022 instructions, inner ones included
+    pub token_instructions: Vec<ResolvedTokenInstruction>,
+    /// System program transfers, account creations and nonce instructions
+    pub system_instructions: Vec<ResolvedSystemInstruction>,
+    /// Requested compute budget, consumed units and priority fee
+    pub compute_budget: ComputeBudget,
+    /// Transfer to a Jito tip account, if any
+    pub jito_tip: Option<JitoTip>,
+    /// Why the transaction failed, `None` if it succeeded
+    pub error: Option<TransactionFailure>,
+}
+
+impl DecodedTransaction {
+    pub fn decode(
+        ctx: &TransactionContext,
+        decoders: &[Box<dyn DexDecoder>],
+        idls: &IdlRegistry,
+    ) -> Self {
+        let system_instructions = system::decode_system_instructions(ctx);
+        let error = TransactionFailure::from_context(ctx);
+        let known: Vec<Pubkey> = decoders.iter().map(|d| d.program_id()).collect();
+        let mut swaps = ctx.decode_swaps(decoders);
+        for swap in &mut swaps {
+            swap.error = error.clone();
+        }
+        DecodedTransaction {
+            slot: ctx.slot,
+            signature: ctx.signature,
+            signer: ctx.account_keys.first().copied(),
+            writable_accounts: ctx.writable_accounts().collect(),
+            swaps,
+            jupiter_routes: jupiter::decode_routes(ctx),
+            pools_created: ctx.decode_pool_creations(decoders),
+            idl_instructions: idls.decode_transaction(ctx, &known),
+            token_instructions: spl_token::decode_token_instructions(ctx),
+            jito_tip: tip::detect_tip(&system_instructions),
+            system_instructions,
+            compute_budget: ComputeBudget::from_context(ctx),
+            error,
+        }
+    }
+}
+
+/// Custom program error mapped to the program's error table where we know it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgramFailure {
+    RaydiumAmm(RaydiumAmmError),
+    Pumpfun(PumpfunError),
+    Custom(u32),
+}
+
+/// A failed transaction's error, classified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionFailure {
+    pub error: TransactionError,
+    /// Top level instruction that failed, for instruction errors
+    pub instruction_index: Option<u8>,
+    /// Program that raised the error, which may be a CPI callee of the failed instruction
+    pub program_id: Option<Pubkey>,
+    /// Set for `InstructionError::Custom`
+    pub program_error: Option<ProgramFailure>,
+}
+
+impl TransactionFailure {
+    pub fn from_context(ctx: &TransactionContext) -> Option<Self> {
+        let error = ctx.error.clone()?;
+        let (instruction_index, code) = match &error {
+            TransactionError::InstructionError(index, InstructionError::Custom(code)) => {
+                (Some(*index), Some(*code))
+            }
+            TransactionError::InstructionError(index, _) => (Some(*index), None),
+            _ => (None, None),
+        };
+        let program_id = failed_program(&ctx.log_messages).or_else(|| {
+            let ix = ctx.instructions.get(instruction_index? as usize)?;
+            ctx.program_id(ix)
+        });
+        let program_error = code.map(|code| classify(program_id, code));
+
+        Some(TransactionFailure {
+            error,
+            instruction_index,
+            program_id,
+            program_error,
+        })
+    }
+}
+
+// 第一条 "Program <id> failed: ..." 日志来自最内层真正报错的程序，外层调用者随后依次打印同样的日志
+fn failed_program(log_messages: &[String]) -> Option<Pubkey> {
+    log_messages.iter().find_map(|log| {
+        let (program, _) = log.strip_prefix("Program ")?.split_once(" failed: ")?;
+        Pubkey::from_str(program).ok()
+    })
+}
+
+fn classify(program_id: Option<Pubkey>, code: u32) -> ProgramFailure {
+    let error = match program_id {
+        Some(id) if id == constants::RAYDIUM_AAM_PROGRAM => {
+            RaydiumAmmError::from_code(code).map(ProgramFailure::RaydiumAmm)
+        }
+        Some(id) if id == constants::PUMP_FUN_PROGRAM => {
+            PumpfunError::from_code(code).map(ProgramFailure::Pumpfun)
+        }
+        _ => None,
+    };
+    error.unwrap_or(ProgramFailure::Custom(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn context(
+        account_keys: Vec<Pubkey>,
+        log_messages: Vec<String>,
+        error: TransactionError,
+    ) -> TransactionContext {
+        TransactionContext {
+            slot: 1,
+            signature: Signature::default(),
+            account_keys,
+            instructions: vec![solana_transaction_status::UiCompiledInstruction {
+                program_id_index: 1,
+                accounts: vec![],
+                data: String::new(),
+                stack_height: None,
+            }],
+            inner_instructions: Some(vec![vec![]]),
+            pre_token_balances: HashMap::new(),
+            post_token_balances: HashMap::new(),
+            log_messages,
+            compute_units_consumed: None,
+            error: Some(error),
+            writable: vec![],
+        }
+    }
+
+    #[test]
+    fn test_transaction_failure() {
+        let raydium = constants::RAYDIUM_AAM_PROGRAM;
+        let pumpfun = constants::PUMP_FUN_PROGRAM;
+        let router = Pubkey::new_unique();
+
+        // 通过路由合约 CPI 调用 raydium，以日志中最内层的程序为准
+        let ctx = context(
+            vec![Pubkey::new_unique(), router, raydium],
+            vec![
+                format!("Program {} invoke [1]", router),
+                format!("Program {} invoke [2]", raydium),
+                format!("Program {} failed: custom program error: 0x1e", raydium),
+                format!("Program {} failed: custom program error: 0x1e", router),
+            ],
+            TransactionError::InstructionError(0, InstructionError::Custom(0x1e)),
+        );
+        let failure = TransactionFailure::from_context(&ctx).unwrap();
+        assert_eq!(failure.instruction_index, Some(0));
+        assert_eq!(failure.program_id, Some(raydium));
+        assert_eq!(
+            failure.program_error,
+            Some(ProgramFailure::RaydiumAmm(
+                RaydiumAmmError::ExceededSlippage
+            ))
+        );
+
+        // 没有日志时退回到失败的顶层指令
+        let ctx = context(
+            vec![Pubkey::new_unique(), pumpfun],
+            vec![],
+            TransactionError::InstructionError(0, InstructionError::Custom(6002)),
+        );
+        let failure = TransactionFailure::from_context(&ctx).unwrap();
+        assert_eq!(failure.program_id, Some(pumpfun));
+        assert_eq!(
+            failure.program_error,
+            Some(ProgramFailure::Pumpfun(PumpfunError::TooMuchSolRequired))
+        );
+
+        let ctx = context(
+            vec![Pubkey::new_unique(), router],
+            vec![],
+            TransactionError::InstructionError(0, InstructionError::Custom(6002)),
+        );
+        let failure = TransactionFailure::from_context(&ctx).unwrap();
+        assert_eq!(failure.program_error, Some(ProgramFailure::Custom(6002)));
+
+        let ctx = context(vec![], vec![], TransactionError::BlockhashNotFound);
+        let failure = TransactionFailure::from_context(&ctx).unwrap();
+        assert_eq!(failure.instruction_index, None);
+        assert_eq!(failure.program_error, None);
+    }
+
+    #[test]
+    fn test_failed_swap() {
+        use crate::decoder::TokenBalance;
+        use crate::raydium::RaydiumAmmDecoder;
+
+        let raydium = constants::RAYDIUM_AAM_PROGRAM;
+        let mut account_keys = vec![Pubkey::new_unique(), raydium];
+        account_keys.extend((0..17).map(|_| Pubkey::new_unique()));
+        let mut ctx = context(
+            account_keys.clone(),
+            vec![],
+            TransactionError::InstructionError(0, InstructionError::Custom(0x1e)),
+        );
+        let mut data = vec![9u8];
+        data.extend(1_000u64.to_le_bytes());
+        data.extend(900u64.to_le_bytes());
+        // 17 个池子账户之后是签名者
+        let mut accounts: Vec<u8> = (2..19).collect();
+        accounts.push(0);
+        ctx.instructions[0].accounts = accounts;
+        ctx.instructions[0].data = bs58::encode(data).into_string();
+        let (mint_in, mint_out) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let balance = |mint| TokenBalance {
+            mint,
+            owner: None,
+            amount: 0,
+        };
+        // 失败交易的余额不变，只能用来解析 mint
+        ctx.pre_token_balances.insert(17, balance(mint_in));
+        ctx.pre_token_balances.insert(18, balance(mint_out));
+        ctx.post_token_balances = ctx.pre_token_balances.clone();
+
+        let decoders: Vec<Box<dyn DexDecoder>> = vec![Box::new(RaydiumAmmDecoder)];
+        let decoded = DecodedTransaction::decode(&ctx, &decoders, &IdlRegistry::default());
+        assert_eq!(decoded.swaps.len(), 1);
+        let swap = &decoded.swaps[0];
+        assert_eq!((swap.amount_in, swap.amount_out), (1_000, 900));
+        assert_eq!((swap.mint_in, swap.mint_out), (mint_in, mint_out));
+        assert_eq!(swap.trader, account_keys[0]);
+        assert_eq!(
+            swap.error.as_ref().unwrap().program_error,
+            Some(ProgramFailure::RaydiumAmm(
+                RaydiumAmmError::ExceededSlippage
+            ))
+        );
+    }
+
+    #[test]
+    fn test_pool_creations() {
+        use borsh::BorshSerialize;
+
+        use crate::decoder::Venue;
+        use crate::pumpfun::{CreateEvent, PumpfunDecoder};
+        use crate::raydium::{Initialize2Instruction, RaydiumAmmDecoder};
+
+        let raydium = constants::RAYDIUM_AAM_PROGRAM;
+        let pumpfun = constants::PUMP_FUN_PROGRAM;
+        let mut account_keys = vec![raydium, pumpfun];
+        account_keys.extend((0..18).map(|_| Pubkey::new_unique()));
+
+        let mut initialize = vec![1u8];
+        Initialize2Instruction {
+            nonce: 254,
+            open_time: 0,
+            init_pc_amount: 1_000,
+            init_coin_amount: 2_000,
+        }
+        .serialize(&mut initialize)
+        .unwrap();
+
+        let create = CreateEvent {
+            name: "name".to_string(),
+            symbol: "SYM".to_string(),
+            uri: String::new(),
+            mint: Pubkey::new_unique(),
+            bonding_curve: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+        };
+        // anchor event CPI: 8 字节 event 指令标识 + 8 字节事件 discriminator
+        let mut event = vec![228, 69, 165, 46, 81, 203, 154, 29];
+        event.extend([27, 114, 169, 77, 222, 235, 99, 118]);
+        create.serialize(&mut event).unwrap();
+
+        let ix = |program_id_index, data: &[u8]| solana_transaction_status::UiCompiledInstruction {
+            program_id_index,
+            accounts: (2..20).collect(),
+            data: bs58::encode(data).into_string(),
+            stack_height: None,
+        };
+        let ctx = TransactionContext {
+            slot: 1,
+            signature: Signature::default(),
+            account_keys: account_keys.clone(),
+            instructions: vec![ix(0, &initialize)],
+            inner_instructions: Some(vec![vec![ix(1, &event)]]),
+            pre_token_balances: HashMap::new(),
+            post_token_balances: HashMap::new(),
+            log_messages: vec![],
+            compute_units_consumed: None,
+            error: None,
+            writable: vec![],
+        };
+        let decoders: Vec<Box<dyn DexDecoder>> =
+            vec![Box::new(RaydiumAmmDecoder), Box::new(PumpfunDecoder)];
+        let pools = ctx.decode_pool_creations(&decoders);
+
+        assert_eq!(pools.len(), 2);
+        assert_eq!(pools[0].venue, Venue::RaydiumAmm);
+        assert_eq!(pools[0].pool, account_keys[6]);
+        assert_eq!(
+            (pools[0].base_mint, pools[0].quote_mint),
+            (account_keys[10], account_keys[11])
+        );
+        assert_eq!(pools[0].creator, account_keys[19]);
+        assert_eq!(pools[1].venue, Venue::Pumpfun);
+        assert_eq!(pools[1].pool, create.bonding_curve);
+        assert_eq!(pools[1].base_mint, create.mint);
+        assert_eq!(pools[1].creator, create.user);
+    }
+}
@@ -2,7 +2,13 @@ use anyhow::{Result, anyhow};
 use sha2::{Digest, Sha256};
 use solana_program::system_instruction;
 use solana_sdk::{
-    hash::Hash, pubkey::Pubkey, signature::Keypair, signer::Signer, transaction::Transaction,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    system_program,
+    transaction::Transaction,
 };
 use std::env;
 use std::path::PathBuf;
@@ -11,7 +17,13 @@ use crate::constants;
 
 /// 计算 anchor 指令 discriminator（前 8 字节的 sha256）
 pub fn calculate_discriminator(instruction_name: &str) -> [u8; 8] {
-    let full_name = format!("global:{}", instruction_name);
+    calculate_namespaced_discriminator("global", instruction_name)
+}
+
+/// 计算 anchor discriminator: sha256("<namespace>:<name>")[..8]，
+/// 指令为 "global"，账户为 "account"，事件为 "event"
+pub fn calculate_namespaced_discriminator(namespace: &str, name: &str) -> [u8; 8] {
+    let full_name = format!("{}:{}", namespace, name);
     let hash = Sha256::digest(full_name.as_bytes());
 
     let mut discriminator = [0u8; 8];
@@ -66,6 +78,36 @@ pub fn create_tip_tx(
     Ok(tx)
 }
 
+/// 钱包 `owner` 持有 `mint` 的关联账户（ATA）
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &constants::ASSOCIATED_TOKEN_PROGRAM,
+    )
+    .0
+}
+
+/// 创建 ATA 的指令（CreateIdempotent），账户已存在时不做任何事
+pub fn create_associated_token_account_idempotent(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        constants::ASSOCIATED_TOKEN_PROGRAM,
+        &[1],
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(owner, mint, token_program), false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
 #[cfg(test)]
 mod tests {
 